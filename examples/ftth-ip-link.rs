@@ -1,10 +1,11 @@
-use std::io::{self, ErrorKind};
+use std::io;
 use std::net::{Ipv4Addr, Ipv6Addr};
 
 use clap::{Args, Parser, Subcommand};
 use ftth_rtnl::{
-    Gre6Config, GreConfig, Ip6TnlConfig, IpIpConfig, RtnlClient, VirtualInterfaceDelete,
-    VirtualInterfaceKind, VirtualInterfaceSpec, VirtualInterfaceUpdate, VlanConfig, link::MacAddr,
+    Gre6Config, GreConfig, GreFlags, Ip6TnlConfig, Ip6TunnelFlags, IpIpConfig, RtnlClient,
+    VirtualInterfaceDelete, VirtualInterfaceKind, VirtualInterfaceSpec, VirtualInterfaceUpdate,
+    VlanConfig, link::MacAddr,
 };
 
 #[derive(Parser)]
@@ -43,7 +44,7 @@ struct VirtualInterfaceConfigureArgs {
     #[command(flatten)]
     target: VirtualInterfaceTarget,
     #[command(subcommand)]
-    kind: VirtualInterfaceCreateKind,
+    kind: Option<VirtualInterfaceCreateKind>,
     #[arg(long)]
     new_name: Option<String>,
     #[arg(long, value_parser = parse_bool_flag, action = clap::ArgAction::Set)]
@@ -81,6 +82,14 @@ struct GreArgs {
     #[arg(long)]
     key: Option<u32>,
     #[arg(long)]
+    ikey: Option<u32>,
+    #[arg(long)]
+    okey: Option<u32>,
+    #[arg(long)]
+    csum: bool,
+    #[arg(long)]
+    seq: bool,
+    #[arg(long)]
     encap_limit: Option<u8>,
     #[arg(long, value_parser = parse_bool_flag, default_value = "true", action = clap::ArgAction::Set)]
     pmtudisc: bool,
@@ -107,6 +116,14 @@ struct Gre6Args {
     #[arg(long)]
     key: Option<u32>,
     #[arg(long)]
+    ikey: Option<u32>,
+    #[arg(long)]
+    okey: Option<u32>,
+    #[arg(long)]
+    csum: bool,
+    #[arg(long)]
+    seq: bool,
+    #[arg(long)]
     encap_limit: Option<u8>,
     #[arg(long, value_parser = parse_bool_flag, default_value = "true", action = clap::ArgAction::Set)]
     pmtudisc: bool,
@@ -215,10 +232,15 @@ fn build_virtual_interface_kind(
                 ttl: args.ttl,
                 tos: args.tos,
                 key: args.key,
+                ikey: args.ikey,
+                okey: args.okey,
+                iflags: gre_flags(args.csum, args.seq),
+                oflags: gre_flags(args.csum, args.seq),
                 encap_limit: args.encap_limit,
                 pmtudisc: args.pmtudisc,
                 ignore_df: args.ignore_df,
                 link,
+                external: false,
             };
             Ok(VirtualInterfaceBuild {
                 name: args.name.clone(),
@@ -234,10 +256,15 @@ fn build_virtual_interface_kind(
                 ttl: args.ttl,
                 tos: args.tos,
                 key: args.key,
+                ikey: args.ikey,
+                okey: args.okey,
+                iflags: gre_flags(args.csum, args.seq),
+                oflags: gre_flags(args.csum, args.seq),
                 encap_limit: args.encap_limit,
                 pmtudisc: args.pmtudisc,
                 ignore_df: args.ignore_df,
                 link,
+                external: false,
             };
             Ok(VirtualInterfaceBuild {
                 name: args.name.clone(),
@@ -253,10 +280,16 @@ fn build_virtual_interface_kind(
                 hop_limit: args.hop_limit,
                 traffic_class: args.traffic_class,
                 key: args.key,
+                ikey: args.ikey,
+                okey: args.okey,
+                iflags: gre_flags(args.csum, args.seq),
+                oflags: gre_flags(args.csum, args.seq),
                 encap_limit: args.encap_limit,
                 pmtudisc: args.pmtudisc,
                 ignore_df: args.ignore_df,
                 link,
+                flags: Ip6TunnelFlags::default(),
+                external: false,
             };
             Ok(VirtualInterfaceBuild {
                 name: args.name.clone(),
@@ -272,10 +305,16 @@ fn build_virtual_interface_kind(
                 hop_limit: args.hop_limit,
                 traffic_class: args.traffic_class,
                 key: args.key,
+                ikey: args.ikey,
+                okey: args.okey,
+                iflags: gre_flags(args.csum, args.seq),
+                oflags: gre_flags(args.csum, args.seq),
                 encap_limit: args.encap_limit,
                 pmtudisc: args.pmtudisc,
                 ignore_df: args.ignore_df,
                 link,
+                flags: Ip6TunnelFlags::default(),
+                external: false,
             };
             Ok(VirtualInterfaceBuild {
                 name: args.name.clone(),
@@ -293,6 +332,7 @@ fn build_virtual_interface_kind(
                 encap_limit: args.encap_limit,
                 pmtudisc: args.pmtudisc,
                 link,
+                external: false,
             };
             Ok(VirtualInterfaceBuild {
                 name: args.name.clone(),
@@ -311,6 +351,9 @@ fn build_virtual_interface_kind(
                 encap_limit: args.encap_limit,
                 pmtudisc: args.pmtudisc,
                 link,
+                mode: None,
+                flags: Ip6TunnelFlags::default(),
+                external: false,
             };
             Ok(VirtualInterfaceBuild {
                 name: args.name.clone(),
@@ -541,7 +584,7 @@ fn run_get_mtu(client: &RtnlClient, interface: &str) -> io::Result<()> {
 }
 
 fn run_set_mac(client: &RtnlClient, interface: &str, mac: &str) -> io::Result<()> {
-    let mac_addr = parse_mac(mac)?;
+    let mac_addr: MacAddr = mac.parse()?;
     let link_client = client.link();
     let iface = link_client.interface_get_by_name(interface)?;
     link_client.mac_addr_set(iface.if_id, iface.link_layer_type, mac_addr)?;
@@ -574,17 +617,24 @@ fn run_virtual_interface(client: &RtnlClient, command: VirtualInterfaceCommand)
                 name: name.clone(),
                 admin_up: admin_up.unwrap_or(true),
                 kind,
+                num_tx_queues: None,
+                num_rx_queues: None,
+                mtu: None,
+                mac_addr: None,
+                master: None,
             };
-            vif_client.create(spec)?;
-            println!("Created virtual interface {}", name);
+            let index = vif_client.create(spec)?;
+            println!("Created virtual interface {} ({})", name, index);
         }
         VirtualInterfaceCommand::Configure(args) => {
             let index = args.target.resolve_index(client)?;
-            let VirtualInterfaceBuild {
-                name: _,
-                admin_up,
-                kind,
-            } = build_virtual_interface_kind(client, args.kind)?;
+            let (admin_up, kind) = match args.kind {
+                Some(kind) => {
+                    let build = build_virtual_interface_kind(client, kind)?;
+                    (build.admin_up, Some(build.kind))
+                }
+                None => (None, None),
+            };
             let update = VirtualInterfaceUpdate {
                 if_id: index,
                 new_name: args.new_name.clone(),
@@ -622,25 +672,12 @@ fn validate_virtual_interface_create(kind: &VirtualInterfaceKind) -> io::Result<
     }
 }
 
-fn parse_mac(s: &str) -> io::Result<MacAddr> {
-    let parts: Vec<&str> = s.split(':').collect();
-    if parts.len() != 6 {
-        return Err(io::Error::new(
-            ErrorKind::InvalidInput,
-            "Invalid MAC address",
-        ));
-    }
-
-    let mut bytes = [0u8; 6];
-    for (i, part) in parts.iter().enumerate() {
-        bytes[i] = u8::from_str_radix(part, 16).map_err(|err| {
-            io::Error::new(
-                ErrorKind::InvalidInput,
-                format!("Invalid MAC segment: {}", err),
-            )
-        })?;
+fn gre_flags(csum: bool, seq: bool) -> GreFlags {
+    GreFlags {
+        csum,
+        seq,
+        key: false,
     }
-    Ok(MacAddr::new(bytes))
 }
 
 fn parse_bool_flag(value: &str) -> Result<bool, String> {
@@ -3,7 +3,10 @@ use std::io::{self, ErrorKind};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
-use ftth_rtnl::{Ipv4Route, Ipv6Route, RouteNextHopFlags, RtnlClient};
+use ftth_rtnl::{
+    IpRoute, Ipv4Route, Ipv6Route, RouteGateway, RouteLookupOptions, RouteMetrics,
+    RouteNextHopFlags, RouteStatus, RouteType, RtnlClient,
+};
 use ipnet::IpNet;
 
 #[derive(Parser)]
@@ -29,13 +32,23 @@ enum Command {
     /// Delete an IPv6 route
     Del6(RouteV6DeleteArgs),
     /// Lookup the selected IPv4 route
-    Get4 { destination: Ipv4Addr },
+    Get4 {
+        destination: Ipv4Addr,
+        #[command(flatten)]
+        lookup: LookupArgs,
+    },
     /// Lookup the IPv4 route matching the given prefix
     Get4Prefix { prefix: String },
     /// Lookup the selected IPv6 route
-    Get6 { destination: Ipv6Addr },
+    Get6 {
+        destination: Ipv6Addr,
+        #[command(flatten)]
+        lookup: LookupArgs,
+    },
     /// Lookup the IPv6 route matching the given prefix
     Get6Prefix { prefix: String },
+    /// List every route covering the destination, most specific first
+    GetAll { destination: IpAddr },
 }
 
 #[derive(ValueEnum, Clone, Copy)]
@@ -60,9 +73,20 @@ struct RouteV4Args {
     /// Route metric
     #[arg(long)]
     metric: Option<u32>,
+    /// Type of service the route matches
+    #[arg(long, default_value_t = 0)]
+    tos: u8,
     /// Route table ID
     #[arg(long)]
     table: Option<u32>,
+    /// Use the gateway even if it is outside the interface's prefixes
+    #[arg(long)]
+    onlink: bool,
+    /// Locked path MTU
+    #[arg(long)]
+    mtu: Option<u32>,
+    #[command(flatten)]
+    metrics: MetricArgs,
     /// Replace an existing route instead of adding a new one
     #[arg(long)]
     replace: bool,
@@ -87,11 +111,76 @@ struct RouteV6Args {
     /// Route table ID
     #[arg(long)]
     table: Option<u32>,
+    /// Use the gateway even if it is outside the interface's prefixes
+    #[arg(long)]
+    onlink: bool,
+    /// Locked path MTU
+    #[arg(long)]
+    mtu: Option<u32>,
+    /// Lifetime in seconds after which the kernel removes the route
+    #[arg(long)]
+    expires: Option<u32>,
+    #[command(flatten)]
+    metrics: MetricArgs,
     /// Replace an existing route instead of adding a new one
     #[arg(long)]
     replace: bool,
 }
 
+#[derive(Args, Clone)]
+struct LookupArgs {
+    /// Source address of the packet
+    #[arg(long)]
+    from: Option<IpAddr>,
+    /// Interface the packet is received on
+    #[arg(long)]
+    iif: Option<String>,
+    /// Firewall mark of the packet
+    #[arg(long)]
+    mark: Option<u32>,
+    /// Type of service of the packet
+    #[arg(long, default_value_t = 0)]
+    tos: u8,
+    /// Report no route instead of a local or broadcast one
+    #[arg(long)]
+    skip_local: bool,
+    /// Show the matching FIB entry instead of the resolved route
+    #[arg(long)]
+    fibmatch: bool,
+}
+
+#[derive(Args, Clone)]
+struct MetricArgs {
+    /// Advertised TCP MSS
+    #[arg(long)]
+    advmss: Option<u32>,
+    /// Hop limit for packets sent along the route
+    #[arg(long)]
+    hoplimit: Option<u32>,
+    /// Initial TCP congestion window in segments
+    #[arg(long)]
+    initcwnd: Option<u32>,
+    /// Initial TCP receive window in segments
+    #[arg(long)]
+    initrwnd: Option<u32>,
+    /// Minimum TCP retransmission timeout in milliseconds
+    #[arg(long)]
+    rto_min: Option<u32>,
+}
+
+impl From<MetricArgs> for RouteMetrics {
+    fn from(args: MetricArgs) -> Self {
+        Self {
+            advmss: args.advmss,
+            hoplimit: args.hoplimit,
+            initcwnd: args.initcwnd,
+            initrwnd: args.initrwnd,
+            rto_min: args.rto_min,
+            lock: None,
+        }
+    }
+}
+
 #[derive(Args, Clone)]
 struct RouteV4DeleteArgs {
     prefix: String,
@@ -99,6 +188,8 @@ struct RouteV4DeleteArgs {
     via: Option<IpAddr>,
     #[arg(long)]
     dev: Option<String>,
+    #[arg(long, default_value_t = 0)]
+    tos: u8,
     #[arg(long)]
     table: Option<u32>,
 }
@@ -124,10 +215,17 @@ fn main() -> io::Result<()> {
         Command::Add6(args) => run_add6(&client, args),
         Command::Del4(args) => run_del4(&client, args),
         Command::Del6(args) => run_del6(&client, args),
-        Command::Get4 { destination } => run_get4(&client, destination),
+        Command::Get4 {
+            destination,
+            lookup,
+        } => run_get4(&client, destination, lookup),
         Command::Get4Prefix { prefix } => run_get4_prefix(&client, &prefix),
-        Command::Get6 { destination } => run_get6(&client, destination),
+        Command::Get6 {
+            destination,
+            lookup,
+        } => run_get6(&client, destination, lookup),
         Command::Get6Prefix { prefix } => run_get6_prefix(&client, &prefix),
+        Command::GetAll { destination } => run_get_all(&client, destination),
     }
 }
 
@@ -149,15 +247,20 @@ fn run_list(client: &RtnlClient, family: RouteFamily) -> io::Result<()> {
 }
 
 fn run_add4(client: &RtnlClient, args: RouteV4Args) -> io::Result<()> {
-    let route = build_ipv4_route(
-        client,
-        &args.prefix,
-        args.via,
-        args.dev,
-        args.src,
-        args.metric,
-        args.table,
-    )?;
+    let route = Ipv4Route {
+        tos: args.tos,
+        mtu: args.mtu,
+        metrics: args.metrics.into(),
+        onlink: args.onlink,
+        ..build_ipv4_route(
+            &args.prefix,
+            args.via,
+            args.dev,
+            args.src,
+            args.metric,
+            args.table,
+        )?
+    };
     if args.replace {
         client.route().ipv4_route_replace(route)?;
         println!("IPv4 route replaced");
@@ -169,15 +272,20 @@ fn run_add4(client: &RtnlClient, args: RouteV4Args) -> io::Result<()> {
 }
 
 fn run_add6(client: &RtnlClient, args: RouteV6Args) -> io::Result<()> {
-    let route = build_ipv6_route(
-        client,
-        &args.prefix,
-        args.via,
-        args.dev,
-        args.src,
-        args.metric,
-        args.table,
-    )?;
+    let route = Ipv6Route {
+        mtu: args.mtu,
+        metrics: args.metrics.into(),
+        onlink: args.onlink,
+        expires: args.expires,
+        ..build_ipv6_route(
+            &args.prefix,
+            args.via,
+            args.dev,
+            args.src,
+            args.metric,
+            args.table,
+        )?
+    };
     if args.replace {
         client.route().ipv6_route_replace(route)?;
         println!("IPv6 route replaced");
@@ -189,37 +297,27 @@ fn run_add6(client: &RtnlClient, args: RouteV6Args) -> io::Result<()> {
 }
 
 fn run_del4(client: &RtnlClient, args: RouteV4DeleteArgs) -> io::Result<()> {
-    let route = build_ipv4_route(
-        client,
-        &args.prefix,
-        args.via,
-        args.dev,
-        None,
-        None,
-        args.table,
-    )?;
+    let route = Ipv4Route {
+        tos: args.tos,
+        ..build_ipv4_route(&args.prefix, args.via, args.dev, None, None, args.table)?
+    };
     client.route().ipv4_route_del(route)?;
     println!("IPv4 route deleted");
     Ok(())
 }
 
 fn run_del6(client: &RtnlClient, args: RouteV6DeleteArgs) -> io::Result<()> {
-    let route = build_ipv6_route(
-        client,
-        &args.prefix,
-        args.via,
-        args.dev,
-        None,
-        None,
-        args.table,
-    )?;
+    let route = build_ipv6_route(&args.prefix, args.via, args.dev, None, None, args.table)?;
     client.route().ipv6_route_del(route)?;
     println!("IPv6 route deleted");
     Ok(())
 }
 
-fn run_get4(client: &RtnlClient, destination: Ipv4Addr) -> io::Result<()> {
-    let route = client.route().ipv4_route_get(destination)?;
+fn run_get4(client: &RtnlClient, destination: Ipv4Addr, lookup: LookupArgs) -> io::Result<()> {
+    let options = build_lookup_options(client, lookup)?;
+    let route = client
+        .route()
+        .ipv4_route_get_with_options(destination, options)?;
     let link_map = build_interface_map(client)?;
     print_ipv4_route(&route, &link_map)
 }
@@ -239,8 +337,11 @@ fn run_get4_prefix(client: &RtnlClient, prefix: &str) -> io::Result<()> {
     print_ipv4_route(&route, &link_map)
 }
 
-fn run_get6(client: &RtnlClient, destination: Ipv6Addr) -> io::Result<()> {
-    let route = client.route().ipv6_route_get(destination)?;
+fn run_get6(client: &RtnlClient, destination: Ipv6Addr, lookup: LookupArgs) -> io::Result<()> {
+    let options = build_lookup_options(client, lookup)?;
+    let route = client
+        .route()
+        .ipv6_route_get_with_options(destination, options)?;
     let link_map = build_interface_map(client)?;
     print_ipv6_route(&route, &link_map)
 }
@@ -260,8 +361,19 @@ fn run_get6_prefix(client: &RtnlClient, prefix: &str) -> io::Result<()> {
     print_ipv6_route(&route, &link_map)
 }
 
+fn run_get_all(client: &RtnlClient, destination: IpAddr) -> io::Result<()> {
+    let routes = client.route().route_get_all(destination)?;
+    let link_map = build_interface_map(client)?;
+    for route in routes {
+        match route {
+            IpRoute::V4(route) => print_ipv4_route(&route, &link_map)?,
+            IpRoute::V6(route) => print_ipv6_route(&route, &link_map)?,
+        }
+    }
+    Ok(())
+}
+
 fn build_ipv4_route(
-    client: &RtnlClient,
     prefix: &str,
     gateway: Option<IpAddr>,
     dev: Option<String>,
@@ -278,20 +390,30 @@ fn build_ipv4_route(
             return Err(io::Error::other("Expected IPv4 prefix"));
         }
     };
-    let if_id = resolve_interface(client, dev)?;
     Ok(Ipv4Route {
-        if_id,
-        gateway,
+        if_id: None,
+        if_name: dev,
+        gateway: gateway.map(RouteGateway::Gateway),
         source,
         metric,
+        tos: 0,
         table,
         route: net,
         nexthops: Vec::new(),
+        nexthop_id: None,
+        kind: RouteType::Unicast,
+        protocol: None,
+        mtu: None,
+        metrics: RouteMetrics::default(),
+        onlink: false,
+        encap: None,
+        status: RouteStatus::default(),
+        cache_info: None,
+        extra: Vec::new(),
     })
 }
 
 fn build_ipv6_route(
-    client: &RtnlClient,
     prefix: &str,
     gateway: Option<Ipv6Addr>,
     dev: Option<String>,
@@ -308,15 +430,37 @@ fn build_ipv6_route(
             return Err(io::Error::other("Expected IPv6 prefix"));
         }
     };
-    let if_id = resolve_interface(client, dev)?;
     Ok(Ipv6Route {
-        if_id,
-        gateway: gateway.map(IpAddr::V6),
+        if_id: None,
+        if_name: dev,
+        gateway: gateway.map(|gw| RouteGateway::Gateway(IpAddr::V6(gw))),
         source,
         metric,
         table,
         route: net,
         nexthops: Vec::new(),
+        nexthop_id: None,
+        kind: RouteType::Unicast,
+        protocol: None,
+        mtu: None,
+        metrics: RouteMetrics::default(),
+        expires: None,
+        onlink: false,
+        encap: None,
+        status: RouteStatus::default(),
+        cache_info: None,
+        extra: Vec::new(),
+    })
+}
+
+fn build_lookup_options(client: &RtnlClient, args: LookupArgs) -> io::Result<RouteLookupOptions> {
+    Ok(RouteLookupOptions {
+        iif: resolve_interface(client, args.iif)?,
+        mark: args.mark,
+        source: args.from,
+        tos: args.tos,
+        skip_local: args.skip_local,
+        fib_match: args.fibmatch,
     })
 }
 
@@ -335,27 +479,37 @@ fn build_interface_map(client: &RtnlClient) -> io::Result<HashMap<u32, String>>
     Ok(map)
 }
 
+fn format_via(gateway: Option<RouteGateway>) -> String {
+    match gateway {
+        Some(RouteGateway::Gateway(addr)) => addr.to_string(),
+        Some(RouteGateway::Via(IpAddr::V4(addr))) => format!("inet {}", addr),
+        Some(RouteGateway::Via(IpAddr::V6(addr))) => format!("inet6 {}", addr),
+        None => "direct".into(),
+    }
+}
+
 fn print_ipv4_route(route: &Ipv4Route, links: &HashMap<u32, String>) -> io::Result<()> {
-    let metric = route.metric.map_or("-".into(), |m| m.to_string());
+    let mut metric = route.metric.map_or("-".into(), |m| m.to_string());
+    if route.tos != 0 {
+        metric = format!("{} tos 0x{:02x}", metric, route.tos);
+    }
     let table = route.table.map_or("main".into(), |t| t.to_string());
     let source = route.source.map_or("-".into(), |s| s.to_string());
 
-    let format_via = |gateway: Option<IpAddr>| -> String {
-        match gateway {
-            Some(IpAddr::V6(addr)) => format!("inet6 {}", addr),
-            Some(IpAddr::V4(addr)) => addr.to_string(),
-            None => "direct".into(),
-        }
-    };
-
     if route.nexthops.is_empty() {
         let dev = route.if_id.and_then(|id| links.get(&id).cloned());
         let via = format_via(route.gateway);
         let dev_str = dev.unwrap_or_else(|| "-".into());
-        println!(
+        let flags = format_next_hop_flags(RouteNextHopFlags::empty(), route.status);
+        let line = format!(
             "{} via {} dev {} src {} metric {} table {}",
             route.route, via, dev_str, source, metric, table,
         );
+        if flags.is_empty() {
+            println!("{}", line);
+        } else {
+            println!("{} {}", line, flags);
+        }
     } else {
         println!(
             "{} src {} metric {} table {}",
@@ -367,7 +521,7 @@ fn print_ipv4_route(route: &Ipv4Route, links: &HashMap<u32, String>) -> io::Resu
                 .and_then(|id| links.get(&id).cloned())
                 .unwrap_or_else(|| "-".into());
             let via = format_via(nh.gateway);
-            let flags = format_next_hop_flags(nh.flags);
+            let flags = format_next_hop_flags(nh.flags, nh.status);
             if flags.is_empty() {
                 println!("  nexthop via {} dev {} weight {}", via, dev, nh.weight);
             } else {
@@ -386,22 +540,20 @@ fn print_ipv6_route(route: &Ipv6Route, links: &HashMap<u32, String>) -> io::Resu
     let table = route.table.map_or("main".into(), |t| t.to_string());
     let source = route.source.map_or("-".into(), |s| s.to_string());
 
-    let format_via = |gateway: Option<IpAddr>| -> String {
-        match gateway {
-            Some(IpAddr::V4(addr)) => format!("inet {}", addr),
-            Some(IpAddr::V6(addr)) => addr.to_string(),
-            None => "direct".into(),
-        }
-    };
-
     if route.nexthops.is_empty() {
         let dev = route.if_id.and_then(|id| links.get(&id).cloned());
         let via = format_via(route.gateway);
         let dev_str = dev.unwrap_or_else(|| "-".into());
-        println!(
+        let flags = format_next_hop_flags(RouteNextHopFlags::empty(), route.status);
+        let line = format!(
             "{} via {} dev {} src {} metric {} table {}",
             route.route, via, dev_str, source, metric, table,
         );
+        if flags.is_empty() {
+            println!("{}", line);
+        } else {
+            println!("{} {}", line, flags);
+        }
     } else {
         println!(
             "{} src {} metric {} table {}",
@@ -413,7 +565,7 @@ fn print_ipv6_route(route: &Ipv6Route, links: &HashMap<u32, String>) -> io::Resu
                 .and_then(|id| links.get(&id).cloned())
                 .unwrap_or_else(|| "-".into());
             let via = format_via(nh.gateway);
-            let flags = format_next_hop_flags(nh.flags);
+            let flags = format_next_hop_flags(nh.flags, nh.status);
             if flags.is_empty() {
                 println!("  nexthop via {} dev {} weight {}", via, dev, nh.weight);
             } else {
@@ -427,13 +579,9 @@ fn print_ipv6_route(route: &Ipv6Route, links: &HashMap<u32, String>) -> io::Resu
     Ok(())
 }
 
-fn format_next_hop_flags(flags: RouteNextHopFlags) -> String {
-    if flags.is_empty() {
-        return String::new();
-    }
-
+fn format_next_hop_flags(flags: RouteNextHopFlags, status: RouteStatus) -> String {
     let mut parts = Vec::new();
-    if flags.contains(RouteNextHopFlags::Dead) {
+    if status.dead {
         parts.push("dead");
     }
     if flags.contains(RouteNextHopFlags::Pervasive) {
@@ -442,18 +590,24 @@ fn format_next_hop_flags(flags: RouteNextHopFlags) -> String {
     if flags.contains(RouteNextHopFlags::Onlink) {
         parts.push("onlink");
     }
-    if flags.contains(RouteNextHopFlags::Offload) {
+    if status.offload {
         parts.push("offload");
     }
-    if flags.contains(RouteNextHopFlags::Linkdown) {
+    if status.linkdown {
         parts.push("linkdown");
     }
-    if flags.contains(RouteNextHopFlags::Unresolved) {
+    if status.unresolved {
         parts.push("unresolved");
     }
-    if flags.contains(RouteNextHopFlags::Trap) {
+    if status.trap {
         parts.push("trap");
     }
+    if status.offload_failed {
+        parts.push("offload_failed");
+    }
+    if status.cloned {
+        parts.push("cloned");
+    }
 
     if parts.is_empty() {
         String::new()
@@ -24,7 +24,7 @@ fn main() -> std::io::Result<()> {
         for addr in v6addrs {
             println!("    {}", addr);
         }
-        println!("");
+        println!();
     }
     Ok(())
 }
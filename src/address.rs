@@ -1,16 +1,27 @@
 #![allow(unreachable_patterns)]
 
+use std::collections::HashMap;
 use std::io::{self, ErrorKind};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+#[cfg(feature = "monitor")]
+use std::time::Instant;
 
 use futures::TryStreamExt;
+use netlink_packet_core::{DefaultNla, Nla};
 
 use ftth_common::channel::{AsyncWorldClient, AsyncWorldServer};
 use netlink_packet_route::{
     AddressFamily,
-    address::{AddressAttribute, AddressMessage, AddressScope},
+    address::{
+        AddressAttribute, AddressFlags, AddressHeaderFlags, AddressMessage, AddressScope, CacheInfo,
+    },
 };
 
+use crate::error::RtnlError;
+#[cfg(feature = "monitor")]
+use crate::monitor::{RtnlEvent, RtnlGroup, RtnlMonitor};
+
 pub(crate) type Client = AsyncWorldClient<RtnlAddressRequest, RtnlAddressResponse>;
 pub(crate) type Server = AsyncWorldServer<RtnlAddressRequest, RtnlAddressResponse>;
 
@@ -25,13 +36,18 @@ pub enum RtnlAddressRequest {
         if_id: u32,
         scope: Option<AddressScope>,
     },
+    AddrsList {
+        if_id: u32,
+    },
     Ipv4AddrSet {
         prefix: crate::Ipv4Net,
         if_id: u32,
+        options: AddressOptions,
     },
     Ipv6AddrSet {
         prefix: crate::Ipv6Net,
         if_id: u32,
+        options: AddressOptions,
     },
     Ipv4AddrDel {
         prefix: crate::Ipv4Net,
@@ -41,6 +57,45 @@ pub enum RtnlAddressRequest {
         prefix: crate::Ipv6Net,
         if_id: u32,
     },
+    AddrFlush {
+        if_id: u32,
+        filter: AddressFilter,
+    },
+    // The *ByName variants resolve the interface in the worker and then
+    // behave like their index-based counterparts.
+    Ipv4AddrSetByName {
+        prefix: crate::Ipv4Net,
+        if_name: String,
+        options: AddressOptions,
+    },
+    Ipv6AddrSetByName {
+        prefix: crate::Ipv6Net,
+        if_name: String,
+        options: AddressOptions,
+    },
+    Ipv4AddrDelByName {
+        prefix: crate::Ipv4Net,
+        if_name: String,
+    },
+    Ipv6AddrDelByName {
+        prefix: crate::Ipv6Net,
+        if_name: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IpFamily {
+    Ipv4,
+    Ipv6,
+}
+
+impl IpFamily {
+    fn address_family(self) -> AddressFamily {
+        match self {
+            IpFamily::Ipv4 => AddressFamily::Inet,
+            IpFamily::Ipv6 => AddressFamily::Inet6,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -52,8 +107,156 @@ pub enum RtnlAddressResponse {
     NotFound,
     Ipv4Addrs(Vec<Ipv4Addr>),
     Ipv6Addrs(Vec<Ipv6Addr>),
+    AddrInfos(Vec<AddressInfo>),
+    Error(RtnlError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressInfo {
+    pub if_id: u32,
+    pub address: crate::IpNet,
+    // Set on point-to-point addresses, where IFA_ADDRESS names the peer
+    // instead of repeating IFA_LOCAL.
+    pub peer: Option<IpAddr>,
+    pub broadcast: Option<Ipv4Addr>,
+    pub scope: AddressScope,
+    pub flags: AddressFlags,
+    pub label: Option<String>,
+    // Remaining lifetimes in seconds; `None` means forever.
+    pub valid_lft: Option<u32>,
+    pub preferred_lft: Option<u32>,
+    pub cacheinfo: Option<CacheInfo>,
+    pub metric: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressOptions {
+    pub scope: Option<AddressScope>,
+    // The kernel honours Nodad, Noprefixroute, Managetempaddr and
+    // Homeaddress on add; Noprefixroute leaves the connected route to the
+    // caller.
+    pub flags: AddressFlags,
+    // Lifetimes in seconds after which the kernel deprecates and removes
+    // the address; `None` means forever. The preferred lifetime defaults to
+    // the valid one.
+    pub valid_lft: Option<u32>,
+    pub preferred_lft: Option<u32>,
+    // Replace an existing address instead of leaving it untouched, so that
+    // renewed lifetimes and flags take effect.
+    pub replace: bool,
+    // IPv4 only, e.g. `eth0:vip`; at most 15 bytes.
+    pub label: Option<String>,
+    // Report an existing address as an `AlreadyExists` error instead of
+    // success, so callers can tell whether anything changed.
+    pub strict: bool,
+    // Metric of the connected route the kernel installs for the prefix.
+    pub metric: Option<u32>,
+    // IPv4 only; derived from the prefix when unset.
+    pub broadcast: Option<Ipv4Addr>,
+}
+
+impl Default for AddressOptions {
+    fn default() -> Self {
+        Self {
+            scope: None,
+            flags: AddressFlags::empty(),
+            valid_lft: None,
+            preferred_lft: None,
+            replace: false,
+            label: None,
+            strict: false,
+            metric: None,
+            broadcast: None,
+        }
+    }
+}
+
+// Selects addresses by family, scope and flags; unset fields match
+// anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressFilter {
+    pub family: Option<IpFamily>,
+    pub scope: Option<AddressScope>,
+    // All of these flags must be set.
+    pub flags: AddressFlags,
+    // None of these flags may be set; `Permanent` here keeps statically
+    // configured addresses and selects SLAAC/DHCP ones.
+    pub without_flags: AddressFlags,
+}
+
+impl Default for AddressFilter {
+    fn default() -> Self {
+        Self {
+            family: None,
+            scope: None,
+            flags: AddressFlags::empty(),
+            without_flags: AddressFlags::empty(),
+        }
+    }
+}
+
+impl AddressFilter {
+    pub fn family(family: IpFamily) -> Self {
+        Self {
+            family: Some(family),
+            ..Self::default()
+        }
+    }
+
+    pub fn matches(&self, info: &AddressInfo) -> bool {
+        let family_matches = match self.family {
+            Some(IpFamily::Ipv4) => matches!(info.address, crate::IpNet::V4(_)),
+            Some(IpFamily::Ipv6) => matches!(info.address, crate::IpNet::V6(_)),
+            None => true,
+        };
+        family_matches
+            && self.scope.is_none_or(|scope| info.scope == scope)
+            && info.flags.contains(self.flags)
+            && !info.flags.intersects(self.without_flags)
+    }
+}
+
+impl AddressInfo {
+    // Duplicate address detection is still in progress; the address cannot
+    // be bound yet.
+    pub fn is_tentative(&self) -> bool {
+        self.flags.contains(AddressFlags::Tentative) && !self.is_dad_failed()
+    }
+
+    // IPv4 only: another address of the same subnet is primary, and this one
+    // is removed along with it unless promote_secondaries is enabled.
+    pub fn is_secondary(&self) -> bool {
+        self.flags.contains(AddressFlags::Secondary)
+    }
+
+    // Time since boot at which the address was added, from IFA_CACHEINFO.
+    pub fn created(&self) -> Option<Duration> {
+        self.cacheinfo.map(|info| cstamp_to_duration(info.cstamp))
+    }
+
+    // Time since boot at which the address was last updated, e.g. renewed
+    // by DHCP.
+    pub fn updated(&self) -> Option<Duration> {
+        self.cacheinfo.map(|info| cstamp_to_duration(info.tstamp))
+    }
+
+    // Duplicate address detection found another node using the address.
+    pub fn is_dad_failed(&self) -> bool {
+        self.flags.contains(AddressFlags::Dadfailed)
+    }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AddressSyncReport {
+    pub added: Vec<crate::IpNet>,
+    pub removed: Vec<crate::IpNet>,
+}
+
+const ENODEV: i32 = 19;
+const INFINITY_LIFE_TIME: u32 = u32::MAX;
+const ADDRESS_LABEL_MAX_LEN: usize = 15;
+const IFA_RT_PRIORITY: u16 = 9;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RtnlAddressClient {
     client: Client,
@@ -64,6 +267,10 @@ impl RtnlAddressClient {
         Self { client }
     }
 
+    pub fn send_raw(&self, request: RtnlAddressRequest) -> io::Result<RtnlAddressResponse> {
+        self.client.send_request(request)
+    }
+
     pub fn ipv4_addrs_get(&self, if_id: Option<u32>) -> std::io::Result<Vec<Ipv4Addr>> {
         self.ipv4_addrs_get_with_scope(if_id, None)
     }
@@ -78,12 +285,10 @@ impl RtnlAddressClient {
             scope,
         })?;
         match res {
-            RtnlAddressResponse::Ipv4Addrs(addrs) => {
-                return Ok(addrs);
-            }
-            _ => {}
+            RtnlAddressResponse::Ipv4Addrs(addrs) => Ok(addrs),
+            RtnlAddressResponse::Error(err) => Err(err.into_io_error("Get IPv4 addresses")),
+            _ => Err(std::io::Error::other("Failed to get IPv4 addresses")),
         }
-        Err(std::io::Error::other("Failed to get IPv4 addresses"))
     }
 
     pub fn ipv6_addrs_get(&self, if_id: Option<u32>) -> std::io::Result<Vec<Ipv6Addr>> {
@@ -100,25 +305,187 @@ impl RtnlAddressClient {
             scope,
         })?;
         match res {
-            RtnlAddressResponse::Ipv6Addrs(addrs) => {
-                return Ok(addrs);
+            RtnlAddressResponse::Ipv6Addrs(addrs) => Ok(addrs),
+            RtnlAddressResponse::Error(err) => Err(err.into_io_error("Get IPv6 addresses")),
+            _ => Err(std::io::Error::other("Failed to get IPv6 addresses")),
+        }
+    }
+
+    // Lists IPv4 and IPv6 addresses with their prefix length, scope, flags
+    // and lifetimes.
+    pub fn addrs_list(&self, if_id: Option<u32>) -> io::Result<Vec<AddressInfo>> {
+        let res = self.client.send_request(RtnlAddressRequest::AddrsList {
+            if_id: if_id.unwrap_or(0),
+        })?;
+        match res {
+            RtnlAddressResponse::AddrInfos(addrs) => Ok(addrs),
+            RtnlAddressResponse::Error(err) => Err(err.into_io_error("List addresses")),
+            _ => Err(io::Error::other("Failed to list addresses")),
+        }
+    }
+
+    // Addresses of all interfaces from a single dump, keyed by ifindex.
+    pub fn addrs_get_all(&self) -> io::Result<HashMap<u32, Vec<AddressInfo>>> {
+        let mut addrs: HashMap<u32, Vec<AddressInfo>> = HashMap::new();
+        for info in self.addrs_list(None)? {
+            addrs.entry(info.if_id).or_default().push(info);
+        }
+        Ok(addrs)
+    }
+
+    // Checks for an exact address and prefix length match on the interface.
+    pub fn addr_exists(&self, if_id: u32, prefix: crate::IpNet) -> io::Result<bool> {
+        Ok(self
+            .addrs_list(Some(if_id))?
+            .iter()
+            .any(|info| info.address == prefix))
+    }
+
+    // Subscribes to IPv4 and IPv6 address notifications.
+    #[cfg(feature = "monitor")]
+    pub fn watch(&self) -> io::Result<AddressWatcher> {
+        let monitor = RtnlMonitor::new(&[RtnlGroup::Ipv4Address, RtnlGroup::Ipv6Address])?;
+        Ok(AddressWatcher { monitor })
+    }
+
+    // Waits until duplicate address detection for `addr` has finished.
+    // Fails with `AddrInUse` when a duplicate was detected. Subscribes before
+    // reading the current state, so a transition between the two cannot be
+    // missed.
+    #[cfg(feature = "monitor")]
+    pub fn wait_dad_complete(
+        &self,
+        if_id: u32,
+        addr: Ipv6Addr,
+        timeout: Duration,
+    ) -> io::Result<()> {
+        let watcher = self.watch()?;
+        let current = self
+            .addrs_list(Some(if_id))?
+            .into_iter()
+            .find(|info| info.address.addr() == IpAddr::V6(addr));
+        match current {
+            Some(info) => {
+                if let Some(result) = dad_result(&info) {
+                    return result;
+                }
+            }
+            None => {
+                return Err(io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("Address {} not found", addr),
+                ));
+            }
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let event = watcher.recv_timeout(remaining)?.ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::TimedOut,
+                    format!("Duplicate address detection for {} did not finish", addr),
+                )
+            })?;
+            match event {
+                AddressEvent::Added(info)
+                    if info.if_id == if_id && info.address.addr() == IpAddr::V6(addr) =>
+                {
+                    if let Some(result) = dad_result(&info) {
+                        return result;
+                    }
+                }
+                AddressEvent::Removed(info)
+                    if info.if_id == if_id && info.address.addr() == IpAddr::V6(addr) =>
+                {
+                    return Err(io::Error::new(ErrorKind::NotFound, "Address removed"));
+                }
+                _ => {}
             }
-            _ => {}
         }
-        Err(std::io::Error::other("Failed to get IPv6 addresses"))
     }
 
     pub fn ipv4_addr_set(&self, if_id: u32, prefix: crate::Ipv4Net) -> io::Result<()> {
-        let res = self
-            .client
-            .send_request(RtnlAddressRequest::Ipv4AddrSet { prefix, if_id })?;
+        self.ipv4_addr_set_with_scope(if_id, prefix, None)
+    }
+
+    pub fn ipv4_addr_set_with_scope(
+        &self,
+        if_id: u32,
+        prefix: crate::Ipv4Net,
+        scope: Option<AddressScope>,
+    ) -> io::Result<()> {
+        let options = AddressOptions {
+            scope,
+            ..AddressOptions::default()
+        };
+        self.ipv4_addr_set_with_options(if_id, prefix, options)
+    }
+
+    pub fn ipv4_addr_set_with_options(
+        &self,
+        if_id: u32,
+        prefix: crate::Ipv4Net,
+        options: AddressOptions,
+    ) -> io::Result<()> {
+        if options
+            .label
+            .as_ref()
+            .is_some_and(|label| label.len() > ADDRESS_LABEL_MAX_LEN)
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Address label is too long",
+            ));
+        }
+        let res = self.client.send_request(RtnlAddressRequest::Ipv4AddrSet {
+            prefix,
+            if_id,
+            options,
+        })?;
         handle_basic_response("IPv4 address set", res, false)
     }
 
     pub fn ipv6_addr_set(&self, if_id: u32, prefix: crate::Ipv6Net) -> io::Result<()> {
-        let res = self
-            .client
-            .send_request(RtnlAddressRequest::Ipv6AddrSet { prefix, if_id })?;
+        self.ipv6_addr_set_with_scope(if_id, prefix, None)
+    }
+
+    pub fn ipv6_addr_set_with_scope(
+        &self,
+        if_id: u32,
+        prefix: crate::Ipv6Net,
+        scope: Option<AddressScope>,
+    ) -> io::Result<()> {
+        let options = AddressOptions {
+            scope,
+            ..AddressOptions::default()
+        };
+        self.ipv6_addr_set_with_options(if_id, prefix, options)
+    }
+
+    pub fn ipv6_addr_set_with_options(
+        &self,
+        if_id: u32,
+        prefix: crate::Ipv6Net,
+        options: AddressOptions,
+    ) -> io::Result<()> {
+        if options.label.is_some() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Address labels are only supported for IPv4",
+            ));
+        }
+        if options.broadcast.is_some() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Broadcast addresses are only supported for IPv4",
+            ));
+        }
+        let res = self.client.send_request(RtnlAddressRequest::Ipv6AddrSet {
+            prefix,
+            if_id,
+            options,
+        })?;
         handle_basic_response("IPv6 address set", res, false)
     }
 
@@ -129,12 +496,357 @@ impl RtnlAddressClient {
         handle_basic_response("IPv4 address delete", res, true)
     }
 
+    // Deletes an IPv4 address and restores any secondaries of the same
+    // subnet that the kernel dropped with it, so the result does not depend
+    // on the promote_secondaries sysctl.
+    pub fn ipv4_addr_del_keep_secondaries(
+        &self,
+        if_id: u32,
+        prefix: crate::Ipv4Net,
+    ) -> io::Result<()> {
+        let secondaries: Vec<_> = self
+            .addrs_list(Some(if_id))?
+            .into_iter()
+            .filter(|info| match info.address {
+                crate::IpNet::V4(addr) => {
+                    info.is_secondary()
+                        && addr != prefix
+                        && addr.prefix_len() == prefix.prefix_len()
+                        && addr.trunc() == prefix.trunc()
+                }
+                crate::IpNet::V6(_) => false,
+            })
+            .collect();
+        self.ipv4_addr_del(if_id, prefix)?;
+        if secondaries.is_empty() {
+            return Ok(());
+        }
+
+        let current: Vec<_> = self
+            .addrs_list(Some(if_id))?
+            .into_iter()
+            .map(|info| info.address)
+            .collect();
+        for info in secondaries {
+            let crate::IpNet::V4(addr) = info.address else {
+                continue;
+            };
+            if current.contains(&info.address) {
+                continue;
+            }
+            self.ipv4_addr_set_with_options(if_id, addr, readd_options(info))?;
+        }
+        Ok(())
+    }
+
     pub fn ipv6_addr_del(&self, if_id: u32, prefix: crate::Ipv6Net) -> io::Result<()> {
         let res = self
             .client
             .send_request(RtnlAddressRequest::Ipv6AddrDel { prefix, if_id })?;
         handle_basic_response("IPv6 address delete", res, true)
     }
+
+    pub fn ipv4_addr_set_by_name(&self, if_name: &str, prefix: crate::Ipv4Net) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlAddressRequest::Ipv4AddrSetByName {
+                prefix,
+                if_name: if_name.to_string(),
+                options: AddressOptions::default(),
+            })?;
+        handle_basic_response("IPv4 address set", res, false)
+    }
+
+    pub fn ipv6_addr_set_by_name(&self, if_name: &str, prefix: crate::Ipv6Net) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlAddressRequest::Ipv6AddrSetByName {
+                prefix,
+                if_name: if_name.to_string(),
+                options: AddressOptions::default(),
+            })?;
+        handle_basic_response("IPv6 address set", res, false)
+    }
+
+    pub fn ipv4_addr_del_by_name(&self, if_name: &str, prefix: crate::Ipv4Net) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlAddressRequest::Ipv4AddrDelByName {
+                prefix,
+                if_name: if_name.to_string(),
+            })?;
+        handle_basic_response("IPv4 address delete", res, true)
+    }
+
+    pub fn ipv6_addr_del_by_name(&self, if_name: &str, prefix: crate::Ipv6Net) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlAddressRequest::Ipv6AddrDelByName {
+                prefix,
+                if_name: if_name.to_string(),
+            })?;
+        handle_basic_response("IPv6 address delete", res, true)
+    }
+
+    // Brings the addresses on the interface in line with `desired`. IPv6
+    // link-local addresses are managed by the kernel and left alone unless
+    // listed. Addresses are re-read after the removals, since deleting a
+    // primary IPv4 address can take its secondaries with it.
+    pub fn addr_sync(&self, if_id: u32, desired: &[crate::IpNet]) -> io::Result<AddressSyncReport> {
+        let mut report = AddressSyncReport::default();
+        for info in self.addrs_list(Some(if_id))? {
+            if desired.contains(&info.address) || is_ipv6_link_local(&info.address) {
+                continue;
+            }
+            let result = match info.address {
+                crate::IpNet::V4(prefix) => self.ipv4_addr_del(if_id, prefix),
+                crate::IpNet::V6(prefix) => self.ipv6_addr_del(if_id, prefix),
+            };
+            match result {
+                Ok(()) => report.removed.push(info.address),
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        let current: Vec<_> = self
+            .addrs_list(Some(if_id))?
+            .into_iter()
+            .map(|info| info.address)
+            .collect();
+        for prefix in desired {
+            if current.contains(prefix) || report.added.contains(prefix) {
+                continue;
+            }
+            match *prefix {
+                crate::IpNet::V4(prefix) => self.ipv4_addr_set(if_id, prefix)?,
+                crate::IpNet::V6(prefix) => self.ipv6_addr_set(if_id, prefix)?,
+            }
+            report.added.push(*prefix);
+        }
+        Ok(report)
+    }
+
+    // Removes every address of `family` (all families when `None`) from the
+    // interface, like `ip addr flush dev`.
+    pub fn addr_flush(&self, if_id: u32, family: Option<IpFamily>) -> io::Result<()> {
+        let filter = AddressFilter {
+            family,
+            ..AddressFilter::default()
+        };
+        self.addr_flush_matching(if_id, filter)
+    }
+
+    // Removes only the addresses selected by `filter`, e.g. the deprecated
+    // or dynamic ones.
+    pub fn addr_flush_matching(&self, if_id: u32, filter: AddressFilter) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlAddressRequest::AddrFlush { if_id, filter })?;
+        handle_basic_response("Address flush", res, true)
+    }
+}
+
+#[cfg(feature = "monitor")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressEvent {
+    Added(AddressInfo),
+    Removed(AddressInfo),
+}
+
+#[cfg(feature = "monitor")]
+#[derive(Debug)]
+pub struct AddressWatcher {
+    monitor: RtnlMonitor,
+}
+
+#[cfg(feature = "monitor")]
+impl AddressWatcher {
+    pub fn recv(&self) -> io::Result<AddressEvent> {
+        loop {
+            if let Some(event) = address_event(self.monitor.recv()?) {
+                return Ok(event);
+            }
+        }
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> io::Result<Option<AddressEvent>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.monitor.recv_timeout(remaining)? {
+                Some(event) => {
+                    if let Some(event) = address_event(event) {
+                        return Ok(Some(event));
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = AddressEvent> + '_ {
+        self.monitor.iter().filter_map(address_event)
+    }
+}
+
+#[cfg(feature = "monitor")]
+fn dad_result(info: &AddressInfo) -> Option<io::Result<()>> {
+    if info.is_dad_failed() {
+        Some(Err(io::Error::new(
+            ErrorKind::AddrInUse,
+            format!("Duplicate address detected for {}", info.address.addr()),
+        )))
+    } else if info.is_tentative() {
+        None
+    } else {
+        Some(Ok(()))
+    }
+}
+
+#[cfg(feature = "monitor")]
+fn address_event(event: RtnlEvent) -> Option<AddressEvent> {
+    match event {
+        RtnlEvent::NewAddress(msg) => address_info_from_message(&msg).map(AddressEvent::Added),
+        RtnlEvent::DelAddress(msg) => address_info_from_message(&msg).map(AddressEvent::Removed),
+        _ => None,
+    }
+}
+
+pub fn address_message(if_id: u32, prefix: crate::IpNet) -> AddressMessage {
+    match prefix {
+        crate::IpNet::V4(prefix) => build_ipv4_address_message(&prefix, if_id),
+        crate::IpNet::V6(prefix) => build_ipv6_address_message(&prefix, if_id),
+    }
+}
+
+pub fn address_from_message(message: &AddressMessage) -> Option<(u32, crate::IpNet)> {
+    let mut address = None;
+    let mut local = None;
+    for attr in message.attributes.iter() {
+        match attr {
+            AddressAttribute::Address(addr) => address = Some(*addr),
+            AddressAttribute::Local(addr) => local = Some(*addr),
+            _ => {}
+        }
+    }
+    let addr = local.or(address)?;
+    let prefix = crate::IpNet::new(addr, message.header.prefix_len).ok()?;
+    Some((message.header.index, prefix))
+}
+
+pub fn address_info_from_message(message: &AddressMessage) -> Option<AddressInfo> {
+    let mut address = None;
+    let mut local = None;
+    let mut flags = None;
+    let mut label = None;
+    let mut cacheinfo = None;
+    let mut metric = None;
+    let mut broadcast = None;
+    for attr in message.attributes.iter() {
+        match attr {
+            AddressAttribute::Broadcast(addr) => broadcast = Some(*addr),
+            // netlink-packet-route leaves IFA_RT_PRIORITY undecoded.
+            AddressAttribute::Other(nla) if nla.kind() == IFA_RT_PRIORITY => {
+                let mut value = vec![0u8; nla.value_len()];
+                nla.emit_value(&mut value);
+                metric = value.try_into().ok().map(u32::from_ne_bytes);
+            }
+            AddressAttribute::Address(addr) => address = Some(*addr),
+            AddressAttribute::Local(addr) => local = Some(*addr),
+            AddressAttribute::Flags(value) => flags = Some(*value),
+            AddressAttribute::Label(value) => label = Some(value.clone()),
+            AddressAttribute::CacheInfo(value) => cacheinfo = Some(*value),
+            _ => {}
+        }
+    }
+    let addr = local.or(address)?;
+    let lifetime = |value: u32| (value != INFINITY_LIFE_TIME).then_some(value);
+    Some(AddressInfo {
+        if_id: message.header.index,
+        address: crate::IpNet::new(addr, message.header.prefix_len).ok()?,
+        peer: address.filter(|peer| local.is_some() && *peer != addr),
+        broadcast,
+        scope: message.header.scope,
+        // IFA_FLAGS supersedes the 8-bit flags in the header when present.
+        flags: flags.unwrap_or_else(|| {
+            AddressFlags::from_bits_retain(u32::from(message.header.flags.bits()))
+        }),
+        label,
+        valid_lft: cacheinfo.and_then(|info| lifetime(info.ifa_valid)),
+        preferred_lft: cacheinfo.and_then(|info| lifetime(info.ifa_preferred)),
+        cacheinfo,
+        metric,
+    })
+}
+
+// Options that add `info` back as it was, with its remaining lifetimes.
+fn readd_options(info: AddressInfo) -> AddressOptions {
+    let mut flags = info.flags;
+    // Set by the kernel depending on the other addresses of the subnet.
+    flags.remove(AddressFlags::Secondary);
+    AddressOptions {
+        scope: Some(info.scope),
+        flags,
+        valid_lft: info.valid_lft,
+        preferred_lft: info.preferred_lft,
+        label: info.label,
+        metric: info.metric,
+        broadcast: info.broadcast,
+        ..AddressOptions::default()
+    }
+}
+
+fn apply_address_options(message: &mut AddressMessage, options: &AddressOptions) {
+    if let Some(scope) = options.scope {
+        message.header.scope = scope;
+    }
+    if !options.flags.is_empty() {
+        // The header only has room for the low 8 bits.
+        message.header.flags = AddressHeaderFlags::from_bits_retain(options.flags.bits() as u8);
+        message
+            .attributes
+            .push(AddressAttribute::Flags(options.flags));
+    }
+    if options.valid_lft.is_some() || options.preferred_lft.is_some() {
+        let valid = options.valid_lft.unwrap_or(INFINITY_LIFE_TIME);
+        let mut cacheinfo = CacheInfo::default();
+        cacheinfo.ifa_valid = valid;
+        cacheinfo.ifa_preferred = options.preferred_lft.unwrap_or(valid);
+        message
+            .attributes
+            .push(AddressAttribute::CacheInfo(cacheinfo));
+    }
+    if let Some(metric) = options.metric {
+        message
+            .attributes
+            .push(AddressAttribute::Other(DefaultNla::new(
+                IFA_RT_PRIORITY,
+                metric.to_ne_bytes().to_vec(),
+            )));
+    }
+    if let Some(label) = &options.label {
+        message
+            .attributes
+            .push(AddressAttribute::Label(label.clone()));
+    }
+    if let Some(broadcast) = options.broadcast {
+        message
+            .attributes
+            .retain(|attr| !matches!(attr, AddressAttribute::Broadcast(_)));
+        message
+            .attributes
+            .push(AddressAttribute::Broadcast(broadcast));
+    }
+}
+
+// cstamp and tstamp count hundredths of a second since boot.
+fn cstamp_to_duration(stamp: u32) -> Duration {
+    Duration::from_millis(u64::from(stamp) * 10)
+}
+
+fn is_ipv6_link_local(prefix: &crate::IpNet) -> bool {
+    matches!(prefix, crate::IpNet::V6(net) if net.addr().is_unicast_link_local())
 }
 
 fn build_ipv4_address_message(prefix: &crate::Ipv4Net, if_id: u32) -> AddressMessage {
@@ -210,6 +922,7 @@ fn handle_basic_response(
                 format!("{} not found", operation)
             },
         )),
+        RtnlAddressResponse::Error(err) => Err(err.into_io_error(operation)),
         unexpected => Err(io::Error::other(format!(
             "{} returned unexpected response: {:?}",
             operation, unexpected
@@ -217,24 +930,91 @@ fn handle_basic_response(
     }
 }
 
-pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::AddressHandle) {
+// Rewrites the *ByName requests into their index-based forms.
+async fn resolve_request_name(
+    links: &mut rtnetlink::LinkHandle,
+    req: RtnlAddressRequest,
+) -> Result<RtnlAddressRequest, RtnlAddressResponse> {
+    let if_name = match &req {
+        RtnlAddressRequest::Ipv4AddrSetByName { if_name, .. }
+        | RtnlAddressRequest::Ipv6AddrSetByName { if_name, .. }
+        | RtnlAddressRequest::Ipv4AddrDelByName { if_name, .. }
+        | RtnlAddressRequest::Ipv6AddrDelByName { if_name, .. } => if_name.clone(),
+        _ => return Ok(req),
+    };
+    let response = links.get().match_name(if_name).execute();
+    futures::pin_mut!(response);
+    let if_id = match response.try_next().await {
+        Ok(Some(msg)) => msg.header.index,
+        Ok(None) => return Err(RtnlAddressResponse::NotFound),
+        Err(rtnetlink::Error::NetlinkError(err_msg))
+            if err_msg.to_io().kind() == ErrorKind::NotFound
+                || err_msg.raw_code().abs() == ENODEV =>
+        {
+            return Err(RtnlAddressResponse::NotFound);
+        }
+        Err(err) => return Err(RtnlAddressResponse::Error(RtnlError::from_rtnetlink(&err))),
+    };
+    Ok(match req {
+        RtnlAddressRequest::Ipv4AddrSetByName {
+            prefix, options, ..
+        } => RtnlAddressRequest::Ipv4AddrSet {
+            prefix,
+            if_id,
+            options,
+        },
+        RtnlAddressRequest::Ipv6AddrSetByName {
+            prefix, options, ..
+        } => RtnlAddressRequest::Ipv6AddrSet {
+            prefix,
+            if_id,
+            options,
+        },
+        RtnlAddressRequest::Ipv4AddrDelByName { prefix, .. } => {
+            RtnlAddressRequest::Ipv4AddrDel { prefix, if_id }
+        }
+        RtnlAddressRequest::Ipv6AddrDelByName { prefix, .. } => {
+            RtnlAddressRequest::Ipv6AddrDel { prefix, if_id }
+        }
+        other => other,
+    })
+}
+
+pub(crate) async fn run_server(
+    mut server: Server,
+    handle: rtnetlink::AddressHandle,
+    mut links: rtnetlink::LinkHandle,
+) {
     while let Some((req, respond)) = server.accept().await {
+        let req = match resolve_request_name(&mut links, req).await {
+            Ok(req) => req,
+            Err(response) => {
+                respond(response);
+                continue;
+            }
+        };
         match req {
             RtnlAddressRequest::Ipv4AddrsGet { if_id, scope } => {
                 let if_index = if_id;
                 let mut addrs = Vec::new();
                 let mut req = handle.get();
+                req.message_mut().header.family = AddressFamily::Inet;
                 if if_index != 0 {
                     req = req.set_link_index_filter(if_index);
                 }
-                let response = req.execute();
+                let messages = match req.execute().try_collect::<Vec<_>>().await {
+                    Ok(messages) => messages,
+                    Err(err) => {
+                        respond(RtnlAddressResponse::Error(RtnlError::from_rtnetlink(&err)));
+                        continue;
+                    }
+                };
 
-                futures::pin_mut!(response);
-                while let Ok(Some(response)) = response.try_next().await {
+                for response in messages {
                     if response.header.family != netlink_packet_route::AddressFamily::Inet {
                         continue;
                     }
-                    if scope.map_or(false, |filter| response.header.scope != filter) {
+                    if scope.is_some_and(|filter| response.header.scope != filter) {
                         continue;
                     }
                     for addr in response.attributes.iter() {
@@ -252,17 +1032,23 @@ pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::AddressHan
                 let if_index = if_id;
                 let mut addrs = Vec::new();
                 let mut req = handle.get();
+                req.message_mut().header.family = AddressFamily::Inet6;
                 if if_index != 0 {
                     req = req.set_link_index_filter(if_index);
                 }
-                let response = req.execute();
+                let messages = match req.execute().try_collect::<Vec<_>>().await {
+                    Ok(messages) => messages,
+                    Err(err) => {
+                        respond(RtnlAddressResponse::Error(RtnlError::from_rtnetlink(&err)));
+                        continue;
+                    }
+                };
 
-                futures::pin_mut!(response);
-                while let Ok(Some(response)) = response.try_next().await {
+                for response in messages {
                     if response.header.family != netlink_packet_route::AddressFamily::Inet6 {
                         continue;
                     }
-                    if scope.map_or(false, |filter| response.header.scope != filter) {
+                    if scope.is_some_and(|filter| response.header.scope != filter) {
                         continue;
                     }
                     for addr in response.attributes.iter() {
@@ -276,7 +1062,28 @@ pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::AddressHan
                 }
                 respond(RtnlAddressResponse::Ipv6Addrs(addrs));
             }
-            RtnlAddressRequest::Ipv4AddrSet { prefix, if_id } => {
+            RtnlAddressRequest::AddrsList { if_id } => {
+                let mut req = handle.get();
+                if if_id != 0 {
+                    req = req.set_link_index_filter(if_id);
+                }
+                match req.execute().try_collect::<Vec<_>>().await {
+                    Ok(messages) => respond(RtnlAddressResponse::AddrInfos(
+                        messages
+                            .iter()
+                            .filter_map(address_info_from_message)
+                            .collect(),
+                    )),
+                    Err(err) => {
+                        respond(RtnlAddressResponse::Error(RtnlError::from_rtnetlink(&err)));
+                    }
+                }
+            }
+            RtnlAddressRequest::Ipv4AddrSet {
+                prefix,
+                if_id,
+                options,
+            } => {
                 if if_id == 0 {
                     respond(RtnlAddressResponse::Failed);
                     continue;
@@ -284,17 +1091,25 @@ pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::AddressHan
 
                 let addr = prefix.addr();
                 let prefix_len = prefix.prefix_len();
-                let result = handle
-                    .add(if_id, IpAddr::V4(addr), prefix_len)
-                    .execute()
-                    .await;
+                let mut req = handle.add(if_id, IpAddr::V4(addr), prefix_len);
+                if options.replace {
+                    req = req.replace();
+                }
+                apply_address_options(req.message_mut(), &options);
+                let result = req.execute().await;
 
                 match result {
                     Ok(()) => respond(RtnlAddressResponse::Success),
                     Err(rtnetlink::Error::NetlinkError(err_msg))
                         if err_msg.to_io().kind() == ErrorKind::AlreadyExists =>
                     {
-                        respond(RtnlAddressResponse::Success);
+                        if options.strict {
+                            respond(RtnlAddressResponse::Error(RtnlError::from_netlink(
+                                &err_msg,
+                            )));
+                        } else {
+                            respond(RtnlAddressResponse::Success);
+                        }
                     }
                     Err(err) => {
                         tracing::warn!(
@@ -304,11 +1119,15 @@ pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::AddressHan
                             if_id,
                             err,
                         );
-                        respond(RtnlAddressResponse::Failed);
+                        respond(RtnlAddressResponse::Error(RtnlError::from_rtnetlink(&err)));
                     }
                 }
             }
-            RtnlAddressRequest::Ipv6AddrSet { prefix, if_id } => {
+            RtnlAddressRequest::Ipv6AddrSet {
+                prefix,
+                if_id,
+                options,
+            } => {
                 if if_id == 0 {
                     respond(RtnlAddressResponse::Failed);
                     continue;
@@ -316,17 +1135,25 @@ pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::AddressHan
 
                 let addr = prefix.addr();
                 let prefix_len = prefix.prefix_len();
-                let result = handle
-                    .add(if_id, IpAddr::V6(addr), prefix_len)
-                    .execute()
-                    .await;
+                let mut req = handle.add(if_id, IpAddr::V6(addr), prefix_len);
+                if options.replace {
+                    req = req.replace();
+                }
+                apply_address_options(req.message_mut(), &options);
+                let result = req.execute().await;
 
                 match result {
                     Ok(()) => respond(RtnlAddressResponse::Success),
                     Err(rtnetlink::Error::NetlinkError(err_msg))
                         if err_msg.to_io().kind() == ErrorKind::AlreadyExists =>
                     {
-                        respond(RtnlAddressResponse::Success);
+                        if options.strict {
+                            respond(RtnlAddressResponse::Error(RtnlError::from_netlink(
+                                &err_msg,
+                            )));
+                        } else {
+                            respond(RtnlAddressResponse::Success);
+                        }
                     }
                     Err(err) => {
                         tracing::warn!(
@@ -336,7 +1163,7 @@ pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::AddressHan
                             if_id,
                             err,
                         );
-                        respond(RtnlAddressResponse::Failed);
+                        respond(RtnlAddressResponse::Error(RtnlError::from_rtnetlink(&err)));
                     }
                 }
             }
@@ -369,7 +1196,9 @@ pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::AddressHan
                                 if_id,
                                 err_msg,
                             );
-                            respond(RtnlAddressResponse::Failed);
+                            respond(RtnlAddressResponse::Error(RtnlError::from_netlink(
+                                &err_msg,
+                            )));
                         }
                     }
                     Err(err) => {
@@ -380,7 +1209,7 @@ pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::AddressHan
                             if_id,
                             err,
                         );
-                        respond(RtnlAddressResponse::Failed);
+                        respond(RtnlAddressResponse::Error(RtnlError::from_rtnetlink(&err)));
                     }
                 }
             }
@@ -413,7 +1242,9 @@ pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::AddressHan
                                 if_id,
                                 err_msg,
                             );
-                            respond(RtnlAddressResponse::Failed);
+                            respond(RtnlAddressResponse::Error(RtnlError::from_netlink(
+                                &err_msg,
+                            )));
                         }
                     }
                     Err(err) => {
@@ -424,10 +1255,61 @@ pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::AddressHan
                             if_id,
                             err,
                         );
-                        respond(RtnlAddressResponse::Failed);
+                        respond(RtnlAddressResponse::Error(RtnlError::from_rtnetlink(&err)));
                     }
                 }
             }
+            RtnlAddressRequest::AddrFlush { if_id, filter } => {
+                if if_id == 0 {
+                    respond(RtnlAddressResponse::Failed);
+                    continue;
+                }
+
+                let mut req = handle.get();
+                if let Some(family) = filter.family {
+                    req.message_mut().header.family = family.address_family();
+                }
+                let messages = match req
+                    .set_link_index_filter(if_id)
+                    .execute()
+                    .try_collect::<Vec<_>>()
+                    .await
+                {
+                    Ok(messages) => messages,
+                    Err(err) => {
+                        respond(RtnlAddressResponse::Error(RtnlError::from_rtnetlink(&err)));
+                        continue;
+                    }
+                };
+
+                let mut failure = None;
+                for message in messages {
+                    if message.header.index != if_id
+                        || !address_info_from_message(&message)
+                            .is_some_and(|info| filter.matches(&info))
+                    {
+                        continue;
+                    }
+                    match handle.del(message).execute().await {
+                        Ok(()) => {}
+                        // Secondaries go away together with their primary.
+                        Err(rtnetlink::Error::NetlinkError(err_msg))
+                            if matches!(
+                                err_msg.to_io().kind(),
+                                ErrorKind::AddrNotAvailable | ErrorKind::NotFound
+                            ) => {}
+                        Err(err) => {
+                            tracing::warn!("Failed to flush address on ifindex {}: {}", if_id, err);
+                            failure = Some(RtnlError::from_rtnetlink(&err));
+                            break;
+                        }
+                    }
+                }
+                match failure {
+                    Some(err) => respond(RtnlAddressResponse::Error(err)),
+                    None => respond(RtnlAddressResponse::Success),
+                }
+            }
             _ => respond(RtnlAddressResponse::NotImplemented),
         }
     }
@@ -0,0 +1,155 @@
+use std::fmt::{self, Display};
+use std::io::{self, ErrorKind};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtnlError {
+    operation: Option<String>,
+    message: String,
+    errno: Option<i32>,
+    ext_ack: Option<String>,
+}
+
+impl RtnlError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            operation: None,
+            message: message.into(),
+            errno: None,
+            ext_ack: None,
+        }
+    }
+
+    pub fn from_io_error(err: &io::Error) -> Option<&RtnlError> {
+        err.get_ref()?.downcast_ref::<RtnlError>()
+    }
+
+    pub fn operation(&self) -> Option<&str> {
+        self.operation.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn errno(&self) -> Option<i32> {
+        self.errno
+    }
+
+    pub fn ext_ack(&self) -> Option<&str> {
+        self.ext_ack.as_deref()
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self.errno {
+            Some(errno) => io::Error::from_raw_os_error(errno).kind(),
+            None => ErrorKind::Other,
+        }
+    }
+}
+
+// Only the subsystem modules build errors from netlink replies.
+#[cfg(any(
+    feature = "address",
+    feature = "link",
+    feature = "neighbor",
+    feature = "nexthop",
+    feature = "route",
+    feature = "rule",
+    feature = "virtual-interface"
+))]
+impl RtnlError {
+    pub(crate) fn from_netlink(err: &netlink_packet_core::ErrorMessage) -> Self {
+        Self {
+            operation: None,
+            message: err.to_io().to_string(),
+            errno: Some(err.raw_code().abs()),
+            ext_ack: ext_ack::parse_ext_ack(&err.header),
+        }
+    }
+
+    #[cfg(any(
+        feature = "address",
+        feature = "link",
+        feature = "neighbor",
+        feature = "route",
+        feature = "rule",
+        feature = "virtual-interface"
+    ))]
+    pub(crate) fn from_rtnetlink(err: &rtnetlink::Error) -> Self {
+        match err {
+            rtnetlink::Error::NetlinkError(err_msg) => Self::from_netlink(err_msg),
+            other => Self::new(other.to_string()),
+        }
+    }
+
+    pub(crate) fn into_io_error(mut self, operation: &str) -> io::Error {
+        self.operation = Some(operation.to_owned());
+        io::Error::new(self.kind(), self)
+    }
+}
+
+impl Display for RtnlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(operation) = &self.operation {
+            write!(f, "{} failed: ", operation)?;
+        }
+        f.write_str(&self.message)?;
+        if let Some(ext_ack) = &self.ext_ack {
+            write!(f, " ({})", ext_ack)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RtnlError {}
+
+#[cfg(any(
+    feature = "address",
+    feature = "link",
+    feature = "neighbor",
+    feature = "nexthop",
+    feature = "route",
+    feature = "rule",
+    feature = "virtual-interface"
+))]
+mod ext_ack {
+    const NLMSG_HDRLEN: usize = 16;
+    const NLA_HEADER_LEN: usize = 4;
+    const NLA_TYPE_MASK: u16 = 0x3fff;
+    const NLMSGERR_ATTR_MSG: u16 = 1;
+
+    // The NLMSG_ERROR payload echoes the offending request, followed by the
+    // extended ack attributes when NETLINK_EXT_ACK is enabled on the socket.
+    pub(super) fn parse_ext_ack(payload: &[u8]) -> Option<String> {
+        if payload.len() < NLMSG_HDRLEN {
+            return None;
+        }
+        let request_len = u32::from_ne_bytes(payload[0..4].try_into().ok()?) as usize;
+        let mut offset = if payload.len() >= request_len {
+            align_nla(request_len)
+        } else {
+            NLMSG_HDRLEN
+        };
+
+        while offset + NLA_HEADER_LEN <= payload.len() {
+            let len = u16::from_ne_bytes([payload[offset], payload[offset + 1]]) as usize;
+            let kind =
+                u16::from_ne_bytes([payload[offset + 2], payload[offset + 3]]) & NLA_TYPE_MASK;
+            if len < NLA_HEADER_LEN || offset + len > payload.len() {
+                return None;
+            }
+            if kind == NLMSGERR_ATTR_MSG {
+                let value = &payload[offset + NLA_HEADER_LEN..offset + len];
+                let value = value.split(|b| *b == 0).next().unwrap_or_default();
+                let text = String::from_utf8_lossy(value).trim().to_owned();
+                return if text.is_empty() { None } else { Some(text) };
+            }
+            offset += align_nla(len);
+        }
+        None
+    }
+
+    fn align_nla(len: usize) -> usize {
+        (len + 3) & !3
+    }
+}
@@ -0,0 +1,53 @@
+use std::io::{self, ErrorKind};
+
+use crate::virtual_interface::{VirtualInterfaceKind, VirtualInterfaceSpec};
+
+mod dslite;
+mod hub;
+mod mape;
+mod multiwan;
+mod proxy_ndp;
+mod sixrd;
+mod wan;
+
+pub use dslite::{
+    DsLiteConfig, DsLiteTunnel, dslite_setup, dslite_setup_with_config, dslite_teardown,
+};
+pub use hub::{Hub, HubConfig, HubReport, SpokeSpec};
+pub use mape::{MapEConfig, MapEInterfaceId, MapEParams, MapERule, MapETunnel, mape_setup};
+pub use multiwan::{MultiWan, MultiWanConfig, Uplink};
+pub use proxy_ndp::{ProxyNdp, ProxyNdpConfig};
+pub use sixrd::{
+    SixRd, SixRdConfig, SixRdLan, SixRdRule, SixRdTunnel, sixrd_setup, sixrd_setup_with_addr,
+};
+pub use wan::{WanEvent, WanTracker, WanTrackerConfig};
+
+fn ignore_exists(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => Ok(()),
+        other => other,
+    }
+}
+
+fn ignore_not_found(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        other => other,
+    }
+}
+
+// `ensure` only sets the admin state when it creates or reconfigures the
+// tunnel, so callers bring it up afterwards in case a previous run left an
+// unchanged one down.
+fn tunnel_spec(name: &str, kind: VirtualInterfaceKind) -> VirtualInterfaceSpec {
+    VirtualInterfaceSpec {
+        name: name.to_string(),
+        kind,
+        admin_up: true,
+        num_tx_queues: None,
+        num_rx_queues: None,
+        mtu: None,
+        mac_addr: None,
+        master: None,
+    }
+}
@@ -0,0 +1,148 @@
+use std::io::{self, ErrorKind};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use netlink_packet_route::address::AddressScope;
+use netlink_packet_route::route::RouteType;
+
+use crate::route::{Ipv4Route, RouteMetrics, RouteStatus};
+use crate::virtual_interface::{
+    Ip6TnlConfig, Ip6TnlMode, Ip6TunnelFlags, VirtualInterfaceDelete, VirtualInterfaceKind,
+};
+use crate::{Ipv4Net, RtnlClient};
+
+use super::{ignore_exists, ignore_not_found, tunnel_spec};
+
+// RFC 6333 reserves 192.0.0.0/29 for the B4-AFTR softwire.
+const B4_IPV4_PREFIX: Ipv4Net = Ipv4Net::new_assert(Ipv4Addr::new(192, 0, 0, 2), 29);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DsLiteConfig {
+    pub tunnel_name: String,
+    pub aftr_addr: Ipv6Addr,
+    pub wan_if: String,
+    pub b4_addr: Option<Ipv6Addr>,
+    pub mtu: Option<u32>,
+    pub route_metric: Option<u32>,
+    pub route_table: Option<u32>,
+}
+
+impl DsLiteConfig {
+    pub fn new(aftr_addr: Ipv6Addr, wan_if: &str) -> Self {
+        Self {
+            tunnel_name: "dslite0".to_string(),
+            aftr_addr,
+            wan_if: wan_if.to_string(),
+            b4_addr: None,
+            mtu: None,
+            route_metric: None,
+            route_table: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DsLiteTunnel {
+    pub tunnel_name: String,
+    pub if_id: u32,
+    pub wan_if_id: u32,
+    pub b4_addr: Ipv6Addr,
+    pub aftr_addr: Ipv6Addr,
+    pub default_route: Ipv4Route,
+}
+
+impl DsLiteTunnel {
+    pub fn teardown(&self, client: &RtnlClient) -> io::Result<()> {
+        ignore_not_found(client.route().ipv4_route_del(self.default_route.clone()))?;
+        ignore_not_found(
+            client
+                .virtual_interface()
+                .delete(VirtualInterfaceDelete::ByIndex(self.if_id)),
+        )
+    }
+}
+
+pub fn dslite_setup(aftr_addr: Ipv6Addr, wan_if: &str) -> io::Result<DsLiteTunnel> {
+    dslite_setup_with_config(&RtnlClient::new(), &DsLiteConfig::new(aftr_addr, wan_if))
+}
+
+pub fn dslite_teardown(tunnel: &DsLiteTunnel) -> io::Result<()> {
+    tunnel.teardown(&RtnlClient::new())
+}
+
+pub fn dslite_setup_with_config(
+    client: &RtnlClient,
+    config: &DsLiteConfig,
+) -> io::Result<DsLiteTunnel> {
+    let wan_if_id = client.link().interface_get_by_name(&config.wan_if)?.if_id;
+    let b4_addr = match config.b4_addr {
+        Some(addr) => addr,
+        None => client
+            .address()
+            .ipv6_addrs_get_with_scope(Some(wan_if_id), Some(AddressScope::Universe))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::AddrNotAvailable,
+                    format!("No global IPv6 address on {}", config.wan_if),
+                )
+            })?,
+    };
+
+    let kind = VirtualInterfaceKind::Ip6Tnl(Ip6TnlConfig {
+        local: b4_addr,
+        remote: config.aftr_addr,
+        hop_limit: None,
+        traffic_class: None,
+        flow_label: None,
+        encap_limit: None,
+        pmtudisc: false,
+        link: Some(wan_if_id),
+        mode: Some(Ip6TnlMode::IpIp6),
+        flags: Ip6TunnelFlags::default(),
+        external: false,
+    });
+
+    let (if_id, _) = client
+        .virtual_interface()
+        .ensure(tunnel_spec(&config.tunnel_name, kind))?;
+    client.link().interface_set_up(if_id)?;
+
+    if let Some(mtu) = config.mtu {
+        client.link().interface_set_mtu(if_id, mtu)?;
+    }
+
+    ignore_exists(client.address().ipv4_addr_set(if_id, B4_IPV4_PREFIX))?;
+
+    let default_route = Ipv4Route {
+        if_id: Some(if_id),
+        if_name: None,
+        gateway: None,
+        source: None,
+        metric: config.route_metric,
+        tos: 0,
+        table: config.route_table,
+        route: Ipv4Net::default(),
+        nexthops: Vec::new(),
+        nexthop_id: None,
+        kind: RouteType::Unicast,
+        protocol: None,
+        mtu: None,
+        metrics: RouteMetrics::default(),
+        onlink: false,
+        encap: None,
+        status: RouteStatus::default(),
+        cache_info: None,
+        extra: Vec::new(),
+    };
+    client.route().ipv4_route_replace(default_route.clone())?;
+
+    Ok(DsLiteTunnel {
+        tunnel_name: config.tunnel_name.clone(),
+        if_id,
+        wan_if_id,
+        b4_addr,
+        aftr_addr: config.aftr_addr,
+        default_route,
+    })
+}
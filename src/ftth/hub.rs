@@ -0,0 +1,375 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, ErrorKind};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use netlink_packet_route::route::{RouteProtocol, RouteType};
+
+use crate::route::{
+    IpRoute, Ipv4Route, Ipv6Route, RT_TABLE_MAIN, RouteMetrics, RoutePlan, RouteSet, RouteStatus,
+};
+use crate::virtual_interface::{
+    EnsureAction, GreConfig, GreFlags, Ip6TnlConfig, Ip6TnlMode, Ip6TunnelFlags,
+    VirtualInterfaceDelete, VirtualInterfaceInfo, VirtualInterfaceKind,
+};
+use crate::{IpNet, RtnlClient};
+
+use super::{ignore_not_found, tunnel_spec};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpokeSpec {
+    pub name: String,
+    pub kind: VirtualInterfaceKind,
+    pub routes: Vec<IpNet>,
+}
+
+impl SpokeSpec {
+    pub fn gre(name: &str, local: Ipv4Addr, remote: Ipv4Addr, routes: Vec<IpNet>) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: VirtualInterfaceKind::Gre(GreConfig {
+                local,
+                remote,
+                ttl: None,
+                tos: None,
+                key: None,
+                ikey: None,
+                okey: None,
+                iflags: GreFlags::default(),
+                oflags: GreFlags::default(),
+                encap_limit: None,
+                pmtudisc: true,
+                ignore_df: false,
+                link: None,
+                external: false,
+            }),
+            routes,
+        }
+    }
+
+    pub fn ip6tnl(name: &str, local: Ipv6Addr, remote: Ipv6Addr, routes: Vec<IpNet>) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: VirtualInterfaceKind::Ip6Tnl(Ip6TnlConfig {
+                local,
+                remote,
+                hop_limit: None,
+                traffic_class: None,
+                flow_label: None,
+                encap_limit: None,
+                pmtudisc: false,
+                link: None,
+                mode: Some(Ip6TnlMode::Any),
+                flags: Ip6TunnelFlags::default(),
+                external: false,
+            }),
+            routes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HubConfig {
+    // GRE and ip6tnl tunnels whose name starts with this prefix are owned by
+    // the hub; any of them missing from the applied spec list is removed.
+    pub name_prefix: String,
+    pub route_table: Option<u32>,
+    pub route_metric: Option<u32>,
+}
+
+impl HubConfig {
+    pub fn new(name_prefix: &str) -> Self {
+        Self {
+            name_prefix: name_prefix.to_string(),
+            route_table: None,
+            route_metric: None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct HubReport {
+    pub configured: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub removed: Vec<String>,
+    pub failed: Vec<(String, io::Error)>,
+}
+
+#[derive(Debug)]
+pub struct Hub {
+    client: RtnlClient,
+    config: HubConfig,
+    applied: HashMap<String, (u32, SpokeSpec)>,
+}
+
+impl Hub {
+    pub fn new(client: &RtnlClient, config: HubConfig) -> Self {
+        Self {
+            client: client.clone(),
+            config,
+            applied: HashMap::new(),
+        }
+    }
+
+    pub fn spokes(&self) -> impl Iterator<Item = &SpokeSpec> {
+        self.applied.values().map(|(_, spec)| spec)
+    }
+
+    // Brings the set of spoke tunnels in line with `spokes`. Missing tunnels
+    // are created in one batch, the others reconciled against a single link
+    // dump, and the routes on all spokes diffed against those installed.
+    // Unchanged tunnels keep their admin state, as bringing each one up
+    // would cost a request per spoke. A failure on one spoke is recorded in
+    // the report and does not stop the others; it is retried on the next
+    // call.
+    pub fn apply(&mut self, spokes: Vec<SpokeSpec>) -> io::Result<HubReport> {
+        let mut names = HashSet::new();
+        for spec in &spokes {
+            if !spec.name.starts_with(&self.config.name_prefix) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Spoke {} does not start with {}",
+                        spec.name, self.config.name_prefix
+                    ),
+                ));
+            }
+            if !is_spoke_kind(&spec.kind) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Spoke {} is neither GRE nor ip6tnl", spec.name),
+                ));
+            }
+            if !names.insert(spec.name.as_str()) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Duplicate spoke {}", spec.name),
+                ));
+            }
+        }
+
+        let mut report = HubReport::default();
+        let mut existing: HashMap<String, VirtualInterfaceInfo> = self
+            .client
+            .virtual_interface()
+            .list(None)?
+            .into_iter()
+            .filter(|info| {
+                info.name.starts_with(&self.config.name_prefix) && is_spoke_kind(&info.kind)
+            })
+            .map(|info| (info.name.clone(), info))
+            .collect();
+
+        for (name, info) in &existing {
+            if names.contains(name.as_str()) {
+                continue;
+            }
+            self.applied.remove(name);
+            match self.delete_tunnel(info.if_id) {
+                Ok(()) => report.removed.push(name.clone()),
+                Err(err) => report.failed.push((name.clone(), err)),
+            }
+        }
+        existing.retain(|name, _| names.contains(name.as_str()));
+
+        // Spokes whose tunnel is in place, with whether anything changed.
+        let mut ready: Vec<(SpokeSpec, u32, bool)> = Vec::new();
+        let mut missing = Vec::new();
+        for spec in spokes {
+            self.applied.remove(&spec.name);
+            let Some(info) = existing.get(&spec.name) else {
+                missing.push(spec);
+                continue;
+            };
+            if info.kind.interface_type() != spec.kind.interface_type() {
+                match self.delete_tunnel(info.if_id) {
+                    Ok(()) => missing.push(spec),
+                    Err(err) => report.failed.push((spec.name, err)),
+                }
+                continue;
+            }
+            let tunnel = tunnel_spec(&spec.name, spec.kind.clone());
+            match self.client.virtual_interface().ensure_listed(tunnel, info) {
+                Ok(action) => {
+                    let if_id = info.if_id;
+                    ready.push((spec, if_id, action != EnsureAction::Unchanged));
+                }
+                Err(err) => report.failed.push((spec.name, err)),
+            }
+        }
+
+        if !missing.is_empty() {
+            let tunnels = missing
+                .iter()
+                .map(|spec| tunnel_spec(&spec.name, spec.kind.clone()))
+                .collect();
+            let created = self.client.virtual_interface().create_many(tunnels)?;
+            for (spec, result) in missing.into_iter().zip(created) {
+                match result {
+                    Ok(if_id) => ready.push((spec, if_id, true)),
+                    Err(err) => report.failed.push((spec.name, err)),
+                }
+            }
+        }
+
+        let plan = RouteSet::diff(&self.installed_routes(&ready)?, &self.wanted_routes(&ready));
+        let mut route_errors: HashMap<u32, io::Error> = HashMap::new();
+        let mut routes_changed = HashSet::new();
+        for (if_id, result) in self.apply_plan(plan) {
+            match result {
+                Ok(()) => {
+                    routes_changed.insert(if_id);
+                }
+                Err(err) => {
+                    route_errors.entry(if_id).or_insert(err);
+                }
+            }
+        }
+
+        for (spec, if_id, changed) in ready {
+            if let Some(err) = route_errors.remove(&if_id) {
+                report.failed.push((spec.name, err));
+                continue;
+            }
+            if changed || routes_changed.contains(&if_id) {
+                report.configured.push(spec.name.clone());
+            } else {
+                report.unchanged.push(spec.name.clone());
+            }
+            self.applied.insert(spec.name.clone(), (if_id, spec));
+        }
+        Ok(report)
+    }
+
+    pub fn teardown(&mut self) -> io::Result<()> {
+        for (_, (if_id, _)) in self.applied.drain() {
+            ignore_not_found(
+                self.client
+                    .virtual_interface()
+                    .delete(VirtualInterfaceDelete::ByIndex(if_id)),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn delete_tunnel(&self, if_id: u32) -> io::Result<()> {
+        ignore_not_found(
+            self.client
+                .virtual_interface()
+                .delete(VirtualInterfaceDelete::ByIndex(if_id)),
+        )
+    }
+
+    // Routes the hub installed on the given spokes. Routes the kernel adds
+    // by itself, such as IPv6 link-local prefixes, and those in other tables
+    // are left alone.
+    fn installed_routes(&self, spokes: &[(SpokeSpec, u32, bool)]) -> io::Result<RouteSet> {
+        let if_ids: HashSet<u32> = spokes.iter().map(|(_, if_id, _)| *if_id).collect();
+        let table = self.config.route_table.unwrap_or(RT_TABLE_MAIN);
+        let owned = |if_id: Option<u32>, route_table: Option<u32>, protocol| {
+            if_id.is_some_and(|if_id| if_ids.contains(&if_id))
+                && route_table.unwrap_or(RT_TABLE_MAIN) == table
+                && protocol != Some(RouteProtocol::Kernel)
+        };
+        Ok(self
+            .client
+            .route()
+            .route_list()?
+            .into_iter()
+            .filter(|route| match route {
+                IpRoute::V4(route) => owned(route.if_id, route.table, route.protocol),
+                IpRoute::V6(route) => owned(route.if_id, route.table, route.protocol),
+            })
+            .collect())
+    }
+
+    fn wanted_routes(&self, spokes: &[(SpokeSpec, u32, bool)]) -> RouteSet {
+        spokes
+            .iter()
+            .flat_map(|(spec, if_id, _)| {
+                spec.routes.iter().map(|route| match route {
+                    IpNet::V4(route) => IpRoute::V4(self.ipv4_route(*if_id, *route)),
+                    IpNet::V6(route) => IpRoute::V6(self.ipv6_route(*if_id, *route)),
+                })
+            })
+            .collect()
+    }
+
+    // Deletions go first so that a replaced route never briefly coexists
+    // with a stale one. Each result is keyed by the spoke's interface.
+    fn apply_plan(&self, plan: RoutePlan) -> Vec<(u32, io::Result<()>)> {
+        let routes = self.client.route();
+        let mut results = Vec::new();
+        for route in plan.delete.ipv4 {
+            let if_id = route.if_id.unwrap_or_default();
+            results.push((if_id, ignore_not_found(routes.ipv4_route_del(route))));
+        }
+        for route in plan.delete.ipv6 {
+            let if_id = route.if_id.unwrap_or_default();
+            results.push((if_id, ignore_not_found(routes.ipv6_route_del(route))));
+        }
+        for route in plan.replace.ipv4.into_iter().chain(plan.add.ipv4) {
+            let if_id = route.if_id.unwrap_or_default();
+            results.push((if_id, routes.ipv4_route_replace(route)));
+        }
+        for route in plan.replace.ipv6.into_iter().chain(plan.add.ipv6) {
+            let if_id = route.if_id.unwrap_or_default();
+            results.push((if_id, routes.ipv6_route_replace(route)));
+        }
+        results
+    }
+
+    fn ipv4_route(&self, if_id: u32, route: crate::Ipv4Net) -> Ipv4Route {
+        Ipv4Route {
+            if_id: Some(if_id),
+            if_name: None,
+            gateway: None,
+            source: None,
+            metric: self.config.route_metric,
+            tos: 0,
+            table: self.config.route_table,
+            route: route.trunc(),
+            nexthops: Vec::new(),
+            nexthop_id: None,
+            kind: RouteType::Unicast,
+            protocol: None,
+            mtu: None,
+            metrics: RouteMetrics::default(),
+            onlink: false,
+            encap: None,
+            status: RouteStatus::default(),
+            cache_info: None,
+            extra: Vec::new(),
+        }
+    }
+
+    fn ipv6_route(&self, if_id: u32, route: crate::Ipv6Net) -> Ipv6Route {
+        Ipv6Route {
+            if_id: Some(if_id),
+            if_name: None,
+            gateway: None,
+            source: None,
+            metric: self.config.route_metric,
+            table: self.config.route_table,
+            route: route.trunc(),
+            nexthops: Vec::new(),
+            nexthop_id: None,
+            kind: RouteType::Unicast,
+            protocol: None,
+            mtu: None,
+            metrics: RouteMetrics::default(),
+            expires: None,
+            onlink: false,
+            encap: None,
+            status: RouteStatus::default(),
+            cache_info: None,
+            extra: Vec::new(),
+        }
+    }
+}
+
+fn is_spoke_kind(kind: &VirtualInterfaceKind) -> bool {
+    matches!(
+        kind,
+        VirtualInterfaceKind::Gre(_) | VirtualInterfaceKind::Ip6Tnl(_)
+    )
+}
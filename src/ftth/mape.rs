@@ -0,0 +1,390 @@
+use std::io::{self, ErrorKind};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ops::RangeInclusive;
+
+use netlink_packet_route::address::AddressScope;
+use netlink_packet_route::route::RouteType;
+
+use crate::route::{Ipv4Route, RouteMetrics, RouteStatus};
+use crate::virtual_interface::{
+    Ip6TnlConfig, Ip6TnlMode, Ip6TunnelFlags, VirtualInterfaceDelete, VirtualInterfaceKind,
+};
+use crate::{Ipv4Net, Ipv6Net, RtnlClient};
+
+use super::{ignore_exists, ignore_not_found, tunnel_spec};
+
+const DEFAULT_PSID_OFFSET: u8 = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapERule {
+    pub br_addr: Ipv6Addr,
+    pub ipv6_prefix: Ipv6Net,
+    pub ipv4_prefix: Ipv4Net,
+    pub ea_bits_len: u8,
+    pub psid_offset: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapEInterfaceId {
+    // RFC 7597 section 6: 16 zero bits, IPv4 address, PSID.
+    Rfc7597,
+    // draft-ietf-softwire-map-03, still used by Japanese FTTH operators:
+    // 8 zero bits, IPv4 address, PSID, 8 zero bits.
+    Draft03,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapEParams {
+    pub ipv4_addr: Ipv4Addr,
+    pub psid: u16,
+    pub psid_len: u8,
+    pub psid_offset: u8,
+    pub ce_addr: Ipv6Addr,
+}
+
+impl MapERule {
+    pub fn new(
+        br_addr: Ipv6Addr,
+        ipv6_prefix: Ipv6Net,
+        ipv4_prefix: Ipv4Net,
+        ea_bits_len: u8,
+    ) -> Self {
+        Self {
+            br_addr,
+            ipv6_prefix,
+            ipv4_prefix,
+            ea_bits_len,
+            psid_offset: DEFAULT_PSID_OFFSET,
+        }
+    }
+
+    pub fn end_user_prefix_len(&self) -> u8 {
+        self.ipv6_prefix.prefix_len() + self.ea_bits_len
+    }
+
+    pub fn derive(
+        &self,
+        end_user_prefix: Ipv6Net,
+        interface_id: MapEInterfaceId,
+    ) -> io::Result<MapEParams> {
+        let rule_len = self.ipv6_prefix.prefix_len() as u32;
+        let ea_len = self.ea_bits_len as u32;
+        let suffix_len = 32 - self.ipv4_prefix.prefix_len() as u32;
+        if !self.ipv6_prefix.contains(&end_user_prefix) {
+            return Err(invalid_input(format!(
+                "{} is not within the MAP rule prefix {}",
+                end_user_prefix, self.ipv6_prefix
+            )));
+        }
+        if rule_len + ea_len > 64 || (end_user_prefix.prefix_len() as u32) < rule_len + ea_len {
+            return Err(invalid_input(format!(
+                "EA bits do not fit in the end-user prefix {}",
+                end_user_prefix
+            )));
+        }
+        if ea_len < suffix_len {
+            return Err(invalid_input("EA bits are shorter than the IPv4 suffix"));
+        }
+        let psid_len = ea_len - suffix_len;
+        if psid_len + self.psid_offset as u32 > 16 {
+            return Err(invalid_input("PSID offset and length exceed 16 bits"));
+        }
+
+        let prefix_bits = u128::from(end_user_prefix.trunc().addr());
+        let ea_bits = if ea_len == 0 {
+            0
+        } else {
+            ((prefix_bits << rule_len) >> (128 - ea_len)) as u64
+        };
+        let ipv4_suffix = (ea_bits >> psid_len) as u32;
+        let psid = (ea_bits & ((1u64 << psid_len) - 1)) as u16;
+        let ipv4_addr = Ipv4Addr::from(u32::from(self.ipv4_prefix.network()) | ipv4_suffix);
+
+        let iid = match interface_id {
+            MapEInterfaceId::Rfc7597 => ((u32::from(ipv4_addr) as u64) << 16) | psid as u64,
+            MapEInterfaceId::Draft03 => {
+                ((u32::from(ipv4_addr) as u64) << 24) | ((psid as u64) << 8)
+            }
+        };
+        let subnet = prefix_bits & !((1u128 << 64) - 1);
+        let ce_addr = Ipv6Addr::from(subnet | iid as u128);
+
+        Ok(MapEParams {
+            ipv4_addr,
+            psid,
+            psid_len: psid_len as u8,
+            psid_offset: self.psid_offset,
+            ce_addr,
+        })
+    }
+}
+
+impl MapEParams {
+    pub fn port_ranges(&self) -> Vec<RangeInclusive<u16>> {
+        let offset = self.psid_offset as u32;
+        let psid_len = self.psid_len as u32;
+        if psid_len == 0 {
+            return vec![0..=u16::MAX];
+        }
+        let m = 16 - offset - psid_len;
+        let size = 1u32 << m;
+        let first_block = if offset == 0 { 0 } else { 1 };
+        (first_block..(1u32 << offset))
+            .map(|a| {
+                let start = (a << (16 - offset)) | ((self.psid as u32) << m);
+                start as u16..=(start + size - 1) as u16
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapEConfig {
+    pub tunnel_name: String,
+    pub wan_if: String,
+    pub rule: MapERule,
+    pub end_user_prefix: Option<Ipv6Net>,
+    pub interface_id: MapEInterfaceId,
+    pub mtu: Option<u32>,
+    pub route_metric: Option<u32>,
+    pub route_table: Option<u32>,
+}
+
+impl MapEConfig {
+    pub fn new(rule: MapERule, wan_if: &str) -> Self {
+        Self {
+            tunnel_name: "mape0".to_string(),
+            wan_if: wan_if.to_string(),
+            rule,
+            end_user_prefix: None,
+            interface_id: MapEInterfaceId::Rfc7597,
+            mtu: None,
+            route_metric: None,
+            route_table: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapETunnel {
+    pub tunnel_name: String,
+    pub if_id: u32,
+    pub wan_if_id: u32,
+    pub params: MapEParams,
+    pub default_route: Ipv4Route,
+}
+
+impl MapETunnel {
+    pub fn teardown(&self, client: &RtnlClient) -> io::Result<()> {
+        ignore_not_found(client.route().ipv4_route_del(self.default_route.clone()))?;
+        ignore_not_found(
+            client
+                .virtual_interface()
+                .delete(VirtualInterfaceDelete::ByIndex(self.if_id)),
+        )?;
+        let ce_prefix = Ipv6Net::from(self.params.ce_addr);
+        ignore_not_found(client.address().ipv6_addr_del(self.wan_if_id, ce_prefix))
+    }
+}
+
+pub fn mape_setup(client: &RtnlClient, config: &MapEConfig) -> io::Result<MapETunnel> {
+    let wan_if_id = client.link().interface_get_by_name(&config.wan_if)?.if_id;
+    let end_user_prefix = match config.end_user_prefix {
+        Some(prefix) => prefix,
+        None => find_end_user_prefix(client, wan_if_id, &config.rule)?,
+    };
+    let params = config.rule.derive(end_user_prefix, config.interface_id)?;
+
+    ignore_exists(
+        client
+            .address()
+            .ipv6_addr_set(wan_if_id, Ipv6Net::from(params.ce_addr)),
+    )?;
+
+    let kind = VirtualInterfaceKind::Ip6Tnl(Ip6TnlConfig {
+        local: params.ce_addr,
+        remote: config.rule.br_addr,
+        hop_limit: None,
+        traffic_class: None,
+        flow_label: None,
+        encap_limit: None,
+        pmtudisc: false,
+        link: Some(wan_if_id),
+        mode: Some(Ip6TnlMode::IpIp6),
+        flags: Ip6TunnelFlags::default(),
+        external: false,
+    });
+
+    let (if_id, _) = client
+        .virtual_interface()
+        .ensure(tunnel_spec(&config.tunnel_name, kind))?;
+    client.link().interface_set_up(if_id)?;
+
+    if let Some(mtu) = config.mtu {
+        client.link().interface_set_mtu(if_id, mtu)?;
+    }
+
+    ignore_exists(
+        client
+            .address()
+            .ipv4_addr_set(if_id, Ipv4Net::from(params.ipv4_addr)),
+    )?;
+
+    let default_route = Ipv4Route {
+        if_id: Some(if_id),
+        if_name: None,
+        gateway: None,
+        source: Some(params.ipv4_addr),
+        metric: config.route_metric,
+        tos: 0,
+        table: config.route_table,
+        route: Ipv4Net::default(),
+        nexthops: Vec::new(),
+        nexthop_id: None,
+        kind: RouteType::Unicast,
+        protocol: None,
+        mtu: None,
+        metrics: RouteMetrics::default(),
+        onlink: false,
+        encap: None,
+        status: RouteStatus::default(),
+        cache_info: None,
+        extra: Vec::new(),
+    };
+    client.route().ipv4_route_replace(default_route.clone())?;
+
+    Ok(MapETunnel {
+        tunnel_name: config.tunnel_name.clone(),
+        if_id,
+        wan_if_id,
+        params,
+        default_route,
+    })
+}
+
+fn find_end_user_prefix(
+    client: &RtnlClient,
+    wan_if_id: u32,
+    rule: &MapERule,
+) -> io::Result<Ipv6Net> {
+    client
+        .address()
+        .ipv6_addrs_get_with_scope(Some(wan_if_id), Some(AddressScope::Universe))?
+        .into_iter()
+        .find(|addr| rule.ipv6_prefix.contains(addr))
+        .and_then(|addr| Ipv6Net::new(addr, rule.end_user_prefix_len()).ok())
+        .map(|prefix| prefix.trunc())
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::AddrNotAvailable,
+                format!(
+                    "No address within {} on the WAN interface",
+                    rule.ipv6_prefix
+                ),
+            )
+        })
+}
+
+fn invalid_input(message: impl Into<String>) -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 7597 Appendix A, example 1.
+    fn rfc7597_rule() -> MapERule {
+        MapERule::new(
+            "2001:db8:ffff::1".parse().unwrap(),
+            "2001:db8::/40".parse().unwrap(),
+            "192.0.2.0/24".parse().unwrap(),
+            16,
+        )
+    }
+
+    fn rfc7597_end_user_prefix() -> Ipv6Net {
+        "2001:db8:12:3400::/56".parse().unwrap()
+    }
+
+    #[test]
+    fn derive_rfc7597_example() {
+        let params = rfc7597_rule()
+            .derive(rfc7597_end_user_prefix(), MapEInterfaceId::Rfc7597)
+            .unwrap();
+        assert_eq!(params.ipv4_addr, Ipv4Addr::new(192, 0, 2, 18));
+        assert_eq!(params.psid, 0x34);
+        assert_eq!(params.psid_len, 8);
+        assert_eq!(params.psid_offset, 6);
+        assert_eq!(
+            params.ce_addr,
+            "2001:db8:12:3400:0:c000:212:34"
+                .parse::<Ipv6Addr>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn derive_draft03_interface_id() {
+        let params = rfc7597_rule()
+            .derive(rfc7597_end_user_prefix(), MapEInterfaceId::Draft03)
+            .unwrap();
+        assert_eq!(params.ipv4_addr, Ipv4Addr::new(192, 0, 2, 18));
+        assert_eq!(params.psid, 0x34);
+        assert_eq!(
+            params.ce_addr,
+            "2001:db8:12:3400:c0:2:1200:3400"
+                .parse::<Ipv6Addr>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn derive_rejects_prefix_outside_rule() {
+        let err = rfc7597_rule()
+            .derive("2001:db9::/56".parse().unwrap(), MapEInterfaceId::Rfc7597)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn port_ranges_offset_6() {
+        let params = rfc7597_rule()
+            .derive(rfc7597_end_user_prefix(), MapEInterfaceId::Rfc7597)
+            .unwrap();
+        let ranges = params.port_ranges();
+        assert_eq!(ranges.len(), 63);
+        assert_eq!(ranges[0], 1232..=1235);
+        assert_eq!(ranges[1], 2256..=2259);
+        assert_eq!(ranges[62], 64720..=64723);
+    }
+
+    #[test]
+    fn port_ranges_offset_0() {
+        let mut rule = rfc7597_rule();
+        rule.psid_offset = 0;
+        let params = rule
+            .derive(rfc7597_end_user_prefix(), MapEInterfaceId::Rfc7597)
+            .unwrap();
+        assert_eq!(params.port_ranges(), vec![0x3400..=0x34ff]);
+    }
+
+    #[test]
+    fn port_ranges_without_psid() {
+        let rule = MapERule::new(
+            "2001:db8:ffff::1".parse().unwrap(),
+            "2001:db8::/40".parse().unwrap(),
+            "192.0.2.0/24".parse().unwrap(),
+            8,
+        );
+        let params = rule
+            .derive(
+                "2001:db8:12::/48".parse().unwrap(),
+                MapEInterfaceId::Rfc7597,
+            )
+            .unwrap();
+        assert_eq!(params.ipv4_addr, Ipv4Addr::new(192, 0, 2, 18));
+        assert_eq!(params.psid_len, 0);
+        assert_eq!(params.port_ranges(), vec![0..=u16::MAX]);
+    }
+}
@@ -0,0 +1,395 @@
+use std::io;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use netlink_packet_route::link::LinkAttribute;
+use netlink_packet_route::route::RouteType;
+
+use crate::address::address_from_message;
+use crate::monitor::{RtnlEvent, RtnlGroup, RtnlMonitor};
+use crate::route::{Ipv4Route, Ipv6Route, RouteGateway, RouteMetrics, RouteStatus};
+use crate::rule::RoutingRule;
+use crate::{IpNet, Ipv4Net, Ipv6Net, RtnlClient};
+
+use super::{ignore_exists, ignore_not_found};
+
+const DEFAULT_RULE_PRIORITY: u32 = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uplink {
+    pub if_name: String,
+    pub gateway: Option<IpAddr>,
+    pub table: u32,
+    // When set, a default route with this metric is also kept in the main
+    // table so unbound traffic can fail over between uplinks.
+    pub main_metric: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiWanConfig {
+    pub uplinks: Vec<Uplink>,
+    pub rule_priority: u32,
+}
+
+impl MultiWanConfig {
+    pub fn new(uplinks: Vec<Uplink>) -> Self {
+        Self {
+            uplinks,
+            rule_priority: DEFAULT_RULE_PRIORITY,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct UplinkState {
+    if_id: Option<u32>,
+    addresses: Vec<IpNet>,
+    rules: Vec<RoutingRule>,
+}
+
+#[derive(Debug)]
+pub struct MultiWan {
+    client: RtnlClient,
+    config: MultiWanConfig,
+    monitor: RtnlMonitor,
+    states: Vec<UplinkState>,
+}
+
+impl MultiWan {
+    pub fn new(client: &RtnlClient, config: MultiWanConfig) -> io::Result<Self> {
+        let monitor = RtnlMonitor::with_initial_dump(&[
+            RtnlGroup::Link,
+            RtnlGroup::Ipv4Address,
+            RtnlGroup::Ipv6Address,
+        ])?;
+        let states = config
+            .uplinks
+            .iter()
+            .map(|_| UplinkState::default())
+            .collect();
+        Ok(Self {
+            client: client.clone(),
+            config,
+            monitor,
+            states,
+        })
+    }
+
+    pub fn run(&mut self) -> io::Result<()> {
+        loop {
+            let event = self.monitor.recv()?;
+            self.handle_event(event);
+        }
+    }
+
+    pub fn poll(&mut self, timeout: Duration) -> io::Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.monitor.recv_timeout(remaining)? {
+                Some(event) => self.handle_event(event),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    pub fn sync(&mut self) -> io::Result<()> {
+        for index in 0..self.states.len() {
+            self.apply(index)?;
+        }
+        Ok(())
+    }
+
+    pub fn teardown(&mut self) -> io::Result<()> {
+        for index in 0..self.states.len() {
+            while let Some(rule) = self.states[index].rules.last() {
+                ignore_not_found(self.client.rule().delete(rule.clone()))?;
+                self.states[index].rules.pop();
+            }
+            let Some(if_id) = self.states[index].if_id else {
+                continue;
+            };
+            let (v4, v6) = self.routes(index, if_id);
+            for route in v4 {
+                ignore_not_found(self.client.route().ipv4_route_del(route))?;
+            }
+            for route in v6 {
+                ignore_not_found(self.client.route().ipv6_route_del(route))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: RtnlEvent) {
+        let changed = match event {
+            RtnlEvent::NewLink(msg) => {
+                let name = msg.attributes.iter().find_map(|attr| match attr {
+                    LinkAttribute::IfName(name) => Some(name),
+                    _ => None,
+                });
+                let Some(index) = name.and_then(|name| self.uplink_index(name)) else {
+                    return;
+                };
+                let if_id = Some(msg.header.index);
+                if self.states[index].if_id == if_id {
+                    return;
+                }
+                self.states[index].if_id = if_id;
+                self.states[index].addresses.clear();
+                Some(index)
+            }
+            RtnlEvent::DelLink(msg) => {
+                let Some(index) = self.state_index(msg.header.index) else {
+                    return;
+                };
+                self.states[index].if_id = None;
+                self.states[index].addresses.clear();
+                Some(index)
+            }
+            RtnlEvent::NewAddress(msg) => {
+                let Some((if_id, addr)) = address_from_message(&msg) else {
+                    return;
+                };
+                let Some(index) = self.state_index(if_id) else {
+                    return;
+                };
+                if !is_routable(&addr) || self.states[index].addresses.contains(&addr) {
+                    return;
+                }
+                self.states[index].addresses.push(addr);
+                Some(index)
+            }
+            RtnlEvent::DelAddress(msg) => {
+                let Some((if_id, addr)) = address_from_message(&msg) else {
+                    return;
+                };
+                let Some(index) = self.state_index(if_id) else {
+                    return;
+                };
+                let addresses = &mut self.states[index].addresses;
+                let before = addresses.len();
+                addresses.retain(|a| *a != addr);
+                if addresses.len() == before {
+                    return;
+                }
+                self.remove_subnet_route(index, if_id, addr);
+                Some(index)
+            }
+            _ => None,
+        };
+        if let Some(index) = changed
+            && let Err(err) = self.apply(index)
+        {
+            tracing::warn!(
+                "Failed to apply routing for uplink {}: {}",
+                self.config.uplinks[index].if_name,
+                err
+            );
+        }
+    }
+
+    fn uplink_index(&self, if_name: &str) -> Option<usize> {
+        self.config
+            .uplinks
+            .iter()
+            .position(|uplink| uplink.if_name == if_name)
+    }
+
+    fn state_index(&self, if_id: u32) -> Option<usize> {
+        self.states
+            .iter()
+            .position(|state| state.if_id == Some(if_id))
+    }
+
+    fn apply(&mut self, index: usize) -> io::Result<()> {
+        let uplink = &self.config.uplinks[index];
+        let priority = self.config.rule_priority + index as u32;
+        let wanted: Vec<RoutingRule> = self.states[index]
+            .addresses
+            .iter()
+            .map(|addr| {
+                let host = IpNet::from(addr.addr());
+                RoutingRule {
+                    priority: Some(priority),
+                    ..RoutingRule::from_source(host, uplink.table)
+                }
+            })
+            .collect();
+
+        // The tracked rules follow each change as it succeeds, so an error
+        // midway leaves the installed ones known to `teardown()`.
+        let stale: Vec<RoutingRule> = self.states[index]
+            .rules
+            .iter()
+            .filter(|rule| !wanted.contains(rule))
+            .cloned()
+            .collect();
+        for rule in stale {
+            ignore_not_found(self.client.rule().delete(rule.clone()))?;
+            self.states[index]
+                .rules
+                .retain(|installed| *installed != rule);
+        }
+        for rule in wanted {
+            if !self.states[index].rules.contains(&rule) {
+                ignore_exists(self.client.rule().add(rule.clone()))?;
+                self.states[index].rules.push(rule);
+            }
+        }
+
+        let Some(if_id) = self.states[index].if_id else {
+            return Ok(());
+        };
+        let (v4, v6) = self.routes(index, if_id);
+        for route in v4 {
+            self.client.route().ipv4_route_replace(route)?;
+        }
+        for route in v6 {
+            self.client.route().ipv6_route_replace(route)?;
+        }
+        Ok(())
+    }
+
+    fn routes(&self, index: usize, if_id: u32) -> (Vec<Ipv4Route>, Vec<Ipv6Route>) {
+        let uplink = &self.config.uplinks[index];
+        let addresses = &self.states[index].addresses;
+        let mut v4 = Vec::new();
+        let mut v6 = Vec::new();
+
+        for addr in addresses {
+            match addr {
+                IpNet::V4(net) if net.prefix_len() < 32 => {
+                    v4.push(ipv4_route(
+                        net.trunc(),
+                        if_id,
+                        None,
+                        Some(uplink.table),
+                        None,
+                    ));
+                }
+                IpNet::V6(net) if net.prefix_len() < 128 => {
+                    v6.push(ipv6_route(
+                        net.trunc(),
+                        if_id,
+                        None,
+                        Some(uplink.table),
+                        None,
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        let has_v4 = addresses.iter().any(|addr| matches!(addr, IpNet::V4(_)));
+        let has_v6 = addresses.iter().any(|addr| matches!(addr, IpNet::V6(_)));
+        let gateway = uplink.gateway;
+        let tables = std::iter::once((Some(uplink.table), None))
+            .chain(uplink.main_metric.map(|metric| (None, Some(metric))));
+        for (table, metric) in tables {
+            if has_v4 && gateway.is_none_or(|gw| gw.is_ipv4()) {
+                v4.push(ipv4_route(
+                    Ipv4Net::default(),
+                    if_id,
+                    gateway,
+                    table,
+                    metric,
+                ));
+            }
+            if has_v6 && gateway.is_none_or(|gw| gw.is_ipv6()) {
+                v6.push(ipv6_route(
+                    Ipv6Net::default(),
+                    if_id,
+                    gateway,
+                    table,
+                    metric,
+                ));
+            }
+        }
+        (v4, v6)
+    }
+
+    fn remove_subnet_route(&self, index: usize, if_id: u32, addr: IpNet) {
+        let table = Some(self.config.uplinks[index].table);
+        let result = match addr {
+            IpNet::V4(net) if net.prefix_len() < 32 => self
+                .client
+                .route()
+                .ipv4_route_del(ipv4_route(net.trunc(), if_id, None, table, None)),
+            IpNet::V6(net) if net.prefix_len() < 128 => self
+                .client
+                .route()
+                .ipv6_route_del(ipv6_route(net.trunc(), if_id, None, table, None)),
+            _ => Ok(()),
+        };
+        if let Err(err) = ignore_not_found(result) {
+            tracing::warn!("Failed to remove subnet route for {}: {}", addr, err);
+        }
+    }
+}
+
+fn is_routable(addr: &IpNet) -> bool {
+    match addr {
+        IpNet::V4(net) => !net.addr().is_loopback() && !net.addr().is_link_local(),
+        IpNet::V6(net) => !net.addr().is_loopback() && !net.addr().is_unicast_link_local(),
+    }
+}
+
+fn ipv4_route(
+    route: Ipv4Net,
+    if_id: u32,
+    gateway: Option<IpAddr>,
+    table: Option<u32>,
+    metric: Option<u32>,
+) -> Ipv4Route {
+    Ipv4Route {
+        if_id: Some(if_id),
+        if_name: None,
+        gateway: gateway.map(RouteGateway::Gateway),
+        source: None,
+        metric,
+        tos: 0,
+        table,
+        route,
+        nexthops: Vec::new(),
+        nexthop_id: None,
+        kind: RouteType::Unicast,
+        protocol: None,
+        mtu: None,
+        metrics: RouteMetrics::default(),
+        onlink: false,
+        encap: None,
+        status: RouteStatus::default(),
+        cache_info: None,
+        extra: Vec::new(),
+    }
+}
+
+fn ipv6_route(
+    route: Ipv6Net,
+    if_id: u32,
+    gateway: Option<IpAddr>,
+    table: Option<u32>,
+    metric: Option<u32>,
+) -> Ipv6Route {
+    Ipv6Route {
+        if_id: Some(if_id),
+        if_name: None,
+        gateway: gateway.map(RouteGateway::Gateway),
+        source: None,
+        metric,
+        table,
+        route,
+        nexthops: Vec::new(),
+        nexthop_id: None,
+        kind: RouteType::Unicast,
+        protocol: None,
+        mtu: None,
+        metrics: RouteMetrics::default(),
+        expires: None,
+        onlink: false,
+        encap: None,
+        status: RouteStatus::default(),
+        cache_info: None,
+        extra: Vec::new(),
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+use std::io;
+use std::net::{IpAddr, Ipv6Addr};
+use std::time::{Duration, Instant};
+
+use crate::monitor::{RtnlEvent, RtnlGroup, RtnlMonitor};
+use crate::neighbor::{NeighborDelete, NeighborEntry, NeighbourFlags, NeighbourState};
+use crate::{Ipv6Net, RtnlClient};
+
+use super::{ignore_exists, ignore_not_found};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyNdpConfig {
+    pub prefix: Ipv6Net,
+    pub lan_if: String,
+    pub wan_if: String,
+}
+
+#[derive(Debug)]
+pub struct ProxyNdp {
+    client: RtnlClient,
+    prefix: Ipv6Net,
+    lan_if_id: u32,
+    wan_if_id: u32,
+    monitor: RtnlMonitor,
+    proxied: HashSet<Ipv6Addr>,
+}
+
+impl ProxyNdp {
+    pub fn new(client: &RtnlClient, config: &ProxyNdpConfig) -> io::Result<Self> {
+        let lan_if_id = client.link().interface_get_by_name(&config.lan_if)?.if_id;
+        let wan_if_id = client.link().interface_get_by_name(&config.wan_if)?.if_id;
+        let monitor = RtnlMonitor::with_initial_dump(&[RtnlGroup::Neighbor])?;
+        Ok(Self {
+            client: client.clone(),
+            prefix: config.prefix.trunc(),
+            lan_if_id,
+            wan_if_id,
+            monitor,
+            proxied: HashSet::new(),
+        })
+    }
+
+    pub fn proxied(&self) -> impl Iterator<Item = &Ipv6Addr> {
+        self.proxied.iter()
+    }
+
+    pub fn run(&mut self) -> io::Result<()> {
+        loop {
+            let event = self.monitor.recv()?;
+            self.handle_event(event);
+        }
+    }
+
+    pub fn poll(&mut self, timeout: Duration) -> io::Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.monitor.recv_timeout(remaining)? {
+                Some(event) => self.handle_event(event),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    pub fn clear(&mut self) -> io::Result<()> {
+        for addr in std::mem::take(&mut self.proxied) {
+            self.remove_proxy(addr)?;
+        }
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: RtnlEvent) {
+        let (entry, removed) = match event {
+            RtnlEvent::NewNeighbor(msg) => (NeighborEntry::try_from(msg), false),
+            RtnlEvent::DelNeighbor(msg) => (NeighborEntry::try_from(msg), true),
+            _ => return,
+        };
+        let Ok(entry) = entry else {
+            return;
+        };
+        let IpAddr::V6(addr) = entry.destination else {
+            return;
+        };
+        if entry.if_id != self.lan_if_id || !self.prefix.contains(&addr) {
+            return;
+        }
+        if entry
+            .flags
+            .is_some_and(|flags| flags.contains(NeighbourFlags::Proxy))
+        {
+            return;
+        }
+
+        let active = !removed
+            && entry.state.is_some_and(|state| {
+                !matches!(
+                    state,
+                    NeighbourState::Failed | NeighbourState::Incomplete | NeighbourState::None
+                )
+            });
+        let result = if active && !self.proxied.contains(&addr) {
+            self.add_proxy(addr).map(|()| {
+                self.proxied.insert(addr);
+            })
+        } else if !active && self.proxied.remove(&addr) {
+            self.remove_proxy(addr)
+        } else {
+            Ok(())
+        };
+        if let Err(err) = result {
+            tracing::warn!("Failed to update proxy NDP entry for {}: {}", addr, err);
+        }
+    }
+
+    fn add_proxy(&self, addr: Ipv6Addr) -> io::Result<()> {
+        ignore_exists(self.client.neighbor().add(NeighborEntry {
+            if_id: self.wan_if_id,
+            destination: IpAddr::V6(addr),
+            link_address: None,
+            state: Some(NeighbourState::Permanent),
+            flags: Some(NeighbourFlags::Proxy),
+        }))
+    }
+
+    fn remove_proxy(&self, addr: Ipv6Addr) -> io::Result<()> {
+        ignore_not_found(self.client.neighbor().delete(NeighborDelete {
+            if_id: self.wan_if_id,
+            destination: IpAddr::V6(addr),
+            link_address: None,
+            state: None,
+            flags: Some(NeighbourFlags::Proxy),
+        }))
+    }
+}
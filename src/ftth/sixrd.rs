@@ -0,0 +1,414 @@
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, Instant};
+
+use netlink_packet_route::address::AddressScope;
+use netlink_packet_route::route::RouteType;
+
+use crate::address::address_from_message;
+use crate::monitor::{RtnlEvent, RtnlGroup, RtnlMonitor};
+use crate::route::{Ipv6Route, RouteGateway, RouteMetrics, RouteStatus};
+use crate::virtual_interface::{SitConfig, VirtualInterfaceDelete, VirtualInterfaceKind};
+use crate::{IpNet, Ipv4Net, Ipv6Net, RtnlClient};
+
+use super::{ignore_exists, ignore_not_found, tunnel_spec};
+
+const LAN_PREFIX_LEN: u8 = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SixRdRule {
+    pub prefix: Ipv6Net,
+    pub ipv4_mask_len: u8,
+    pub br_addr: Ipv4Addr,
+}
+
+impl SixRdRule {
+    // RFC 5969 section 4: the 6rd prefix followed by the IPv4 address bits
+    // left after stripping the common `ipv4_mask_len` leading bits.
+    pub fn delegated_prefix(&self, wan_addr: Ipv4Addr) -> io::Result<Ipv6Net> {
+        if self.ipv4_mask_len > 32 {
+            return Err(invalid_input("IPv4 mask length exceeds 32 bits"));
+        }
+        let suffix_len = 32 - self.ipv4_mask_len as u32;
+        let prefix_len = self.prefix.prefix_len() as u32 + suffix_len;
+        if prefix_len > LAN_PREFIX_LEN as u32 {
+            return Err(invalid_input(format!(
+                "6rd delegated prefix would be /{}, longer than /{}",
+                prefix_len, LAN_PREFIX_LEN
+            )));
+        }
+        let suffix = (u32::from(wan_addr) as u64 & ((1u64 << suffix_len) - 1)) as u128;
+        let bits = u128::from(self.prefix.trunc().addr())
+            | suffix.checked_shl(128 - prefix_len).unwrap_or(0);
+        Ipv6Net::new(Ipv6Addr::from(bits), prefix_len as u8)
+            .map_err(|e| invalid_input(e.to_string()))
+    }
+
+    pub fn relay_prefix(&self, wan_addr: Ipv4Addr) -> io::Result<Ipv4Net> {
+        Ipv4Net::new(wan_addr, self.ipv4_mask_len)
+            .map(|net| net.trunc())
+            .map_err(|e| invalid_input(e.to_string()))
+    }
+
+    // The border relay is reached through its IPv4-compatible IPv6 address.
+    pub fn br_gateway(&self) -> Ipv6Addr {
+        Ipv6Addr::from(u32::from(self.br_addr) as u128)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SixRdLan {
+    pub if_name: String,
+    pub subnet_id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SixRdConfig {
+    pub tunnel_name: String,
+    pub wan_if: String,
+    pub rule: SixRdRule,
+    pub lans: Vec<SixRdLan>,
+    pub mtu: Option<u32>,
+    pub route_metric: Option<u32>,
+    pub route_table: Option<u32>,
+}
+
+impl SixRdConfig {
+    pub fn new(rule: SixRdRule, wan_if: &str) -> Self {
+        Self {
+            tunnel_name: "6rd0".to_string(),
+            wan_if: wan_if.to_string(),
+            rule,
+            lans: Vec::new(),
+            mtu: None,
+            route_metric: None,
+            route_table: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SixRdTunnel {
+    pub tunnel_name: String,
+    pub if_id: u32,
+    pub wan_if_id: u32,
+    pub wan_addr: Ipv4Addr,
+    pub delegated_prefix: Ipv6Net,
+    pub tunnel_addr: Ipv6Net,
+    pub lan_addrs: Vec<(u32, Ipv6Net)>,
+    pub default_route: Ipv6Route,
+}
+
+impl SixRdTunnel {
+    pub fn teardown(&self, client: &RtnlClient) -> io::Result<()> {
+        self.release(client)?;
+        ignore_not_found(
+            client
+                .virtual_interface()
+                .delete(VirtualInterfaceDelete::ByIndex(self.if_id)),
+        )
+    }
+
+    // Removes everything derived from the WAN address but keeps the tunnel
+    // interface, so it can be reconfigured for a new address.
+    fn release(&self, client: &RtnlClient) -> io::Result<()> {
+        ignore_not_found(client.route().ipv6_route_del(self.default_route.clone()))?;
+        for (if_id, addr) in &self.lan_addrs {
+            ignore_not_found(client.address().ipv6_addr_del(*if_id, *addr))?;
+        }
+        ignore_not_found(client.address().ipv6_addr_del(self.if_id, self.tunnel_addr))
+    }
+}
+
+pub fn sixrd_setup(client: &RtnlClient, config: &SixRdConfig) -> io::Result<SixRdTunnel> {
+    let wan_if_id = client.link().interface_get_by_name(&config.wan_if)?.if_id;
+    let wan_addr = client
+        .address()
+        .ipv4_addrs_get_with_scope(Some(wan_if_id), Some(AddressScope::Universe))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::AddrNotAvailable,
+                format!("No global IPv4 address on {}", config.wan_if),
+            )
+        })?;
+    sixrd_setup_with_addr(client, config, wan_addr)
+}
+
+pub fn sixrd_setup_with_addr(
+    client: &RtnlClient,
+    config: &SixRdConfig,
+    wan_addr: Ipv4Addr,
+) -> io::Result<SixRdTunnel> {
+    let wan_if_id = client.link().interface_get_by_name(&config.wan_if)?.if_id;
+    let rule = &config.rule;
+    let delegated_prefix = rule.delegated_prefix(wan_addr)?;
+
+    let kind = VirtualInterfaceKind::Sit(SitConfig {
+        local: wan_addr,
+        remote: Ipv4Addr::UNSPECIFIED,
+        ttl: Some(64),
+        tos: None,
+        pmtudisc: true,
+        link: Some(wan_if_id),
+        ip6rd_prefix: Some(rule.prefix.trunc()),
+        ip6rd_relay_prefix: Some(rule.relay_prefix(wan_addr)?),
+    });
+    let (if_id, _) = client
+        .virtual_interface()
+        .ensure(tunnel_spec(&config.tunnel_name, kind))?;
+    client.link().interface_set_up(if_id)?;
+
+    if let Some(mtu) = config.mtu {
+        client.link().interface_set_mtu(if_id, mtu)?;
+    }
+
+    let tunnel_addr = Ipv6Net::new(host_addr(delegated_prefix.addr()), rule.prefix.prefix_len())
+        .map_err(|e| invalid_input(e.to_string()))?;
+    ignore_exists(client.address().ipv6_addr_set(if_id, tunnel_addr))?;
+
+    let mut lan_addrs = Vec::new();
+    for lan in &config.lans {
+        let lan_if_id = client.link().interface_get_by_name(&lan.if_name)?.if_id;
+        let lan_addr = lan_prefix(delegated_prefix, lan.subnet_id)?;
+        ignore_exists(client.address().ipv6_addr_set(lan_if_id, lan_addr))?;
+        lan_addrs.push((lan_if_id, lan_addr));
+    }
+
+    let default_route = Ipv6Route {
+        if_id: Some(if_id),
+        if_name: None,
+        gateway: Some(RouteGateway::Gateway(IpAddr::V6(rule.br_gateway()))),
+        source: None,
+        metric: config.route_metric,
+        table: config.route_table,
+        route: Ipv6Net::default(),
+        nexthops: Vec::new(),
+        nexthop_id: None,
+        kind: RouteType::Unicast,
+        protocol: None,
+        mtu: None,
+        metrics: RouteMetrics::default(),
+        expires: None,
+        onlink: false,
+        encap: None,
+        status: RouteStatus::default(),
+        cache_info: None,
+        extra: Vec::new(),
+    };
+    client.route().ipv6_route_replace(default_route.clone())?;
+
+    Ok(SixRdTunnel {
+        tunnel_name: config.tunnel_name.clone(),
+        if_id,
+        wan_if_id,
+        wan_addr,
+        delegated_prefix,
+        tunnel_addr,
+        lan_addrs,
+        default_route,
+    })
+}
+
+// Keeps a 6rd tunnel in sync with the WAN IPv4 address, re-deriving the
+// delegated prefix whenever the address changes.
+#[derive(Debug)]
+pub struct SixRd {
+    client: RtnlClient,
+    config: SixRdConfig,
+    monitor: RtnlMonitor,
+    wan_if_id: u32,
+    tunnel: Option<SixRdTunnel>,
+}
+
+impl SixRd {
+    pub fn new(client: &RtnlClient, config: SixRdConfig) -> io::Result<Self> {
+        let wan_if_id = client.link().interface_get_by_name(&config.wan_if)?.if_id;
+        let monitor = RtnlMonitor::with_initial_dump(&[RtnlGroup::Ipv4Address])?;
+        Ok(Self {
+            client: client.clone(),
+            config,
+            monitor,
+            wan_if_id,
+            tunnel: None,
+        })
+    }
+
+    pub fn tunnel(&self) -> Option<&SixRdTunnel> {
+        self.tunnel.as_ref()
+    }
+
+    pub fn run(&mut self) -> io::Result<()> {
+        loop {
+            let event = self.monitor.recv()?;
+            self.handle_event(event);
+        }
+    }
+
+    pub fn poll(&mut self, timeout: Duration) -> io::Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.monitor.recv_timeout(remaining)? {
+                Some(event) => self.handle_event(event),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    pub fn teardown(&mut self) -> io::Result<()> {
+        match self.tunnel.take() {
+            Some(tunnel) => tunnel.teardown(&self.client),
+            None => Ok(()),
+        }
+    }
+
+    fn handle_event(&mut self, event: RtnlEvent) {
+        let (msg, removed) = match event {
+            RtnlEvent::NewAddress(msg) => (msg, false),
+            RtnlEvent::DelAddress(msg) => (msg, true),
+            _ => return,
+        };
+        let Some((if_id, IpNet::V4(addr))) = address_from_message(&msg) else {
+            return;
+        };
+        let addr = addr.addr();
+        if if_id != self.wan_if_id || addr.is_link_local() || addr.is_loopback() {
+            return;
+        }
+        let current = self.tunnel.as_ref().map(|tunnel| tunnel.wan_addr);
+        let result = if removed {
+            if current != Some(addr) {
+                return;
+            }
+            self.teardown()
+        } else {
+            if current == Some(addr) {
+                return;
+            }
+            self.reconfigure(addr)
+        };
+        if let Err(err) = result {
+            tracing::warn!("Failed to update 6rd for {}: {}", addr, err);
+        }
+    }
+
+    fn reconfigure(&mut self, wan_addr: Ipv4Addr) -> io::Result<()> {
+        if let Some(tunnel) = self.tunnel.take() {
+            tunnel.release(&self.client)?;
+        }
+        self.tunnel = Some(sixrd_setup_with_addr(&self.client, &self.config, wan_addr)?);
+        Ok(())
+    }
+}
+
+fn lan_prefix(delegated_prefix: Ipv6Net, subnet_id: u64) -> io::Result<Ipv6Net> {
+    let subnet_bits = (LAN_PREFIX_LEN - delegated_prefix.prefix_len()) as u32;
+    if subnet_bits < 64 && subnet_id >> subnet_bits != 0 {
+        return Err(invalid_input(format!(
+            "Subnet ID {} does not fit in {}",
+            subnet_id, delegated_prefix
+        )));
+    }
+    let bits = u128::from(delegated_prefix.trunc().addr()) | ((subnet_id as u128) << 64);
+    Ipv6Net::new(host_addr(Ipv6Addr::from(bits)), LAN_PREFIX_LEN)
+        .map_err(|e| invalid_input(e.to_string()))
+}
+
+fn host_addr(prefix: Ipv6Addr) -> Ipv6Addr {
+    Ipv6Addr::from(u128::from(prefix) | 1)
+}
+
+fn invalid_input(message: impl Into<String>) -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(prefix: &str, ipv4_mask_len: u8) -> SixRdRule {
+        SixRdRule {
+            prefix: prefix.parse().unwrap(),
+            ipv4_mask_len,
+            br_addr: Ipv4Addr::new(192, 0, 2, 254),
+        }
+    }
+
+    fn net(s: &str) -> Ipv6Net {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn delegated_prefix_common_ipv4_bits() {
+        // 6rd prefix 2001:db8::/32 with the leading 8 bits of 10.100.100.1
+        // shared by every CE leaves a /56.
+        let prefix = rule("2001:db8::/32", 8)
+            .delegated_prefix(Ipv4Addr::new(10, 100, 100, 1))
+            .unwrap();
+        assert_eq!(prefix, net("2001:db8:6464:100::/56"));
+    }
+
+    #[test]
+    fn delegated_prefix_mask_len_0_reaches_64() {
+        let prefix = rule("2001:db8::/32", 0)
+            .delegated_prefix(Ipv4Addr::new(192, 0, 2, 1))
+            .unwrap();
+        assert_eq!(prefix, net("2001:db8:c000:201::/64"));
+    }
+
+    #[test]
+    fn delegated_prefix_mask_len_32() {
+        let prefix = rule("2001:db8:1234::/48", 32)
+            .delegated_prefix(Ipv4Addr::new(192, 0, 2, 1))
+            .unwrap();
+        assert_eq!(prefix, net("2001:db8:1234::/48"));
+    }
+
+    #[test]
+    fn delegated_prefix_shift_by_128() {
+        // A /0 6rd prefix with no IPv4 suffix bits shifts the suffix by 128.
+        let prefix = rule("::/0", 32)
+            .delegated_prefix(Ipv4Addr::new(192, 0, 2, 1))
+            .unwrap();
+        assert_eq!(prefix, net("::/0"));
+    }
+
+    #[test]
+    fn delegated_prefix_rejects_invalid_rules() {
+        let err = rule("2001:db8::/32", 33)
+            .delegated_prefix(Ipv4Addr::new(192, 0, 2, 1))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = rule("2001:db8::/40", 0)
+            .delegated_prefix(Ipv4Addr::new(192, 0, 2, 1))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn lan_prefix_subnet_ids() {
+        let delegated = net("2001:db8:6464:100::/56");
+        assert_eq!(
+            lan_prefix(delegated, 0).unwrap(),
+            net("2001:db8:6464:100::1/64")
+        );
+        assert_eq!(
+            lan_prefix(delegated, 0xff).unwrap(),
+            net("2001:db8:6464:1ff::1/64")
+        );
+        let err = lan_prefix(delegated, 0x100).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn lan_prefix_from_64() {
+        let delegated = net("2001:db8:c000:201::/64");
+        assert_eq!(
+            lan_prefix(delegated, 0).unwrap(),
+            net("2001:db8:c000:201::1/64")
+        );
+        assert!(lan_prefix(delegated, 1).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+use std::io;
+use std::time::{Duration, Instant};
+
+use netlink_packet_route::link::{LinkAttribute, LinkFlags, LinkMessage};
+
+use crate::address::address_from_message;
+use crate::monitor::{RtnlEvent, RtnlGroup, RtnlMonitor};
+use crate::route::{Ipv4Route, Ipv6Route};
+use crate::rule::RoutingRule;
+use crate::{IpNet, RtnlClient};
+
+use super::ignore_exists;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WanEvent {
+    Up { if_id: u32 },
+    Down,
+    AddressAdded(IpNet),
+    AddressRemoved(IpNet),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WanTrackerConfig {
+    pub wan_if: String,
+    // Routes without an interface or nexthops are bound to the WAN
+    // interface index at install time.
+    pub ipv4_routes: Vec<Ipv4Route>,
+    pub ipv6_routes: Vec<Ipv6Route>,
+    // Added along with the routes; rules already present are left alone.
+    pub rules: Vec<RoutingRule>,
+}
+
+impl WanTrackerConfig {
+    pub fn new(wan_if: &str) -> Self {
+        Self {
+            wan_if: wan_if.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct WanTracker {
+    client: RtnlClient,
+    config: WanTrackerConfig,
+    monitor: RtnlMonitor,
+    if_id: Option<u32>,
+    up: bool,
+    addresses: Vec<IpNet>,
+}
+
+impl WanTracker {
+    pub fn new(client: &RtnlClient, config: WanTrackerConfig) -> io::Result<Self> {
+        // Route notifications only tell when a configured route goes away,
+        // so existing routes are not replayed.
+        let monitor = RtnlMonitor::with_partial_dump(
+            &[
+                RtnlGroup::Link,
+                RtnlGroup::Ipv4Address,
+                RtnlGroup::Ipv6Address,
+                RtnlGroup::Ipv4Route,
+                RtnlGroup::Ipv6Route,
+            ],
+            &[
+                RtnlGroup::Link,
+                RtnlGroup::Ipv4Address,
+                RtnlGroup::Ipv6Address,
+            ],
+        )?;
+        Ok(Self {
+            client: client.clone(),
+            config,
+            monitor,
+            if_id: None,
+            up: false,
+            addresses: Vec::new(),
+        })
+    }
+
+    pub fn if_id(&self) -> Option<u32> {
+        self.if_id
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.up
+    }
+
+    pub fn addresses(&self) -> &[IpNet] {
+        &self.addresses
+    }
+
+    pub fn run<F: FnMut(&WanEvent)>(&mut self, mut callback: F) -> io::Result<()> {
+        loop {
+            let event = self.monitor.recv()?;
+            self.handle_event(event, &mut callback);
+        }
+    }
+
+    pub fn poll<F: FnMut(&WanEvent)>(
+        &mut self,
+        timeout: Duration,
+        mut callback: F,
+    ) -> io::Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.monitor.recv_timeout(remaining)? {
+                Some(event) => self.handle_event(event, &mut callback),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    pub fn reinstall_routes(&self) -> io::Result<()> {
+        let Some(if_id) = self.if_id else {
+            return Ok(());
+        };
+        for route in self.ipv4_routes(if_id) {
+            self.client.route().ipv4_route_replace(route)?;
+        }
+        for route in self.ipv6_routes(if_id) {
+            self.client.route().ipv6_route_replace(route)?;
+        }
+        Ok(())
+    }
+
+    pub fn reinstall_rules(&self) -> io::Result<()> {
+        for rule in &self.config.rules {
+            ignore_exists(self.client.rule().add(rule.clone()))?;
+        }
+        Ok(())
+    }
+
+    fn ipv4_routes(&self, if_id: u32) -> Vec<Ipv4Route> {
+        self.config
+            .ipv4_routes
+            .iter()
+            .cloned()
+            .map(|mut route| {
+                if route.if_id.is_none() && route.nexthops.is_empty() {
+                    route.if_id = Some(if_id);
+                }
+                route
+            })
+            .collect()
+    }
+
+    fn ipv6_routes(&self, if_id: u32) -> Vec<Ipv6Route> {
+        self.config
+            .ipv6_routes
+            .iter()
+            .cloned()
+            .map(|mut route| {
+                if route.if_id.is_none() && route.nexthops.is_empty() {
+                    route.if_id = Some(if_id);
+                }
+                route
+            })
+            .collect()
+    }
+
+    fn handle_event<F: FnMut(&WanEvent)>(&mut self, event: RtnlEvent, callback: &mut F) {
+        match event {
+            RtnlEvent::NewLink(msg) => self.handle_new_link(&msg, callback),
+            RtnlEvent::DelLink(msg) => {
+                if Some(msg.header.index) != self.if_id {
+                    return;
+                }
+                if self.up {
+                    self.up = false;
+                    callback(&WanEvent::Down);
+                }
+                for addr in std::mem::take(&mut self.addresses) {
+                    callback(&WanEvent::AddressRemoved(addr));
+                }
+                self.if_id = None;
+            }
+            RtnlEvent::NewAddress(msg) => {
+                let Some((if_id, addr)) = address_from_message(&msg) else {
+                    return;
+                };
+                if Some(if_id) != self.if_id || self.addresses.contains(&addr) {
+                    return;
+                }
+                self.addresses.push(addr);
+                callback(&WanEvent::AddressAdded(addr));
+                if self.up {
+                    self.try_reinstall();
+                }
+            }
+            RtnlEvent::DelAddress(msg) => {
+                let Some((if_id, addr)) = address_from_message(&msg) else {
+                    return;
+                };
+                if Some(if_id) != self.if_id {
+                    return;
+                }
+                if let Some(pos) = self.addresses.iter().position(|a| *a == addr) {
+                    self.addresses.remove(pos);
+                    callback(&WanEvent::AddressRemoved(addr));
+                }
+            }
+            RtnlEvent::DelRoute(msg) => {
+                let (true, Some(if_id)) = (self.up, self.if_id) else {
+                    return;
+                };
+                let removed = match Ipv4Route::try_from(msg.clone()) {
+                    Ok(route) => self
+                        .ipv4_routes(if_id)
+                        .iter()
+                        .any(|r| r.canonical_eq(&route)),
+                    Err(_) => Ipv6Route::try_from(msg).is_ok_and(|route| {
+                        self.ipv6_routes(if_id)
+                            .iter()
+                            .any(|r| r.canonical_eq(&route))
+                    }),
+                };
+                if removed {
+                    self.try_reinstall();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_new_link<F: FnMut(&WanEvent)>(&mut self, msg: &LinkMessage, callback: &mut F) {
+        let is_wan = Some(msg.header.index) == self.if_id
+            || msg.attributes.iter().any(
+                |attr| matches!(attr, LinkAttribute::IfName(name) if *name == self.config.wan_if),
+            );
+        if !is_wan {
+            return;
+        }
+        let if_id = msg.header.index;
+        self.if_id = Some(if_id);
+
+        let flags = msg.header.flags;
+        let up = flags.contains(LinkFlags::Up)
+            && (flags.contains(LinkFlags::Running) || flags.contains(LinkFlags::LowerUp));
+        if up == self.up {
+            return;
+        }
+        self.up = up;
+        if up {
+            callback(&WanEvent::Up { if_id });
+            self.try_reinstall();
+        } else {
+            callback(&WanEvent::Down);
+        }
+    }
+
+    fn try_reinstall(&self) {
+        if let Err(err) = self.reinstall_routes() {
+            tracing::warn!(
+                "Failed to reinstall routes on {}: {}",
+                self.config.wan_if,
+                err
+            );
+        }
+        if let Err(err) = self.reinstall_rules() {
+            tracing::warn!(
+                "Failed to reinstall rules for {}: {}",
+                self.config.wan_if,
+                err
+            );
+        }
+    }
+}
@@ -1,53 +1,114 @@
+#[cfg(feature = "address")]
 pub mod address;
+pub mod error;
+#[cfg(feature = "ftth")]
+pub mod ftth;
+#[cfg(feature = "link")]
 pub mod link;
+#[cfg(feature = "monitor")]
+pub mod monitor;
+#[cfg(feature = "neighbor")]
 pub mod neighbor;
+#[cfg(feature = "nexthop")]
+pub mod nexthop;
+pub mod prelude;
+#[cfg(feature = "route")]
 pub mod route;
+#[cfg(feature = "rule")]
+pub mod rule;
+#[cfg(feature = "virtual-interface")]
 pub mod virtual_interface;
 
 use std::any::Any;
 use std::sync::{Arc, Mutex, OnceLock};
 
+#[cfg(all(feature = "address", feature = "monitor"))]
+pub use address::{AddressEvent, AddressWatcher};
+#[cfg(feature = "address")]
+pub use address::{AddressFilter, AddressInfo, AddressOptions, AddressSyncReport, IpFamily};
+pub use error::RtnlError;
 pub use ipnet::{IpNet, Ipv4Net, Ipv6Net};
-pub use neighbor::{NeighborDelete, NeighborEntry};
-pub use netlink_packet_route::address::AddressScope;
+#[cfg(feature = "neighbor")]
+pub use neighbor::{
+    FdbEntry, NeighborDelete, NeighborEntry, NeighborTableKind, NeighborTableParams,
+};
+#[cfg(feature = "address")]
+pub use netlink_packet_route::address::{AddressFlags, AddressScope};
+#[cfg(feature = "neighbor")]
 pub use netlink_packet_route::neighbour::{NeighbourFlags, NeighbourState};
-pub use netlink_packet_route::route::RouteNextHopFlags;
-pub use route::{Ipv4Route, Ipv6Route, RouteNextHopInfo};
+#[cfg(feature = "route")]
+pub use netlink_packet_route::route::{RouteNextHopFlags, RouteProtocol, RouteType, Seg6Mode};
+#[cfg(feature = "nexthop")]
+pub use nexthop::{Nexthop, NexthopFamily, NexthopGroupMember, NexthopKind};
+#[cfg(feature = "route")]
+pub use route::{
+    IpRoute, IpTunnelEncap, Ipv4Route, Ipv6Route, MplsNextHop, MplsRoute, MulticastOif,
+    MulticastRoute, MulticastRouteStats, RouteCacheInfo, RouteEncap, RouteFilter, RouteGateway,
+    RouteLookupOptions, RouteMetrics, RouteNextHopInfo, RoutePlan, RouteSet, RouteStatus,
+    RouteTableNames, Seg6LocalAction,
+};
+#[cfg(feature = "rule")]
+pub use rule::{RoutingRule, RuleAction, RuleFamily};
+#[cfg(feature = "virtual-interface")]
 pub use virtual_interface::{
-    Gre6Config, GreConfig, Ip6TnlConfig, IpIpConfig, VirtualInterfaceDelete, VirtualInterfaceKind,
-    VirtualInterfaceSpec, VirtualInterfaceUpdate, VlanConfig,
+    EnsureAction, GeneveConfig, Gre6Config, GreConfig, GreFlags, Ip6TnlConfig, Ip6TnlMode,
+    Ip6TunnelFlags, IpIpConfig, MacsecCipherSuite, MacsecConfig, SitConfig, VirtualInterfaceDelete,
+    VirtualInterfaceInfo, VirtualInterfaceKind, VirtualInterfaceSpec, VirtualInterfaceType,
+    VirtualInterfaceUpdate, VlanConfig, VxlanConfig,
 };
 
-use ftth_common::channel::create_pair;
-
-use futures::{FutureExt, future::join_all};
-
+use futures::future::{BoxFuture, join_all};
+use rtnetlink::sys::AsyncSocket;
 
 static CLIENT: OnceLock<RtnlClient> = OnceLock::new();
 
 #[derive(Debug, Clone)]
 pub struct RtnlClient {
+    #[cfg(feature = "address")]
     address: address::RtnlAddressClient,
+    #[cfg(feature = "link")]
     link: link::RtnlLinkClient,
+    #[cfg(feature = "neighbor")]
     neighbor: neighbor::RtnlNeighborClient,
+    #[cfg(feature = "nexthop")]
+    nexthop: nexthop::RtnlNexthopClient,
+    #[cfg(feature = "route")]
     route: route::RtnlRouteClient,
+    #[cfg(feature = "rule")]
+    rule: rule::RtnlRuleClient,
+    #[cfg(feature = "virtual-interface")]
     virtual_interface: virtual_interface::RtnlVirtualInterfaceClient,
-    
+
     #[allow(dead_code)]
     receiver: Arc<Mutex<Option<Box<dyn Any + Send>>>>,
 }
 
+impl Default for RtnlClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RtnlClient {
     pub fn new() -> Self {
-        CLIENT.get_or_init(|| Self::new_inner()).clone()
+        CLIENT.get_or_init(Self::new_inner).clone()
     }
 
     pub(crate) fn new_inner() -> Self {
-        let (address_tx, address_rx) = create_pair();
-        let (link_tx, link_rx) = create_pair();
-        let (neighbor_tx, neighbor_rx) = create_pair();
-        let (route_tx, route_rx) = create_pair();
-        let (virtual_interface_tx, virtual_interface_rx) = create_pair();
+        #[cfg(feature = "address")]
+        let (address_tx, address_rx) = ftth_common::channel::create_pair();
+        #[cfg(feature = "link")]
+        let (link_tx, link_rx) = ftth_common::channel::create_pair();
+        #[cfg(feature = "neighbor")]
+        let (neighbor_tx, neighbor_rx) = ftth_common::channel::create_pair();
+        #[cfg(feature = "nexthop")]
+        let (nexthop_tx, nexthop_rx) = ftth_common::channel::create_pair();
+        #[cfg(feature = "route")]
+        let (route_tx, route_rx) = ftth_common::channel::create_pair();
+        #[cfg(feature = "rule")]
+        let (rule_tx, rule_rx) = ftth_common::channel::create_pair();
+        #[cfg(feature = "virtual-interface")]
+        let (virtual_interface_tx, virtual_interface_rx) = ftth_common::channel::create_pair();
 
         let receiver_container = Arc::new(Mutex::new(None));
         let receiver_container_clone = receiver_container.clone();
@@ -65,36 +126,108 @@ impl RtnlClient {
             };
 
             let _ = rt.block_on(async {
-                let (connection, handle, receiver) = rtnetlink::new_connection()?;
+                #[cfg_attr(
+                    not(any(
+                        feature = "address",
+                        feature = "link",
+                        feature = "neighbor",
+                        feature = "route",
+                        feature = "rule",
+                        feature = "virtual-interface"
+                    )),
+                    allow(unused_variables)
+                )]
+                let (mut connection, handle, receiver) = rtnetlink::new_connection()?;
+                if let Err(e) = connection.socket_mut().socket_mut().set_ext_ack(true) {
+                    tracing::warn!("Failed to enable netlink extended ack: {}", e);
+                }
 
                 {
-                    *(receiver_container_clone.lock().map_err(|_e| std::io::Error::other("Poison error"))?) = Some(Box::new(receiver) as Box<dyn Any + Send>);
+                    *(receiver_container_clone
+                        .lock()
+                        .map_err(|_e| std::io::Error::other("Poison error"))?) =
+                        Some(Box::new(receiver) as Box<dyn Any + Send>);
                 }
 
                 tokio::spawn(connection);
 
-                let mut futures = Vec::new();
-                futures.push(address::run_server(address_rx, handle.address()).boxed());
-                futures.push(link::run_server(link_rx, handle.link()).boxed());
-                futures.push(neighbor::run_server(neighbor_rx, handle.neighbours()).boxed());
-                futures.push(route::run_server(route_rx, handle.route()).boxed());
-                futures.push(
-                    virtual_interface::run_server(virtual_interface_rx, handle.link()).boxed(),
-                );
+                // Filtered route dumps need NETLINK_GET_STRICT_CHK, which would
+                // also reject some of the other requests, so they get their own socket.
+                #[cfg(feature = "route")]
+                let dump_handle = {
+                    let (mut connection, handle, _) = rtnetlink::new_connection()?;
+                    if let Err(e) = connection
+                        .socket_mut()
+                        .socket_mut()
+                        .set_netlink_get_strict_chk(true)
+                    {
+                        tracing::warn!("Failed to enable netlink strict checking: {}", e);
+                    }
+                    tokio::spawn(connection);
+                    handle
+                };
 
-                join_all(futures).await;
+                // netlink-packet-route cannot carry nexthop messages, so nexthop
+                // objects are managed over a connection typed for them.
+                #[cfg(feature = "nexthop")]
+                let nexthop_handle = {
+                    let (mut connection, handle, _) =
+                        rtnetlink::proto::new_connection(rtnetlink::sys::protocols::NETLINK_ROUTE)?;
+                    if let Err(e) = connection.socket_mut().socket_mut().set_ext_ack(true) {
+                        tracing::warn!("Failed to enable netlink extended ack: {}", e);
+                    }
+                    tokio::spawn(connection);
+                    handle
+                };
 
+                let futures: Vec<BoxFuture<'static, ()>> = vec![
+                    #[cfg(feature = "address")]
+                    Box::pin(address::run_server(
+                        address_rx,
+                        handle.address(),
+                        handle.link(),
+                    )),
+                    #[cfg(feature = "link")]
+                    Box::pin(link::run_server(link_rx, handle.link())),
+                    #[cfg(feature = "neighbor")]
+                    Box::pin(neighbor::run_server(neighbor_rx, handle.clone())),
+                    #[cfg(feature = "nexthop")]
+                    Box::pin(nexthop::run_server(nexthop_rx, nexthop_handle)),
+                    #[cfg(feature = "route")]
+                    Box::pin(route::run_server(
+                        route_rx,
+                        handle.clone(),
+                        dump_handle.route(),
+                    )),
+                    #[cfg(feature = "rule")]
+                    Box::pin(rule::run_server(rule_rx, handle.rule())),
+                    #[cfg(feature = "virtual-interface")]
+                    Box::pin(virtual_interface::run_server(
+                        virtual_interface_rx,
+                        handle.link(),
+                    )),
+                ];
 
+                join_all(futures).await;
 
                 Ok::<(), std::io::Error>(())
             });
         });
 
         Self {
+            #[cfg(feature = "address")]
             address: address::RtnlAddressClient::new(address_tx),
+            #[cfg(feature = "link")]
             link: link::RtnlLinkClient::new(link_tx),
+            #[cfg(feature = "neighbor")]
             neighbor: neighbor::RtnlNeighborClient::new(neighbor_tx),
+            #[cfg(feature = "nexthop")]
+            nexthop: nexthop::RtnlNexthopClient::new(nexthop_tx),
+            #[cfg(feature = "route")]
             route: route::RtnlRouteClient::new(route_tx),
+            #[cfg(feature = "rule")]
+            rule: rule::RtnlRuleClient::new(rule_tx),
+            #[cfg(feature = "virtual-interface")]
             virtual_interface: virtual_interface::RtnlVirtualInterfaceClient::new(
                 virtual_interface_tx,
             ),
@@ -102,22 +235,37 @@ impl RtnlClient {
         }
     }
 
+    #[cfg(feature = "address")]
     pub fn address(&self) -> address::RtnlAddressClient {
         self.address.clone()
     }
 
+    #[cfg(feature = "link")]
     pub fn link(&self) -> link::RtnlLinkClient {
         self.link.clone()
     }
 
+    #[cfg(feature = "neighbor")]
     pub fn neighbor(&self) -> neighbor::RtnlNeighborClient {
         self.neighbor.clone()
     }
 
+    #[cfg(feature = "nexthop")]
+    pub fn nexthop(&self) -> nexthop::RtnlNexthopClient {
+        self.nexthop.clone()
+    }
+
+    #[cfg(feature = "route")]
     pub fn route(&self) -> route::RtnlRouteClient {
         self.route.clone()
     }
 
+    #[cfg(feature = "rule")]
+    pub fn rule(&self) -> rule::RtnlRuleClient {
+        self.rule.clone()
+    }
+
+    #[cfg(feature = "virtual-interface")]
     pub fn virtual_interface(&self) -> virtual_interface::RtnlVirtualInterfaceClient {
         self.virtual_interface.clone()
     }
@@ -6,14 +6,36 @@ use futures::TryStreamExt;
 
 use std::fmt::{Debug, Display};
 use std::io::{self, ErrorKind};
+use std::net::Ipv6Addr;
+use std::str::FromStr;
+#[cfg(feature = "monitor")]
+use std::time::{Duration, Instant};
 
-use netlink_packet_route::link::{LinkFlags, LinkHeader, LinkLayerType};
+use netlink_packet_core::{DefaultNla, Nla};
+use netlink_packet_route::AddressFamily;
+use netlink_packet_route::link::{
+    AfSpecBridge, BridgePortState, InfoKind, LinkAttribute, LinkExtentMask, LinkFlags, LinkHeader,
+    LinkInfo, LinkLayerType, LinkMessage, LinkProtoInfoBridge, LinkProtocolDownReason, State,
+    Stats, Stats64,
+};
 use rtnetlink::{LinkMessageBuilder, LinkUnspec};
 
+use crate::error::RtnlError;
+#[cfg(feature = "monitor")]
+use crate::monitor::{RtnlEvent, RtnlGroup, RtnlMonitor};
+
+const ENODEV: i32 = 19;
+
+const IFLA_BRPORT_STATE: u16 = 1;
+
+const IFLA_GRO_MAX_SIZE: u16 = 58;
+const IFLA_GSO_IPV4_MAX_SIZE: u16 = 63;
+const IFLA_GRO_IPV4_MAX_SIZE: u16 = 64;
+
 pub(crate) type Client = AsyncWorldClient<RtnlLinkRequest, RtnlLinkResponse>;
 pub(crate) type Server = AsyncWorldServer<RtnlLinkRequest, RtnlLinkResponse>;
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct MacAddr {
     pub inner: [u8; 6],
 }
@@ -22,11 +44,124 @@ impl MacAddr {
     pub const fn new(inner: [u8; 6]) -> Self {
         Self { inner }
     }
+
+    pub const fn octets(&self) -> [u8; 6] {
+        self.inner
+    }
+
+    pub const fn is_multicast(&self) -> bool {
+        self.inner[0] & 0x01 != 0
+    }
+
+    pub const fn is_locally_administered(&self) -> bool {
+        self.inner[0] & 0x02 != 0
+    }
+
+    pub fn random_locally_administered() -> io::Result<Self> {
+        let mut inner = [0u8; 6];
+        getrandom::fill(&mut inner).map_err(io::Error::other)?;
+        inner[0] = (inner[0] & !0x01) | 0x02;
+        Ok(Self { inner })
+    }
+
+    pub const fn to_eui64(&self) -> [u8; 8] {
+        let m = self.inner;
+        [m[0] ^ 0x02, m[1], m[2], 0xff, 0xfe, m[3], m[4], m[5]]
+    }
+
+    pub const fn to_eui64_link_local(&self) -> Ipv6Addr {
+        let e = self.to_eui64();
+        Ipv6Addr::new(
+            0xfe80,
+            0,
+            0,
+            0,
+            u16::from_be_bytes([e[0], e[1]]),
+            u16::from_be_bytes([e[2], e[3]]),
+            u16::from_be_bytes([e[4], e[5]]),
+            u16::from_be_bytes([e[6], e[7]]),
+        )
+    }
+
+    pub const fn to_ipv6_link_local(&self) -> Ipv6Addr {
+        self.to_eui64_link_local()
+    }
 }
 
-impl Default for MacAddr {
-    fn default() -> Self {
-        Self { inner: [0; 6] }
+impl FromStr for MacAddr {
+    type Err = io::Error;
+
+    // Accepts `aa:bb:cc:dd:ee:ff`, `aa-bb-cc-dd-ee-ff` and `aabbccddeeff`.
+    fn from_str(s: &str) -> io::Result<Self> {
+        let invalid = || {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid MAC address: {}", s),
+            )
+        };
+        let parts: Vec<&str> = if s.contains(':') {
+            s.split(':').collect()
+        } else if s.contains('-') {
+            s.split('-').collect()
+        } else if s.len() == 12 && s.is_ascii() {
+            (0..6).map(|i| &s[i * 2..i * 2 + 2]).collect()
+        } else {
+            return Err(invalid());
+        };
+        if parts.len() != 6 {
+            return Err(invalid());
+        }
+
+        let mut inner = [0u8; 6];
+        for (byte, part) in inner.iter_mut().zip(parts) {
+            if part.is_empty() || part.len() > 2 || !part.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(invalid());
+            }
+            *byte = u8::from_str_radix(part, 16).map_err(|_| invalid())?;
+        }
+        Ok(Self { inner })
+    }
+}
+
+impl From<[u8; 6]> for MacAddr {
+    fn from(inner: [u8; 6]) -> Self {
+        Self { inner }
+    }
+}
+
+impl From<MacAddr> for [u8; 6] {
+    fn from(addr: MacAddr) -> Self {
+        addr.inner
+    }
+}
+
+impl TryFrom<&[u8]> for MacAddr {
+    type Error = io::Error;
+
+    fn try_from(bytes: &[u8]) -> io::Result<Self> {
+        let inner: [u8; 6] = bytes.try_into().map_err(|_| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("MAC address must be 6 bytes, got {}", bytes.len()),
+            )
+        })?;
+        Ok(Self { inner })
+    }
+}
+
+#[cfg(feature = "macaddr")]
+impl From<macaddr::MacAddr6> for MacAddr {
+    fn from(addr: macaddr::MacAddr6) -> Self {
+        Self {
+            inner: addr.into_array(),
+        }
+    }
+}
+
+#[cfg(feature = "macaddr")]
+impl From<MacAddr> for macaddr::MacAddr6 {
+    fn from(addr: MacAddr) -> Self {
+        macaddr::MacAddr6::from(addr.inner)
     }
 }
 
@@ -57,16 +192,497 @@ pub struct Interface {
     pub link_layer_type: LinkLayerType,
 }
 
+impl TryFrom<LinkMessage> for Interface {
+    type Error = io::Error;
+
+    fn try_from(message: LinkMessage) -> io::Result<Self> {
+        interface_from_message(&message).ok_or_else(|| {
+            io::Error::new(ErrorKind::InvalidData, "Link message has no index or name")
+        })
+    }
+}
+
+impl From<&Interface> for LinkMessage {
+    fn from(interface: &Interface) -> Self {
+        let header = LinkHeader {
+            index: interface.if_id,
+            link_layer_type: interface.link_layer_type,
+            ..Default::default()
+        };
+        LinkMessageBuilder::<LinkUnspec>::new()
+            .set_header(header)
+            .name(interface.if_name.clone())
+            .build()
+    }
+}
+
+impl From<Interface> for LinkMessage {
+    fn from(interface: Interface) -> Self {
+        LinkMessage::from(&interface)
+    }
+}
+
+fn interface_from_message(message: &LinkMessage) -> Option<Interface> {
+    if message.header.index == 0 {
+        return None;
+    }
+    let if_name = message.attributes.iter().find_map(|attr| {
+        if let LinkAttribute::IfName(name) = attr {
+            Some(name.clone())
+        } else {
+            None
+        }
+    })?;
+    Some(Interface {
+        if_name,
+        if_id: message.header.index,
+        link_layer_type: message.header.link_layer_type,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkDetail {
+    pub if_id: u32,
+    pub if_name: String,
+    pub link_layer_type: LinkLayerType,
+    pub flags: LinkFlags,
+    pub oper_state: State,
+    pub mtu: Option<u32>,
+    pub mac_addr: Option<MacAddr>,
+    pub kind: Option<InfoKind>,
+    pub master: Option<u32>,
+    pub parent: Option<u32>,
+    pub txqueuelen: Option<u32>,
+    pub num_tx_queues: Option<u32>,
+    pub num_rx_queues: Option<u32>,
+}
+
+fn link_detail_from_message(message: &LinkMessage) -> Option<LinkDetail> {
+    let interface = interface_from_message(message)?;
+    let mut detail = LinkDetail {
+        if_id: interface.if_id,
+        if_name: interface.if_name,
+        link_layer_type: interface.link_layer_type,
+        flags: message.header.flags,
+        oper_state: State::Unknown,
+        mtu: None,
+        mac_addr: None,
+        kind: None,
+        master: None,
+        parent: None,
+        txqueuelen: None,
+        num_tx_queues: None,
+        num_rx_queues: None,
+    };
+    for attr in message.attributes.iter() {
+        match attr {
+            LinkAttribute::OperState(state) => detail.oper_state = *state,
+            LinkAttribute::Mtu(mtu) => detail.mtu = Some(*mtu),
+            LinkAttribute::Address(addr) => detail.mac_addr = MacAddr::try_from(&addr[..]).ok(),
+            LinkAttribute::LinkInfo(infos) => detail.kind = info_kind(infos),
+            LinkAttribute::Controller(master) => detail.master = Some(*master),
+            LinkAttribute::Link(parent) => detail.parent = parent_if_id(*parent),
+            LinkAttribute::TxQueueLen(len) => detail.txqueuelen = Some(*len),
+            LinkAttribute::NumTxQueues(queues) => detail.num_tx_queues = Some(*queues),
+            LinkAttribute::NumRxQueues(queues) => detail.num_rx_queues = Some(*queues),
+            _ => {}
+        }
+    }
+    Some(detail)
+}
+
+// IFLA_LINK of 0 means the device is not bound to a lower device, e.g. a
+// tunnel without `dev`.
+fn parent_if_id(link: u32) -> Option<u32> {
+    (link != 0).then_some(link)
+}
+
+fn parent_from_message(message: &LinkMessage) -> Option<u32> {
+    message.attributes.iter().find_map(|attr| match attr {
+        LinkAttribute::Link(link) => parent_if_id(*link),
+        _ => None,
+    })
+}
+
+fn info_kind(infos: &[LinkInfo]) -> Option<InfoKind> {
+    infos.iter().find_map(|info| match info {
+        LinkInfo::Kind(kind) => Some(kind.clone()),
+        _ => None,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LinkKind {
+    // Devices without IFLA_INFO_KIND: NICs, but also the loopback device.
+    Physical,
+    Vlan,
+    Gre,
+    Bridge,
+    Bond,
+    Unknown(String),
+}
+
+impl LinkKind {
+    pub fn from_info_kind(kind: Option<&InfoKind>) -> Self {
+        match kind {
+            None => LinkKind::Physical,
+            Some(InfoKind::Vlan) => LinkKind::Vlan,
+            Some(InfoKind::GreTun | InfoKind::GreTap | InfoKind::GreTun6 | InfoKind::GreTap6) => {
+                LinkKind::Gre
+            }
+            Some(InfoKind::Bridge) => LinkKind::Bridge,
+            Some(InfoKind::Bond) => LinkKind::Bond,
+            Some(other) => LinkKind::Unknown(other.to_string()),
+        }
+    }
+}
+
+fn link_kind_from_message(message: &LinkMessage) -> LinkKind {
+    let kind = message.attributes.iter().find_map(|attr| match attr {
+        LinkAttribute::LinkInfo(infos) => info_kind(infos),
+        _ => None,
+    });
+    LinkKind::from_info_kind(kind.as_ref())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OffloadLimit {
+    GsoMaxSize,
+    GroMaxSize,
+    GsoIpv4MaxSize,
+    GroIpv4MaxSize,
+}
+
+impl OffloadLimit {
+    fn attribute(self, size: u32) -> LinkAttribute {
+        let kind = match self {
+            OffloadLimit::GsoMaxSize => return LinkAttribute::GsoMaxSize(size),
+            OffloadLimit::GroMaxSize => IFLA_GRO_MAX_SIZE,
+            OffloadLimit::GsoIpv4MaxSize => IFLA_GSO_IPV4_MAX_SIZE,
+            OffloadLimit::GroIpv4MaxSize => IFLA_GRO_IPV4_MAX_SIZE,
+        };
+        LinkAttribute::Other(DefaultNla::new(kind, size.to_ne_bytes().to_vec()))
+    }
+}
+
+// A set of attribute changes applied to one interface with a single
+// RTM_SETLINK.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LinkUpdate {
+    pub if_id: u32,
+    pub name: Option<String>,
+    pub admin_up: Option<bool>,
+    pub mtu: Option<u32>,
+    pub mac_addr: Option<MacAddr>,
+    pub promiscuous: Option<bool>,
+    pub arp: Option<bool>,
+    pub all_multicast: Option<bool>,
+    pub master: Option<Option<u32>>,
+    pub group: Option<u32>,
+    pub txqueuelen: Option<u32>,
+}
+
+impl LinkUpdate {
+    pub fn new(if_id: u32) -> Self {
+        Self {
+            if_id,
+            ..Default::default()
+        }
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn up(mut self) -> Self {
+        self.admin_up = Some(true);
+        self
+    }
+
+    pub fn down(mut self) -> Self {
+        self.admin_up = Some(false);
+        self
+    }
+
+    pub fn mtu(mut self, mtu: u32) -> Self {
+        self.mtu = Some(mtu);
+        self
+    }
+
+    pub fn mac(mut self, mac_addr: MacAddr) -> Self {
+        self.mac_addr = Some(mac_addr);
+        self
+    }
+
+    pub fn promiscuous(mut self, enable: bool) -> Self {
+        self.promiscuous = Some(enable);
+        self
+    }
+
+    pub fn arp(mut self, enable: bool) -> Self {
+        self.arp = Some(enable);
+        self
+    }
+
+    pub fn all_multicast(mut self, enable: bool) -> Self {
+        self.all_multicast = Some(enable);
+        self
+    }
+
+    pub fn master(mut self, master: u32) -> Self {
+        self.master = Some(Some(master));
+        self
+    }
+
+    pub fn nomaster(mut self) -> Self {
+        self.master = Some(None);
+        self
+    }
+
+    pub fn group(mut self, group: u32) -> Self {
+        self.group = Some(group);
+        self
+    }
+
+    pub fn txqueuelen(mut self, txqueuelen: u32) -> Self {
+        self.txqueuelen = Some(txqueuelen);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::new(self.if_id)
+    }
+
+    fn build(&self) -> LinkMessage {
+        let mut builder = LinkMessageBuilder::<LinkUnspec>::new().index(self.if_id);
+        if let Some(name) = &self.name {
+            builder = builder.name(name.clone());
+        }
+        if let Some(up) = self.admin_up {
+            builder = if up { builder.up() } else { builder.down() };
+        }
+        if let Some(mtu) = self.mtu {
+            builder = builder.mtu(mtu);
+        }
+        if let Some(mac_addr) = self.mac_addr {
+            builder = builder.address(mac_addr.inner.to_vec());
+        }
+        if let Some(enable) = self.promiscuous {
+            builder = builder.promiscuous(enable);
+        }
+        if let Some(enable) = self.arp {
+            builder = builder.arp(enable);
+        }
+        if let Some(master) = self.master {
+            builder = match master {
+                Some(master) => builder.controller(master),
+                None => builder.nocontroller(),
+            };
+        }
+        if let Some(group) = self.group {
+            builder = builder.append_extra_attribute(LinkAttribute::Group(group));
+        }
+        if let Some(txqueuelen) = self.txqueuelen {
+            builder = builder.append_extra_attribute(LinkAttribute::TxQueueLen(txqueuelen));
+        }
+
+        let mut message = builder.build();
+        if let Some(enable) = self.all_multicast {
+            if enable {
+                message.header.flags |= LinkFlags::Allmulti;
+            } else {
+                message.header.flags.remove(LinkFlags::Allmulti);
+            }
+            message.header.change_mask |= LinkFlags::Allmulti;
+        }
+        message
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CarrierStats {
+    pub changes: u32,
+    pub up_count: u32,
+    pub down_count: u32,
+}
+
+fn carrier_stats_from_message(message: &LinkMessage) -> CarrierStats {
+    let mut stats = CarrierStats::default();
+    for attr in message.attributes.iter() {
+        match attr {
+            LinkAttribute::CarrierChanges(count) => stats.changes = *count,
+            LinkAttribute::CarrierUpCount(count) => stats.up_count = *count,
+            LinkAttribute::CarrierDownCount(count) => stats.down_count = *count,
+            _ => {}
+        }
+    }
+    stats
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProtoDown {
+    pub enabled: bool,
+    // Bitmask of reasons set by the applications that requested protodown.
+    pub reason: u32,
+}
+
+fn proto_down_from_message(message: &LinkMessage) -> Option<ProtoDown> {
+    let mut proto_down = None;
+    let mut reason = 0;
+    for attr in message.attributes.iter() {
+        match attr {
+            LinkAttribute::ProtoDown(value) => proto_down = Some(*value != 0),
+            LinkAttribute::ProtoDownReason(reasons) => {
+                for r in reasons {
+                    if let LinkProtocolDownReason::Value(value) = r {
+                        reason = *value;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    proto_down.map(|enabled| ProtoDown { enabled, reason })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LinkStats {
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errors: u64,
+    pub tx_dropped: u64,
+}
+
+impl From<&Stats64> for LinkStats {
+    fn from(stats: &Stats64) -> Self {
+        Self {
+            rx_bytes: stats.rx_bytes,
+            rx_packets: stats.rx_packets,
+            rx_errors: stats.rx_errors,
+            rx_dropped: stats.rx_dropped,
+            tx_bytes: stats.tx_bytes,
+            tx_packets: stats.tx_packets,
+            tx_errors: stats.tx_errors,
+            tx_dropped: stats.tx_dropped,
+        }
+    }
+}
+
+impl From<&Stats> for LinkStats {
+    fn from(stats: &Stats) -> Self {
+        Self {
+            rx_bytes: stats.rx_bytes as u64,
+            rx_packets: stats.rx_packets as u64,
+            rx_errors: stats.rx_errors as u64,
+            rx_dropped: stats.rx_dropped as u64,
+            tx_bytes: stats.tx_bytes as u64,
+            tx_packets: stats.tx_packets as u64,
+            tx_errors: stats.tx_errors as u64,
+            tx_dropped: stats.tx_dropped as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BridgePort {
+    pub if_id: u32,
+    pub if_name: String,
+    pub master: Option<u32>,
+    pub state: BridgePortState,
+    pub has_vlan_info: bool,
+}
+
+impl BridgePort {
+    pub fn is_forwarding(&self) -> bool {
+        self.state == BridgePortState::Forwarding
+    }
+}
+
+// Decodes one entry of an AF_BRIDGE link dump. Entries without
+// IFLA_PROTINFO describe the bridge itself rather than a port.
+fn bridge_port_from_message(message: &LinkMessage) -> Option<BridgePort> {
+    let interface = interface_from_message(message)?;
+    let mut master = None;
+    let mut state = None;
+    let mut has_vlan_info = false;
+    for attr in message.attributes.iter() {
+        match attr {
+            LinkAttribute::Controller(id) if *id != interface.if_id => master = Some(*id),
+            LinkAttribute::ProtoInfoBridge(infos) => state = Some(bridge_port_state(infos)),
+            LinkAttribute::AfSpecBridge(specs) => {
+                has_vlan_info |= specs
+                    .iter()
+                    .any(|spec| matches!(spec, AfSpecBridge::VlanInfo(_)));
+            }
+            _ => {}
+        }
+    }
+    Some(BridgePort {
+        if_id: interface.if_id,
+        if_name: interface.if_name,
+        master,
+        state: state?,
+        has_vlan_info,
+    })
+}
+
+// netlink-packet-route leaves the IFLA_BRPORT_* attributes undecoded.
+fn bridge_port_state(infos: &[LinkProtoInfoBridge]) -> BridgePortState {
+    infos
+        .iter()
+        .find_map(|info| match info {
+            LinkProtoInfoBridge::Other(nla) if nla.kind() == IFLA_BRPORT_STATE => {
+                let mut value = vec![0u8; nla.value_len()];
+                nla.emit_value(&mut value);
+                value.first().map(|state| BridgePortState::from(*state))
+            }
+            _ => None,
+        })
+        .unwrap_or(BridgePortState::Disabled)
+}
+
+// Prefers the 64-bit counters; the 32-bit block is only used by kernels or
+// drivers that do not report IFLA_STATS64.
+fn link_stats_from_message(message: &LinkMessage) -> Option<LinkStats> {
+    let stats64 = message.attributes.iter().find_map(|attr| match attr {
+        LinkAttribute::Stats64(stats) => Some(LinkStats::from(stats)),
+        _ => None,
+    });
+    stats64.or_else(|| {
+        message.attributes.iter().find_map(|attr| match attr {
+            LinkAttribute::Stats(stats) => Some(LinkStats::from(stats)),
+            _ => None,
+        })
+    })
+}
+
 #[derive(Debug, Clone, PartialEq)]
 #[non_exhaustive]
 pub enum RtnlLinkRequest {
     InterfaceList,
+    InterfaceListDetailed,
+    BridgePortList,
+    InterfaceListByKind {
+        kind: LinkKind,
+    },
     InterfaceGet {
         if_id: u32,
     },
     InterfaceGetByName {
         if_name: String,
     },
+    InterfaceGetByMac {
+        mac_addr: MacAddr,
+    },
+    InterfaceDetailGet {
+        if_id: u32,
+    },
     MacAddrGet {
         if_id: u32,
     },
@@ -78,6 +694,63 @@ pub enum RtnlLinkRequest {
     MtuGet {
         if_id: u32,
     },
+    MtuRangeGet {
+        if_id: u32,
+    },
+    StatsGet {
+        if_id: u32,
+    },
+    OperStateGet {
+        if_id: u32,
+    },
+    CarrierGet {
+        if_id: u32,
+    },
+    CarrierStatsGet {
+        if_id: u32,
+    },
+    KindGet {
+        if_id: u32,
+    },
+    GroupGet {
+        if_id: u32,
+    },
+    GroupSet {
+        if_id: u32,
+        group: u32,
+    },
+    GroupSetAdmin {
+        group: u32,
+        up: bool,
+    },
+    GroupSetMtu {
+        group: u32,
+        mtu: u32,
+    },
+    OffloadMaxSizeSet {
+        if_id: u32,
+        limit: OffloadLimit,
+        size: u32,
+    },
+    ProtoDownGet {
+        if_id: u32,
+    },
+    MasterGet {
+        if_id: u32,
+    },
+    MasterSet {
+        if_id: u32,
+        master: Option<u32>,
+    },
+    ParentGet {
+        if_id: u32,
+    },
+    ProtoDownSet {
+        if_id: u32,
+        enabled: bool,
+        reason_mask: u32,
+        reason_value: u32,
+    },
     InterfaceSetAdmin {
         if_id: u32,
         up: bool,
@@ -102,6 +775,7 @@ pub enum RtnlLinkRequest {
         if_id: u32,
         enable: bool,
     },
+    Apply(LinkUpdate),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -114,8 +788,22 @@ pub enum RtnlLinkResponse {
     NotFound,
     InterfaceList(Vec<Interface>),
     Interface(Interface),
+    InterfaceDetail(LinkDetail),
+    InterfaceDetailList(Vec<LinkDetail>),
+    BridgePortList(Vec<BridgePort>),
     MacAddr(MacAddr),
     Mtu(u32),
+    MtuRange(u32, u32),
+    Stats(LinkStats),
+    OperState(State),
+    Carrier(bool),
+    CarrierStats(CarrierStats),
+    Kind(LinkKind),
+    Group(u32),
+    ProtoDown(ProtoDown),
+    Master(Option<u32>),
+    Parent(Option<u32>),
+    Error(RtnlError),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -128,115 +816,453 @@ impl RtnlLinkClient {
         Self { client }
     }
 
+    pub fn send_raw(&self, request: RtnlLinkRequest) -> io::Result<RtnlLinkResponse> {
+        self.client.send_request(request)
+    }
+
+    pub fn apply(&self, update: LinkUpdate) -> io::Result<()> {
+        if update.is_empty() {
+            return Ok(());
+        }
+        let res = self.client.send_request(RtnlLinkRequest::Apply(update))?;
+        handle_status_response("Apply link update", res)
+    }
+
     pub fn interface_set_up(&self, if_id: u32) -> io::Result<()> {
         self.interface_set_admin_state(if_id, true)
     }
 
-    pub fn interface_set_down(&self, if_id: u32) -> io::Result<()> {
-        self.interface_set_admin_state(if_id, false)
+    pub fn interface_set_down(&self, if_id: u32) -> io::Result<()> {
+        self.interface_set_admin_state(if_id, false)
+    }
+
+    pub fn interface_set_admin_state(&self, if_id: u32, up: bool) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::InterfaceSetAdmin { if_id, up })?;
+        let op = if up {
+            "Set interface up"
+        } else {
+            "Set interface down"
+        };
+        handle_status_response(op, res)
+    }
+
+    pub fn interface_set_promiscuous(&self, if_id: u32, enable: bool) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::InterfaceSetPromisc { if_id, enable })?;
+        handle_status_response(
+            if enable {
+                "Enable promiscuous mode"
+            } else {
+                "Disable promiscuous mode"
+            },
+            res,
+        )
+    }
+
+    pub fn interface_set_arp(&self, if_id: u32, enable: bool) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::InterfaceSetArp { if_id, enable })?;
+        handle_status_response(if enable { "Enable ARP" } else { "Disable ARP" }, res)
+    }
+
+    pub fn interface_set_mtu(&self, if_id: u32, mtu: u32) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::InterfaceSetMtu { if_id, mtu })?;
+        handle_status_response("Set MTU", res)
+    }
+
+    pub fn interface_rename(&self, if_id: u32, new_name: &str) -> io::Result<()> {
+        let res = self.client.send_request(RtnlLinkRequest::InterfaceRename {
+            if_id,
+            if_name: new_name.to_owned(),
+        })?;
+        handle_status_response("Rename interface", res)
+    }
+
+    pub fn interface_get(&self, if_id: u32) -> io::Result<Interface> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::InterfaceGet { if_id })?;
+        match res {
+            RtnlLinkResponse::Interface(interface) => Ok(interface),
+            RtnlLinkResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
+            }
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get interface")),
+            _ => Err(io::Error::other("Failed to get interface")),
+        }
+    }
+
+    pub fn interface_get_by_name(&self, name: &str) -> std::io::Result<Interface> {
+        let name = name.to_owned();
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::InterfaceGetByName { if_name: name })?;
+        match res {
+            RtnlLinkResponse::Interface(interface) => Ok(interface),
+            RtnlLinkResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
+            }
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get interface by name")),
+            _ => Err(std::io::Error::other("Not found")),
+        }
+    }
+
+    pub fn interface_exists(&self, name: &str) -> io::Result<bool> {
+        match self.interface_get_by_name(name) {
+            Ok(_) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn interface_get_by_mac(&self, mac_addr: MacAddr) -> io::Result<Interface> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::InterfaceGetByMac { mac_addr })?;
+        match res {
+            RtnlLinkResponse::Interface(interface) => Ok(interface),
+            RtnlLinkResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
+            }
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get interface by MAC")),
+            _ => Err(io::Error::other("Failed to get interface by MAC")),
+        }
+    }
+
+    pub fn interface_detail_get(&self, if_id: u32) -> io::Result<LinkDetail> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::InterfaceDetailGet { if_id })?;
+        match res {
+            RtnlLinkResponse::InterfaceDetail(detail) => Ok(detail),
+            RtnlLinkResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
+            }
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get interface detail")),
+            _ => Err(io::Error::other("Failed to get interface detail")),
+        }
+    }
+
+    pub fn mac_addr_get(&self, if_id: u32) -> std::io::Result<Option<MacAddr>> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::MacAddrGet { if_id })?;
+        match res {
+            RtnlLinkResponse::MacAddr(addr) => Ok(Some(addr)),
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get MAC address")),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn mtu_get(&self, if_id: u32) -> io::Result<u32> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::MtuGet { if_id })?;
+        match res {
+            RtnlLinkResponse::Mtu(mtu) => Ok(mtu),
+            RtnlLinkResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
+            }
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get MTU")),
+            _ => Err(io::Error::other("Failed to get MTU")),
+        }
+    }
+
+    pub fn link_stats_get(&self, if_id: u32) -> io::Result<LinkStats> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::StatsGet { if_id })?;
+        match res {
+            RtnlLinkResponse::Stats(stats) => Ok(stats),
+            RtnlLinkResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
+            }
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get link statistics")),
+            _ => Err(io::Error::other("Failed to get link statistics")),
+        }
+    }
+
+    pub fn oper_state_get(&self, if_id: u32) -> io::Result<State> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::OperStateGet { if_id })?;
+        match res {
+            RtnlLinkResponse::OperState(state) => Ok(state),
+            RtnlLinkResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
+            }
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get operational state")),
+            _ => Err(io::Error::other("Failed to get operational state")),
+        }
+    }
+
+    pub fn carrier_get(&self, if_id: u32) -> io::Result<bool> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::CarrierGet { if_id })?;
+        match res {
+            RtnlLinkResponse::Carrier(carrier) => Ok(carrier),
+            RtnlLinkResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
+            }
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get carrier")),
+            _ => Err(io::Error::other("Failed to get carrier")),
+        }
+    }
+
+    // Subscribes to link notifications before reading the current state, so
+    // a transition between the two cannot be missed.
+    #[cfg(feature = "monitor")]
+    pub fn wait_oper_up(&self, if_id: u32, timeout: Duration) -> io::Result<()> {
+        let monitor = RtnlMonitor::new(&[RtnlGroup::Link])?;
+        if self.oper_state_get(if_id)? == State::Up {
+            return Ok(());
+        }
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let event = monitor.recv_timeout(remaining)?.ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::TimedOut,
+                    format!("Interface {} did not come up", if_id),
+                )
+            })?;
+            match event {
+                RtnlEvent::NewLink(message) if message.header.index == if_id => {
+                    let up = message
+                        .attributes
+                        .iter()
+                        .any(|attr| matches!(attr, LinkAttribute::OperState(State::Up)));
+                    if up {
+                        return Ok(());
+                    }
+                }
+                RtnlEvent::DelLink(message) if message.header.index == if_id => {
+                    return Err(io::Error::new(ErrorKind::NotFound, "Interface removed"));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn carrier_stats_get(&self, if_id: u32) -> io::Result<CarrierStats> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::CarrierStatsGet { if_id })?;
+        match res {
+            RtnlLinkResponse::CarrierStats(stats) => Ok(stats),
+            RtnlLinkResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
+            }
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get carrier statistics")),
+            _ => Err(io::Error::other("Failed to get carrier statistics")),
+        }
+    }
+
+    pub fn kind_get(&self, if_id: u32) -> io::Result<LinkKind> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::KindGet { if_id })?;
+        match res {
+            RtnlLinkResponse::Kind(kind) => Ok(kind),
+            RtnlLinkResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
+            }
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get link kind")),
+            _ => Err(io::Error::other("Failed to get link kind")),
+        }
+    }
+
+    pub fn group_get(&self, if_id: u32) -> io::Result<u32> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::GroupGet { if_id })?;
+        match res {
+            RtnlLinkResponse::Group(group) => Ok(group),
+            RtnlLinkResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
+            }
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get link group")),
+            _ => Err(io::Error::other("Failed to get link group")),
+        }
+    }
+
+    pub fn group_set(&self, if_id: u32, group: u32) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::GroupSet { if_id, group })?;
+        handle_status_response("Set link group", res)
+    }
+
+    // Like `ip link set group <group> up|down`: every member of the group is
+    // changed by a single RTM_NEWLINK.
+    pub fn group_set_admin_state(&self, group: u32, up: bool) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::GroupSetAdmin { group, up })?;
+        let op = if up { "Set group up" } else { "Set group down" };
+        handle_status_response(op, res)
+    }
+
+    pub fn group_set_mtu(&self, group: u32, mtu: u32) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::GroupSetMtu { group, mtu })?;
+        handle_status_response("Set group MTU", res)
+    }
+
+    pub fn gso_max_size_set(&self, if_id: u32, size: u32) -> io::Result<()> {
+        self.offload_max_size_set(if_id, OffloadLimit::GsoMaxSize, size)
+    }
+
+    pub fn gro_max_size_set(&self, if_id: u32, size: u32) -> io::Result<()> {
+        self.offload_max_size_set(if_id, OffloadLimit::GroMaxSize, size)
+    }
+
+    pub fn gso_ipv4_max_size_set(&self, if_id: u32, size: u32) -> io::Result<()> {
+        self.offload_max_size_set(if_id, OffloadLimit::GsoIpv4MaxSize, size)
     }
 
-    pub fn interface_set_admin_state(&self, if_id: u32, up: bool) -> io::Result<()> {
-        let res = self
-            .client
-            .send_request(RtnlLinkRequest::InterfaceSetAdmin { if_id, up })?;
-        let op = if up {
-            "Set interface up"
-        } else {
-            "Set interface down"
-        };
-        handle_status_response(op, res)
+    pub fn gro_ipv4_max_size_set(&self, if_id: u32, size: u32) -> io::Result<()> {
+        self.offload_max_size_set(if_id, OffloadLimit::GroIpv4MaxSize, size)
     }
 
-    pub fn interface_set_promiscuous(&self, if_id: u32, enable: bool) -> io::Result<()> {
+    pub fn offload_max_size_set(
+        &self,
+        if_id: u32,
+        limit: OffloadLimit,
+        size: u32,
+    ) -> io::Result<()> {
         let res = self
             .client
-            .send_request(RtnlLinkRequest::InterfaceSetPromisc { if_id, enable })?;
-        handle_status_response(
-            if enable {
-                "Enable promiscuous mode"
-            } else {
-                "Disable promiscuous mode"
-            },
-            res,
-        )
+            .send_request(RtnlLinkRequest::OffloadMaxSizeSet { if_id, limit, size })?;
+        handle_status_response("Set offload max size", res)
     }
 
-    pub fn interface_set_arp(&self, if_id: u32, enable: bool) -> io::Result<()> {
+    pub fn protodown_get(&self, if_id: u32) -> io::Result<ProtoDown> {
         let res = self
             .client
-            .send_request(RtnlLinkRequest::InterfaceSetArp { if_id, enable })?;
-        handle_status_response(if enable { "Enable ARP" } else { "Disable ARP" }, res)
+            .send_request(RtnlLinkRequest::ProtoDownGet { if_id })?;
+        match res {
+            RtnlLinkResponse::ProtoDown(proto_down) => Ok(proto_down),
+            RtnlLinkResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
+            }
+            RtnlLinkResponse::NotImplemented => Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "Interface does not report protodown",
+            )),
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get protodown")),
+            _ => Err(io::Error::other("Failed to get protodown")),
+        }
     }
 
-    pub fn interface_set_mtu(&self, if_id: u32, mtu: u32) -> io::Result<()> {
-        let res = self
-            .client
-            .send_request(RtnlLinkRequest::InterfaceSetMtu { if_id, mtu })?;
-        handle_status_response("Set MTU", res)
+    pub fn protodown_set(&self, if_id: u32, enabled: bool) -> io::Result<()> {
+        self.protodown_set_with_reason(if_id, enabled, 0, 0)
     }
 
-    pub fn interface_rename(&self, if_id: u32, new_name: &str) -> io::Result<()> {
-        let res = self.client.send_request(RtnlLinkRequest::InterfaceRename {
+    // The reason bits selected by `reason_mask` are updated before the
+    // protodown state, so a caller can clear its own reason and the state in
+    // one request. The kernel refuses to clear protodown while any reason
+    // bit is still set.
+    pub fn protodown_set_with_reason(
+        &self,
+        if_id: u32,
+        enabled: bool,
+        reason_mask: u32,
+        reason_value: u32,
+    ) -> io::Result<()> {
+        let res = self.client.send_request(RtnlLinkRequest::ProtoDownSet {
             if_id,
-            if_name: new_name.to_owned(),
+            enabled,
+            reason_mask,
+            reason_value,
         })?;
-        handle_status_response("Rename interface", res)
+        handle_status_response("Set protodown", res)
     }
 
-    pub fn interface_get(&self, if_id: u32) -> io::Result<Interface> {
+    pub fn mtu_range_get(&self, if_id: u32) -> io::Result<(u32, u32)> {
         let res = self
             .client
-            .send_request(RtnlLinkRequest::InterfaceGet { if_id })?;
+            .send_request(RtnlLinkRequest::MtuRangeGet { if_id })?;
         match res {
-            RtnlLinkResponse::Interface(interface) => Ok(interface),
+            RtnlLinkResponse::MtuRange(min, max) => Ok((min, max)),
             RtnlLinkResponse::NotFound => {
                 Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
             }
-            _ => Err(io::Error::other("Failed to get interface")),
+            RtnlLinkResponse::NotImplemented => Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "Interface does not report an MTU range",
+            )),
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get MTU range")),
+            _ => Err(io::Error::other("Failed to get MTU range")),
         }
     }
 
-    pub fn interface_get_by_name(&self, name: &str) -> std::io::Result<Interface> {
-        let name = name.to_owned();
-        let res = self
-            .client
-            .send_request(RtnlLinkRequest::InterfaceGetByName { if_name: name })?;
-        match res {
-            RtnlLinkResponse::Interface(interface) => {
-                return Ok(interface);
-            }
-            _ => {}
+    // Checks `mtu` against the range reported by the kernel first, so an
+    // out-of-range value fails with InvalidInput and a descriptive message.
+    pub fn interface_set_mtu_checked(&self, if_id: u32, mtu: u32) -> io::Result<()> {
+        let (min, max) = self.mtu_range_get(if_id)?;
+        // A maximum of zero means the device has no upper bound.
+        if mtu < min || (max != 0 && mtu > max) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("MTU {} is outside the supported range {}-{}", mtu, min, max),
+            ));
         }
-        Err(std::io::Error::other("Not found"))
+        self.interface_set_mtu(if_id, mtu)
     }
 
-    pub fn mac_addr_get(&self, if_id: u32) -> std::io::Result<Option<MacAddr>> {
+    pub fn master_get(&self, if_id: u32) -> io::Result<Option<u32>> {
         let res = self
             .client
-            .send_request(RtnlLinkRequest::MacAddrGet { if_id })?;
+            .send_request(RtnlLinkRequest::MasterGet { if_id })?;
         match res {
-            RtnlLinkResponse::MacAddr(addr) => {
-                return Ok(Some(addr));
+            RtnlLinkResponse::Master(master) => Ok(master),
+            RtnlLinkResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
             }
-            _ => {}
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get master")),
+            _ => Err(io::Error::other("Failed to get master")),
         }
-        Ok(None)
     }
 
-    pub fn mtu_get(&self, if_id: u32) -> io::Result<u32> {
+    pub fn parent_get(&self, if_id: u32) -> io::Result<Option<u32>> {
         let res = self
             .client
-            .send_request(RtnlLinkRequest::MtuGet { if_id })?;
+            .send_request(RtnlLinkRequest::ParentGet { if_id })?;
         match res {
-            RtnlLinkResponse::Mtu(mtu) => Ok(mtu),
+            RtnlLinkResponse::Parent(parent) => Ok(parent),
             RtnlLinkResponse::NotFound => {
                 Err(io::Error::new(ErrorKind::NotFound, "Interface not found"))
             }
-            _ => Err(io::Error::other("Failed to get MTU")),
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("Get parent link")),
+            _ => Err(io::Error::other("Failed to get parent link")),
         }
     }
 
+    pub fn set_master(&self, if_id: u32, master: u32) -> io::Result<()> {
+        let res = self.client.send_request(RtnlLinkRequest::MasterSet {
+            if_id,
+            master: Some(master),
+        })?;
+        handle_status_response("Set master", res)
+    }
+
+    pub fn unset_master(&self, if_id: u32) -> io::Result<()> {
+        let res = self.client.send_request(RtnlLinkRequest::MasterSet {
+            if_id,
+            master: None,
+        })?;
+        handle_status_response("Unset master", res)
+    }
+
     pub fn mac_addr_set(
         &self,
         if_id: u32,
@@ -268,13 +1294,82 @@ impl RtnlLinkClient {
     pub fn interface_list(&self) -> std::io::Result<Vec<Interface>> {
         let res = self.client.send_request(RtnlLinkRequest::InterfaceList)?;
         match res {
-            RtnlLinkResponse::InterfaceList(list) => {
-                return Ok(list);
+            RtnlLinkResponse::InterfaceList(list) => Ok(list),
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("List interfaces")),
+            _ => Err(std::io::Error::other("Unknown error")),
+        }
+    }
+
+    // Matches interface names against a glob where `*` matches any run of
+    // characters and `?` a single one, so `vlan-*` is a prefix match.
+    pub fn interface_find(&self, pattern: &str) -> io::Result<Vec<Interface>> {
+        let pattern: Vec<char> = pattern.chars().collect();
+        Ok(self
+            .interface_list()?
+            .into_iter()
+            .filter(|iface| {
+                let name: Vec<char> = iface.if_name.chars().collect();
+                glob_match(&pattern, &name)
+            })
+            .collect())
+    }
+
+    pub fn interface_list_detailed(&self) -> io::Result<Vec<LinkDetail>> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::InterfaceListDetailed)?;
+        match res {
+            RtnlLinkResponse::InterfaceDetailList(list) => Ok(list),
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("List interface details")),
+            _ => Err(io::Error::other("Failed to list interface details")),
+        }
+    }
+
+    pub fn bridge_port_list(&self) -> io::Result<Vec<BridgePort>> {
+        let res = self.client.send_request(RtnlLinkRequest::BridgePortList)?;
+        match res {
+            RtnlLinkResponse::BridgePortList(list) => Ok(list),
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("List bridge ports")),
+            _ => Err(io::Error::other("Failed to list bridge ports")),
+        }
+    }
+
+    pub fn interface_list_by_kind(&self, kind: LinkKind) -> io::Result<Vec<Interface>> {
+        let res = self
+            .client
+            .send_request(RtnlLinkRequest::InterfaceListByKind { kind })?;
+        match res {
+            RtnlLinkResponse::InterfaceList(list) => Ok(list),
+            RtnlLinkResponse::Error(err) => Err(err.into_io_error("List interfaces by kind")),
+            _ => Err(io::Error::other("Failed to list interfaces by kind")),
+        }
+    }
+}
+
+fn glob_match(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
             }
-            _ => {}
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
         }
-        Err(std::io::Error::other("Unknown error"))
     }
+    pattern[p..].iter().all(|&c| c == '*')
 }
 
 fn handle_status_response(op: &str, response: RtnlLinkResponse) -> io::Result<()> {
@@ -292,6 +1387,7 @@ fn handle_status_response(op: &str, response: RtnlLinkResponse) -> io::Result<()
             ErrorKind::Unsupported,
             format!("{} not implemented", op),
         )),
+        RtnlLinkResponse::Error(err) => Err(err.into_io_error(op)),
         other => Err(io::Error::other(format!(
             "{} returned unexpected response: {:?}",
             op, other
@@ -310,9 +1406,11 @@ where
 {
     let builder = LinkMessageBuilder::<LinkUnspec>::new();
     let builder = if let Some(link_layer_type) = link_layer_type {
-        let mut header = LinkHeader::default();
-        header.index = if_id;
-        header.link_layer_type = link_layer_type;
+        let header = LinkHeader {
+            index: if_id,
+            link_layer_type,
+            ..Default::default()
+        };
         builder.set_header(header)
     } else {
         builder
@@ -324,23 +1422,81 @@ where
     handle.set(message).execute().await
 }
 
+async fn apply_group_set<F>(
+    handle: &rtnetlink::LinkHandle,
+    group: u32,
+    op: F,
+) -> Result<(), rtnetlink::Error>
+where
+    F: FnOnce(LinkMessageBuilder<LinkUnspec>) -> LinkMessageBuilder<LinkUnspec>,
+{
+    // The kernel only applies group-wide changes to an RTM_NEWLINK without an
+    // index, a name or NLM_F_CREATE.
+    let builder =
+        LinkMessageBuilder::<LinkUnspec>::new().append_extra_attribute(LinkAttribute::Group(group));
+    handle.set_port(op(builder).build()).execute().await
+}
+
 fn map_link_result(result: Result<(), rtnetlink::Error>, op: &str, if_id: u32) -> RtnlLinkResponse {
     match result {
         Ok(()) => RtnlLinkResponse::Success,
-        Err(rtnetlink::Error::NetlinkError(err_msg)) => {
-            let io_err = err_msg.to_io();
-            if io_err.kind() == ErrorKind::NotFound {
-                RtnlLinkResponse::NotFound
-            } else {
-                let message = io_err.to_string();
-                tracing::warn!("Failed to {} for ifindex {}: {}", op, if_id, message);
-                RtnlLinkResponse::FailedWithMessage(message)
-            }
+        Err(rtnetlink::Error::NetlinkError(err_msg))
+            if err_msg.to_io().kind() == ErrorKind::NotFound =>
+        {
+            RtnlLinkResponse::NotFound
         }
         Err(err) => {
-            let message = err.to_string();
-            tracing::warn!("Failed to {} for ifindex {}: {}", op, if_id, message);
-            RtnlLinkResponse::FailedWithMessage(message)
+            let error = RtnlError::from_rtnetlink(&err);
+            tracing::warn!("Failed to {} for ifindex {}: {}", op, if_id, error);
+            RtnlLinkResponse::Error(error)
+        }
+    }
+}
+
+async fn get_link(
+    handle: &mut rtnetlink::LinkHandle,
+    if_id: u32,
+) -> Result<Option<LinkMessage>, RtnlError> {
+    if if_id == 0 {
+        return Ok(None);
+    }
+    first_link(handle.get().match_index(if_id).execute()).await
+}
+
+async fn get_link_by_name(
+    handle: &mut rtnetlink::LinkHandle,
+    if_name: &str,
+) -> Result<Option<LinkMessage>, RtnlError> {
+    first_link(handle.get().match_name(if_name.to_owned()).execute()).await
+}
+
+async fn query_link<F>(handle: &mut rtnetlink::LinkHandle, if_id: u32, f: F) -> RtnlLinkResponse
+where
+    F: FnOnce(&LinkMessage) -> Option<RtnlLinkResponse>,
+{
+    match get_link(handle, if_id).await {
+        Ok(Some(message)) => f(&message).unwrap_or(RtnlLinkResponse::NotFound),
+        Ok(None) => RtnlLinkResponse::NotFound,
+        Err(err) => RtnlLinkResponse::Error(err),
+    }
+}
+
+async fn first_link(
+    response: impl futures::Stream<Item = Result<LinkMessage, rtnetlink::Error>>,
+) -> Result<Option<LinkMessage>, RtnlError> {
+    futures::pin_mut!(response);
+    loop {
+        match response.try_next().await {
+            Ok(Some(message)) if message.header.index != 0 => return Ok(Some(message)),
+            Ok(Some(_)) => continue,
+            Ok(None) => return Ok(None),
+            Err(rtnetlink::Error::NetlinkError(err_msg))
+                if err_msg.to_io().kind() == ErrorKind::NotFound
+                    || err_msg.raw_code().abs() == ENODEV =>
+            {
+                return Ok(None);
+            }
+            Err(err) => return Err(RtnlError::from_rtnetlink(&err)),
         }
     }
 }
@@ -349,135 +1505,336 @@ pub(crate) async fn run_server(mut server: Server, mut handle: rtnetlink::LinkHa
     'reqloop: while let Some((req, respond)) = server.accept().await {
         match req {
             RtnlLinkRequest::InterfaceGet { if_id } => {
-                if if_id == 0 {
-                    respond(RtnlLinkResponse::NotFound);
-                    continue 'reqloop;
-                }
-
-                let response = handle.get().match_index(if_id).execute();
-                futures::pin_mut!(response);
-                while let Ok(Some(response)) = response.try_next().await {
-                    let mut if_name = None;
-                    for attr in response.attributes.iter() {
-                        if let netlink_packet_route::link::LinkAttribute::IfName(name) = attr {
-                            if_name = Some(name.clone());
-                        }
-                    }
+                let response = match get_link(&mut handle, if_id).await {
+                    Ok(Some(message)) => match interface_from_message(&message) {
+                        Some(interface) => RtnlLinkResponse::Interface(interface),
+                        None => RtnlLinkResponse::NotFound,
+                    },
+                    Ok(None) => RtnlLinkResponse::NotFound,
+                    Err(err) => RtnlLinkResponse::Error(err),
+                };
+                respond(response);
+            }
+            RtnlLinkRequest::InterfaceGetByName { if_name } => {
+                let response = match get_link_by_name(&mut handle, &if_name).await {
+                    Ok(Some(message)) => {
+                        let name = message
+                            .attributes
+                            .iter()
+                            .find_map(|attr| {
+                                if let LinkAttribute::IfName(name) = attr {
+                                    Some(name.clone())
+                                } else {
+                                    None
+                                }
+                            })
+                            .unwrap_or_else(|| if_name.to_owned());
 
-                    if let Some(name) = if_name {
-                        respond(RtnlLinkResponse::Interface(Interface {
-                            if_id,
+                        RtnlLinkResponse::Interface(Interface {
+                            if_id: message.header.index,
                             if_name: name,
-                            link_layer_type: response.header.link_layer_type,
-                        }));
-                        continue 'reqloop;
+                            link_layer_type: message.header.link_layer_type,
+                        })
                     }
-                }
-                respond(RtnlLinkResponse::NotFound);
+                    Ok(None) => RtnlLinkResponse::NotFound,
+                    Err(err) => RtnlLinkResponse::Error(err),
+                };
+                respond(response);
             }
-            RtnlLinkRequest::InterfaceGetByName { if_name } => {
-                let response = handle.get().match_name(if_name.to_owned()).execute();
-                futures::pin_mut!(response);
-                while let Ok(Some(response)) = response.try_next().await {
-                    let if_index = response.header.index;
-                    if if_index == 0 {
-                        continue;
-                    }
-
-                    let name = response
+            RtnlLinkRequest::InterfaceGetByMac { mac_addr } => {
+                let response = match handle.get().execute().try_collect::<Vec<_>>().await {
+                    Ok(messages) => messages
+                        .iter()
+                        .filter(|message| {
+                            message.attributes.iter().any(|attr| match attr {
+                                LinkAttribute::Address(addr) => addr[..] == mac_addr.inner,
+                                _ => false,
+                            })
+                        })
+                        .find_map(interface_from_message)
+                        .map_or(RtnlLinkResponse::NotFound, RtnlLinkResponse::Interface),
+                    Err(err) => RtnlLinkResponse::Error(RtnlError::from_rtnetlink(&err)),
+                };
+                respond(response);
+            }
+            RtnlLinkRequest::InterfaceDetailGet { if_id } => {
+                let response = match get_link(&mut handle, if_id).await {
+                    Ok(Some(message)) => match link_detail_from_message(&message) {
+                        Some(detail) => RtnlLinkResponse::InterfaceDetail(detail),
+                        None => RtnlLinkResponse::NotFound,
+                    },
+                    Ok(None) => RtnlLinkResponse::NotFound,
+                    Err(err) => RtnlLinkResponse::Error(err),
+                };
+                respond(response);
+            }
+            RtnlLinkRequest::MacAddrGet { if_id } => {
+                let response = match get_link(&mut handle, if_id).await {
+                    Ok(Some(message)) => message
                         .attributes
                         .iter()
-                        .find_map(|attr| {
-                            if let netlink_packet_route::link::LinkAttribute::IfName(name) = attr {
-                                Some(name.clone())
-                            } else {
-                                None
+                        .find_map(|attr| match attr {
+                            LinkAttribute::Address(addr) if addr.len() >= 6 => {
+                                let mut mac_bytes = [0u8; 6];
+                                mac_bytes.copy_from_slice(&addr[..6]);
+                                Some(RtnlLinkResponse::MacAddr(MacAddr::new(mac_bytes)))
                             }
+                            _ => None,
+                        })
+                        .unwrap_or(RtnlLinkResponse::NotFound),
+                    Ok(None) => RtnlLinkResponse::NotFound,
+                    Err(err) => RtnlLinkResponse::Error(err),
+                };
+                respond(response);
+            }
+            RtnlLinkRequest::MtuGet { if_id } => {
+                let response = match get_link(&mut handle, if_id).await {
+                    Ok(Some(message)) => message
+                        .attributes
+                        .iter()
+                        .find_map(|attr| match attr {
+                            LinkAttribute::Mtu(mtu) => Some(RtnlLinkResponse::Mtu(*mtu)),
+                            _ => None,
                         })
-                        .unwrap_or_else(|| if_name.to_owned());
+                        .unwrap_or(RtnlLinkResponse::NotFound),
+                    Ok(None) => RtnlLinkResponse::NotFound,
+                    Err(err) => RtnlLinkResponse::Error(err),
+                };
+                respond(response);
+            }
+            RtnlLinkRequest::StatsGet { if_id } => {
+                let response = query_link(&mut handle, if_id, |message| {
+                    link_stats_from_message(message).map(RtnlLinkResponse::Stats)
+                })
+                .await;
+                respond(response);
+            }
+            RtnlLinkRequest::OperStateGet { if_id } => {
+                let response = query_link(&mut handle, if_id, |message| {
+                    let state = message.attributes.iter().find_map(|attr| match attr {
+                        LinkAttribute::OperState(state) => Some(*state),
+                        _ => None,
+                    });
+                    Some(RtnlLinkResponse::OperState(state.unwrap_or(State::Unknown)))
+                })
+                .await;
+                respond(response);
+            }
+            RtnlLinkRequest::CarrierGet { if_id } => {
+                let response = query_link(&mut handle, if_id, |message| {
+                    message.attributes.iter().find_map(|attr| match attr {
+                        LinkAttribute::Carrier(carrier) => {
+                            Some(RtnlLinkResponse::Carrier(*carrier != 0))
+                        }
+                        _ => None,
+                    })
+                })
+                .await;
+                respond(response);
+            }
+            RtnlLinkRequest::CarrierStatsGet { if_id } => {
+                let response = query_link(&mut handle, if_id, |message| {
+                    Some(RtnlLinkResponse::CarrierStats(carrier_stats_from_message(
+                        message,
+                    )))
+                })
+                .await;
+                respond(response);
+            }
+            RtnlLinkRequest::KindGet { if_id } => {
+                let response = query_link(&mut handle, if_id, |message| {
+                    Some(RtnlLinkResponse::Kind(link_kind_from_message(message)))
+                })
+                .await;
+                respond(response);
+            }
+            RtnlLinkRequest::GroupGet { if_id } => {
+                let response = query_link(&mut handle, if_id, |message| {
+                    let group = message.attributes.iter().find_map(|attr| match attr {
+                        LinkAttribute::Group(group) => Some(*group),
+                        _ => None,
+                    });
+                    Some(RtnlLinkResponse::Group(group.unwrap_or(0)))
+                })
+                .await;
+                respond(response);
+            }
+            RtnlLinkRequest::GroupSet { if_id, group } => {
+                if if_id == 0 {
+                    respond(RtnlLinkResponse::NotFound);
+                    continue 'reqloop;
+                }
 
-                    respond(RtnlLinkResponse::Interface(Interface {
-                        if_id: if_index,
-                        if_name: name,
-                        link_layer_type: response.header.link_layer_type,
-                    }));
+                let result = apply_link_set(&handle, if_id, None, |builder| {
+                    builder.append_extra_attribute(LinkAttribute::Group(group))
+                })
+                .await;
+                respond(map_link_result(result, "set link group", if_id));
+            }
+            RtnlLinkRequest::GroupSetAdmin { group, up } => {
+                let result = apply_group_set(&handle, group, |builder| {
+                    if up { builder.up() } else { builder.down() }
+                })
+                .await;
+                let op_desc = format!("set group {} {}", group, if up { "up" } else { "down" });
+                respond(map_link_result(result, &op_desc, 0));
+            }
+            RtnlLinkRequest::GroupSetMtu { group, mtu } => {
+                let result = apply_group_set(&handle, group, |builder| builder.mtu(mtu)).await;
+                let op_desc = format!("set MTU of group {}", group);
+                respond(map_link_result(result, &op_desc, 0));
+            }
+            RtnlLinkRequest::OffloadMaxSizeSet { if_id, limit, size } => {
+                if if_id == 0 {
+                    respond(RtnlLinkResponse::NotFound);
                     continue 'reqloop;
                 }
-                respond(RtnlLinkResponse::NotFound);
+
+                let result = apply_link_set(&handle, if_id, None, |builder| {
+                    builder.append_extra_attribute(limit.attribute(size))
+                })
+                .await;
+                respond(map_link_result(result, "set offload max size", if_id));
             }
-            RtnlLinkRequest::MacAddrGet { if_id } => {
-                let if_index = if_id;
-                if if_index == 0 {
+            RtnlLinkRequest::ProtoDownGet { if_id } => {
+                let response = query_link(&mut handle, if_id, |message| {
+                    let proto_down = proto_down_from_message(message);
+                    Some(match proto_down {
+                        Some(proto_down) => RtnlLinkResponse::ProtoDown(proto_down),
+                        None => RtnlLinkResponse::NotImplemented,
+                    })
+                })
+                .await;
+                respond(response);
+            }
+            RtnlLinkRequest::ProtoDownSet {
+                if_id,
+                enabled,
+                reason_mask,
+                reason_value,
+            } => {
+                if if_id == 0 {
                     respond(RtnlLinkResponse::NotFound);
                     continue 'reqloop;
                 }
-                let response = handle.get().match_index(if_index).execute();
-                futures::pin_mut!(response);
-                while let Ok(Some(response)) = response.try_next().await {
-                    for link in response.attributes.iter() {
-                        match link {
-                            netlink_packet_route::link::LinkAttribute::Address(addr) => {
-                                if addr.len() < 6 {
-                                    continue;
-                                }
-                                let mut mac_bytes = [0u8; 6];
-                                mac_bytes.copy_from_slice(&addr[..6]);
-                                respond(RtnlLinkResponse::MacAddr(MacAddr::new(mac_bytes)));
-                                continue 'reqloop;
-                            }
+
+                let result = apply_link_set(&handle, if_id, None, |builder| {
+                    let builder = if reason_mask != 0 {
+                        builder.append_extra_attribute(LinkAttribute::ProtoDownReason(vec![
+                            LinkProtocolDownReason::Mask(reason_mask),
+                            LinkProtocolDownReason::Value(reason_value),
+                        ]))
+                    } else {
+                        builder
+                    };
+                    builder.append_extra_attribute(LinkAttribute::ProtoDown(enabled as u8))
+                })
+                .await;
+                respond(map_link_result(result, "set protodown", if_id));
+            }
+            RtnlLinkRequest::MtuRangeGet { if_id } => {
+                let response = query_link(&mut handle, if_id, |message| {
+                    let mut min = None;
+                    let mut max = None;
+                    for attr in message.attributes.iter() {
+                        match attr {
+                            LinkAttribute::MinMtu(mtu) => min = Some(*mtu),
+                            LinkAttribute::MaxMtu(mtu) => max = Some(*mtu),
                             _ => {}
                         }
                     }
-                }
-                respond(RtnlLinkResponse::NotFound);
+                    Some(match (min, max) {
+                        (Some(min), Some(max)) => RtnlLinkResponse::MtuRange(min, max),
+                        _ => RtnlLinkResponse::NotImplemented,
+                    })
+                })
+                .await;
+                respond(response);
             }
-            RtnlLinkRequest::MtuGet { if_id } => {
+            RtnlLinkRequest::MasterGet { if_id } => {
+                let response = query_link(&mut handle, if_id, |message| {
+                    let master = message.attributes.iter().find_map(|attr| match attr {
+                        LinkAttribute::Controller(master) if *master != 0 => Some(*master),
+                        _ => None,
+                    });
+                    Some(RtnlLinkResponse::Master(master))
+                })
+                .await;
+                respond(response);
+            }
+            RtnlLinkRequest::ParentGet { if_id } => {
+                let response = query_link(&mut handle, if_id, |message| {
+                    Some(RtnlLinkResponse::Parent(parent_from_message(message)))
+                })
+                .await;
+                respond(response);
+            }
+            RtnlLinkRequest::MasterSet { if_id, master } => {
                 if if_id == 0 {
                     respond(RtnlLinkResponse::NotFound);
                     continue 'reqloop;
                 }
 
-                let response = handle.get().match_index(if_id).execute();
-                futures::pin_mut!(response);
-                while let Ok(Some(response)) = response.try_next().await {
-                    for link in response.attributes.iter() {
-                        if let netlink_packet_route::link::LinkAttribute::Mtu(mtu) = link {
-                            respond(RtnlLinkResponse::Mtu(*mtu));
-                            continue 'reqloop;
-                        }
-                    }
-                }
-                respond(RtnlLinkResponse::NotFound);
+                let result = apply_link_set(&handle, if_id, None, |builder| match master {
+                    Some(master) => builder.controller(master),
+                    None => builder.nocontroller(),
+                })
+                .await;
+                let op_desc = if master.is_some() {
+                    "set master"
+                } else {
+                    "unset master"
+                };
+                respond(map_link_result(result, op_desc, if_id));
             }
             RtnlLinkRequest::InterfaceList => {
-                let mut interfaces = Vec::new();
-                let response = handle.get().execute();
-                futures::pin_mut!(response);
-                while let Ok(Some(response)) = response.try_next().await {
-                    let if_index = response.header.index;
-                    let mut if_name = None;
-                    for link in response.attributes.iter() {
-                        match link {
-                            netlink_packet_route::link::LinkAttribute::IfName(name) => {
-                                if_name = Some(name.clone());
-                            }
-                            _ => {}
-                        }
-                    }
-
-                    if let Some(name) = if_name {
-                        if if_index == 0 {
-                            continue;
-                        }
-
-                        interfaces.push(Interface {
-                            if_id: if_index,
-                            if_name: name,
-                            link_layer_type: response.header.link_layer_type,
-                        });
-                    }
-                }
-                respond(RtnlLinkResponse::InterfaceList(interfaces));
+                let response = match handle.get().execute().try_collect::<Vec<_>>().await {
+                    Ok(messages) => RtnlLinkResponse::InterfaceList(
+                        messages.iter().filter_map(interface_from_message).collect(),
+                    ),
+                    Err(err) => RtnlLinkResponse::Error(RtnlError::from_rtnetlink(&err)),
+                };
+                respond(response);
+            }
+            RtnlLinkRequest::InterfaceListDetailed => {
+                let response = match handle.get().execute().try_collect::<Vec<_>>().await {
+                    Ok(messages) => RtnlLinkResponse::InterfaceDetailList(
+                        messages
+                            .iter()
+                            .filter_map(link_detail_from_message)
+                            .collect(),
+                    ),
+                    Err(err) => RtnlLinkResponse::Error(RtnlError::from_rtnetlink(&err)),
+                };
+                respond(response);
+            }
+            RtnlLinkRequest::BridgePortList => {
+                let request = handle
+                    .get()
+                    .set_filter_mask(AddressFamily::Bridge, vec![LinkExtentMask::Brvlan]);
+                let response = match request.execute().try_collect::<Vec<_>>().await {
+                    Ok(messages) => RtnlLinkResponse::BridgePortList(
+                        messages
+                            .iter()
+                            .filter_map(bridge_port_from_message)
+                            .collect(),
+                    ),
+                    Err(err) => RtnlLinkResponse::Error(RtnlError::from_rtnetlink(&err)),
+                };
+                respond(response);
+            }
+            RtnlLinkRequest::InterfaceListByKind { kind } => {
+                let response = match handle.get().execute().try_collect::<Vec<_>>().await {
+                    Ok(messages) => RtnlLinkResponse::InterfaceList(
+                        messages
+                            .iter()
+                            .filter(|message| link_kind_from_message(message) == kind)
+                            .filter_map(interface_from_message)
+                            .collect(),
+                    ),
+                    Err(err) => RtnlLinkResponse::Error(RtnlError::from_rtnetlink(&err)),
+                };
+                respond(response);
             }
             RtnlLinkRequest::MacAddrSet {
                 if_id,
@@ -588,6 +1945,15 @@ pub(crate) async fn run_server(mut server: Server, mut handle: rtnetlink::LinkHa
 
                 respond(map_link_result(result, op_desc, if_id));
             }
+            RtnlLinkRequest::Apply(update) => {
+                if update.if_id == 0 {
+                    respond(RtnlLinkResponse::NotFound);
+                    continue 'reqloop;
+                }
+
+                let result = handle.set(update.build()).execute().await;
+                respond(map_link_result(result, "apply link update", update.if_id));
+            }
             _ => respond(RtnlLinkResponse::NotImplemented),
         }
     }
@@ -0,0 +1,323 @@
+use std::io::{self, ErrorKind};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use futures::{StreamExt, TryStreamExt};
+use netlink_packet_core::{Emitable, NetlinkPayload};
+use netlink_packet_route::address::AddressMessage;
+use netlink_packet_route::link::LinkMessage;
+use netlink_packet_route::neighbour::NeighbourMessage;
+use netlink_packet_route::prefix::PrefixMessage;
+use netlink_packet_route::route::RouteMessage;
+use netlink_packet_route::{AddressFamily, RouteNetlinkMessage};
+use rtnetlink::RouteMessageBuilder;
+use rtnetlink::constants::{
+    RTMGRP_IPV4_IFADDR, RTMGRP_IPV4_ROUTE, RTMGRP_IPV6_IFADDR, RTMGRP_IPV6_PREFIX,
+    RTMGRP_IPV6_ROUTE, RTMGRP_LINK, RTMGRP_NEIGH,
+};
+use rtnetlink::sys::{AsyncSocket, SocketAddr};
+
+use crate::Ipv6Net;
+
+const PREFIX_HEADER_LEN: usize = 12;
+const PREFIX_ADDRESS: u16 = 1;
+const PREFIX_CACHEINFO: u16 = 2;
+const IF_PREFIX_ONLINK: u8 = 0x01;
+const IF_PREFIX_AUTOCONF: u8 = 0x02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RtnlGroup {
+    Link,
+    Neighbor,
+    Ipv4Address,
+    Ipv6Address,
+    Ipv4Route,
+    Ipv6Route,
+    Ipv6Prefix,
+}
+
+impl RtnlGroup {
+    fn mask(self) -> u32 {
+        match self {
+            RtnlGroup::Link => RTMGRP_LINK,
+            RtnlGroup::Neighbor => RTMGRP_NEIGH,
+            RtnlGroup::Ipv4Address => RTMGRP_IPV4_IFADDR,
+            RtnlGroup::Ipv6Address => RTMGRP_IPV6_IFADDR,
+            RtnlGroup::Ipv4Route => RTMGRP_IPV4_ROUTE,
+            RtnlGroup::Ipv6Route => RTMGRP_IPV6_ROUTE,
+            RtnlGroup::Ipv6Prefix => RTMGRP_IPV6_PREFIX,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RtnlEvent {
+    NewLink(LinkMessage),
+    DelLink(LinkMessage),
+    NewAddress(AddressMessage),
+    DelAddress(AddressMessage),
+    NewRoute(RouteMessage),
+    DelRoute(RouteMessage),
+    NewNeighbor(NeighbourMessage),
+    DelNeighbor(NeighbourMessage),
+    NewPrefix(PrefixMessage),
+}
+
+impl RtnlEvent {
+    fn from_message(message: RouteNetlinkMessage) -> Option<Self> {
+        match message {
+            RouteNetlinkMessage::NewLink(msg) => Some(RtnlEvent::NewLink(msg)),
+            RouteNetlinkMessage::DelLink(msg) => Some(RtnlEvent::DelLink(msg)),
+            RouteNetlinkMessage::NewAddress(msg) => Some(RtnlEvent::NewAddress(msg)),
+            RouteNetlinkMessage::DelAddress(msg) => Some(RtnlEvent::DelAddress(msg)),
+            RouteNetlinkMessage::NewRoute(msg) => Some(RtnlEvent::NewRoute(msg)),
+            RouteNetlinkMessage::DelRoute(msg) => Some(RtnlEvent::DelRoute(msg)),
+            RouteNetlinkMessage::NewNeighbour(msg) => Some(RtnlEvent::NewNeighbor(msg)),
+            RouteNetlinkMessage::DelNeighbour(msg) => Some(RtnlEvent::DelNeighbor(msg)),
+            RouteNetlinkMessage::NewPrefix(msg) => Some(RtnlEvent::NewPrefix(msg)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ipv6PrefixInfo {
+    pub if_id: u32,
+    pub prefix: Ipv6Net,
+    pub on_link: bool,
+    pub autoconf: bool,
+    pub preferred_lifetime: Option<u32>,
+    pub valid_lifetime: Option<u32>,
+}
+
+// netlink-packet-route does not export its prefix attribute type, so the
+// attributes are read back from the serialized message.
+pub fn ipv6_prefix_from_message(message: &PrefixMessage) -> Option<Ipv6PrefixInfo> {
+    let mut buffer = vec![0u8; message.buffer_len()];
+    message.emit(&mut buffer);
+
+    let mut address = None;
+    let mut cache_info = None;
+    let mut offset = PREFIX_HEADER_LEN;
+    while offset + 4 <= buffer.len() {
+        let len = u16::from_ne_bytes([buffer[offset], buffer[offset + 1]]) as usize;
+        let kind = u16::from_ne_bytes([buffer[offset + 2], buffer[offset + 3]]);
+        if len < 4 || offset + len > buffer.len() {
+            break;
+        }
+        let value = &buffer[offset + 4..offset + len];
+        match kind {
+            PREFIX_ADDRESS => {
+                address = <[u8; 16]>::try_from(value).ok().map(Ipv6Addr::from);
+            }
+            PREFIX_CACHEINFO if value.len() >= 8 => {
+                let preferred = u32::from_ne_bytes(value[0..4].try_into().ok()?);
+                let valid = u32::from_ne_bytes(value[4..8].try_into().ok()?);
+                cache_info = Some((preferred, valid));
+            }
+            _ => {}
+        }
+        offset += (len + 3) & !3;
+    }
+
+    let header = &message.header;
+    let prefix = Ipv6Net::new(address?, header.prefix_len).ok()?.trunc();
+    Some(Ipv6PrefixInfo {
+        if_id: u32::try_from(header.ifindex).ok()?,
+        prefix,
+        on_link: header.flags & IF_PREFIX_ONLINK != 0,
+        autoconf: header.flags & IF_PREFIX_AUTOCONF != 0,
+        preferred_lifetime: cache_info.map(|(preferred, _)| preferred),
+        valid_lifetime: cache_info.map(|(_, valid)| valid),
+    })
+}
+
+#[derive(Debug)]
+pub struct RtnlMonitor {
+    receiver: mpsc::Receiver<RtnlEvent>,
+}
+
+impl RtnlMonitor {
+    pub fn new(groups: &[RtnlGroup]) -> io::Result<Self> {
+        Self::spawn(groups.to_vec(), Vec::new())
+    }
+
+    // Existing objects of the subscribed groups are replayed as New* events
+    // before any notification, so callers can build their state from the
+    // event stream alone.
+    pub fn with_initial_dump(groups: &[RtnlGroup]) -> io::Result<Self> {
+        Self::spawn(groups.to_vec(), groups.to_vec())
+    }
+
+    // Like `with_initial_dump`, but replays only the `dump` groups, for
+    // callers that need notifications of a group without its existing
+    // objects.
+    pub fn with_partial_dump(groups: &[RtnlGroup], dump: &[RtnlGroup]) -> io::Result<Self> {
+        Self::spawn(groups.to_vec(), dump.to_vec())
+    }
+
+    pub fn recv(&self) -> io::Result<RtnlEvent> {
+        self.receiver
+            .recv()
+            .map_err(|_| io::Error::new(ErrorKind::BrokenPipe, "Monitor stopped"))
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> io::Result<Option<RtnlEvent>> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(event) => Ok(Some(event)),
+            Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Err(io::Error::new(ErrorKind::BrokenPipe, "Monitor stopped"))
+            }
+        }
+    }
+
+    pub fn try_recv(&self) -> io::Result<Option<RtnlEvent>> {
+        match self.receiver.try_recv() {
+            Ok(event) => Ok(Some(event)),
+            Err(mpsc::TryRecvError::Empty) => Ok(None),
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Err(io::Error::new(ErrorKind::BrokenPipe, "Monitor stopped"))
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = RtnlEvent> + '_ {
+        self.receiver.iter()
+    }
+
+    fn spawn(groups: Vec<RtnlGroup>, dump: Vec<RtnlGroup>) -> io::Result<Self> {
+        let (event_tx, event_rx) = mpsc::channel();
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let rt = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(rt) => rt,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+
+            rt.block_on(run_monitor(groups, dump, event_tx, ready_tx));
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok(())) => Ok(Self { receiver: event_rx }),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(io::Error::new(
+                ErrorKind::BrokenPipe,
+                "Monitor thread exited during setup",
+            )),
+        }
+    }
+}
+
+async fn run_monitor(
+    groups: Vec<RtnlGroup>,
+    dump: Vec<RtnlGroup>,
+    events: mpsc::Sender<RtnlEvent>,
+    ready: mpsc::Sender<io::Result<()>>,
+) {
+    let (mut connection, handle, mut messages) = match rtnetlink::new_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            let _ = ready.send(Err(e));
+            return;
+        }
+    };
+
+    let mask = groups.iter().fold(0, |mask, group| mask | group.mask());
+    if let Err(e) = connection
+        .socket_mut()
+        .socket_mut()
+        .bind(&SocketAddr::new(0, mask))
+    {
+        let _ = ready.send(Err(e));
+        return;
+    }
+    tokio::spawn(connection);
+    let _ = ready.send(Ok(()));
+
+    if !dump.is_empty()
+        && let Err(err) = dump_groups(&handle, &dump, &events).await
+    {
+        tracing::warn!("Initial monitor dump failed: {}", err);
+    }
+
+    while let Some((message, _)) = messages.next().await {
+        let NetlinkPayload::InnerMessage(message) = message.payload else {
+            continue;
+        };
+        let Some(event) = RtnlEvent::from_message(message) else {
+            continue;
+        };
+        if events.send(event).is_err() {
+            break;
+        }
+    }
+}
+
+async fn dump_groups(
+    handle: &rtnetlink::Handle,
+    groups: &[RtnlGroup],
+    events: &mpsc::Sender<RtnlEvent>,
+) -> Result<(), rtnetlink::Error> {
+    let send = |event| {
+        let _ = events.send(event);
+    };
+
+    if groups.contains(&RtnlGroup::Link) {
+        let mut stream = handle.link().get().execute();
+        while let Some(msg) = stream.try_next().await? {
+            send(RtnlEvent::NewLink(msg));
+        }
+    }
+
+    let ipv4_addr = groups.contains(&RtnlGroup::Ipv4Address);
+    let ipv6_addr = groups.contains(&RtnlGroup::Ipv6Address);
+    if ipv4_addr || ipv6_addr {
+        let mut stream = handle.address().get().execute();
+        while let Some(msg) = stream.try_next().await? {
+            let wanted = match msg.header.family {
+                AddressFamily::Inet => ipv4_addr,
+                AddressFamily::Inet6 => ipv6_addr,
+                _ => false,
+            };
+            if wanted {
+                send(RtnlEvent::NewAddress(msg));
+            }
+        }
+    }
+
+    if groups.contains(&RtnlGroup::Ipv4Route) {
+        let message = RouteMessageBuilder::<Ipv4Addr>::new().build();
+        let mut stream = handle.route().get(message).execute();
+        while let Some(msg) = stream.try_next().await? {
+            send(RtnlEvent::NewRoute(msg));
+        }
+    }
+
+    if groups.contains(&RtnlGroup::Ipv6Route) {
+        let message = RouteMessageBuilder::<Ipv6Addr>::new().build();
+        let mut stream = handle.route().get(message).execute();
+        while let Some(msg) = stream.try_next().await? {
+            send(RtnlEvent::NewRoute(msg));
+        }
+    }
+
+    if groups.contains(&RtnlGroup::Neighbor) {
+        let mut stream = handle.neighbours().get().execute();
+        while let Some(msg) = stream.try_next().await? {
+            send(RtnlEvent::NewNeighbor(msg));
+        }
+    }
+
+    Ok(())
+}
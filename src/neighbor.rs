@@ -1,21 +1,32 @@
 #![allow(unreachable_patterns)]
 
+use std::hash::{Hash, Hasher};
 use std::io::{self, ErrorKind};
 use std::net::IpAddr;
+use std::time::Duration;
 
 use ftth_common::channel::{AsyncWorldClient, AsyncWorldServer};
-use futures::TryStreamExt;
+use futures::{StreamExt, TryStreamExt};
 
-use tracing::warn;
+use netlink_packet_core::{
+    NLM_F_ACK, NLM_F_APPEND, NLM_F_CREATE, NLM_F_DUMP, NLM_F_EXCL, NLM_F_REPLACE, NLM_F_REQUEST,
+    NetlinkMessage, NetlinkPayload,
+};
 use netlink_packet_route::neighbour::{NeighbourAddress, NeighbourAttribute, NeighbourMessage};
-use netlink_packet_route::{AddressFamily, route::RouteType};
+use netlink_packet_route::neighbour_table::{
+    NeighbourTableAttribute, NeighbourTableMessage, NeighbourTableParameter,
+};
+use netlink_packet_route::{AddressFamily, RouteNetlinkMessage, route::RouteType};
+use tracing::warn;
 
 pub use netlink_packet_route::neighbour::{NeighbourFlags, NeighbourState};
 
+use crate::error::RtnlError;
+
 pub(crate) type Client = AsyncWorldClient<RtnlNeighborRequest, RtnlNeighborResponse>;
 pub(crate) type Server = AsyncWorldServer<RtnlNeighborRequest, RtnlNeighborResponse>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NeighborEntry {
     pub if_id: u32,
     pub destination: IpAddr,
@@ -24,6 +35,28 @@ pub struct NeighborEntry {
     pub flags: Option<NeighbourFlags>,
 }
 
+impl NeighborEntry {
+    pub fn normalized(&self) -> Self {
+        let mut entry = self.clone();
+        entry.link_address = entry.link_address.filter(|addr| !addr.is_empty());
+        entry.state = entry.state.filter(|state| *state != NeighbourState::None);
+        entry.flags = entry.flags.filter(|flags| !flags.is_empty());
+        entry
+    }
+
+    pub fn canonical_eq(&self, other: &Self) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl Hash for NeighborEntry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.if_id.hash(state);
+        self.destination.hash(state);
+        self.link_address.hash(state);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct NeighborDelete {
     pub if_id: u32,
@@ -33,6 +66,52 @@ pub struct NeighborDelete {
     pub flags: Option<NeighbourFlags>,
 }
 
+// A bridge forwarding database entry, as managed by `bridge fdb`. On a VXLAN
+// device `destination`, `port` and `vni` select the remote VTEP; the
+// all-zero MAC entry is the flood list for broadcast and unknown traffic and
+// may point at several VTEPs, one entry per remote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FdbEntry {
+    pub if_id: u32,
+    pub link_address: Vec<u8>,
+    pub vlan: Option<u16>,
+    pub destination: Option<IpAddr>,
+    // UDP port of the remote VTEP; the device's port when unset.
+    pub port: Option<u16>,
+    // The device's VNI when unset.
+    pub vni: Option<u32>,
+    // Installed as permanent when unset.
+    pub state: Option<NeighbourState>,
+    // Installed into the device's own table (NTF_SELF) when unset, as
+    // `bridge fdb` does.
+    pub flags: Option<NeighbourFlags>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NeighborTableKind {
+    // IPv4 ARP cache.
+    Arp,
+    // IPv6 neighbor discovery cache.
+    Ndisc,
+}
+
+// Tunables of a neighbor table, as shown by `ip ntable`. Unset fields are
+// left alone by `table_set`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct NeighborTableParams {
+    // Table-wide: below gc_thresh1 entries are never collected, above
+    // gc_thresh2 they are collected after 5 seconds, and gc_thresh3 is the
+    // hard limit. Can only be changed from the initial network namespace.
+    pub gc_thresh1: Option<u32>,
+    pub gc_thresh2: Option<u32>,
+    pub gc_thresh3: Option<u32>,
+    pub gc_interval: Option<Duration>,
+    // Per interface, or the defaults for new interfaces without one. Entries
+    // stay reachable for a random time between 0.5 and 1.5 times this.
+    pub base_reachable_time: Option<Duration>,
+    pub gc_stale_time: Option<Duration>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 #[non_exhaustive]
 pub enum RtnlNeighborRequest {
@@ -46,6 +125,22 @@ pub enum RtnlNeighborRequest {
         destination: IpAddr,
         if_id: Option<u32>,
     },
+    FdbAdd(FdbEntry),
+    FdbAppend(FdbEntry),
+    FdbReplace(FdbEntry),
+    FdbDelete(FdbEntry),
+    FdbList {
+        if_id: Option<u32>,
+    },
+    TableGet {
+        kind: NeighborTableKind,
+        if_id: Option<u32>,
+    },
+    TableSet {
+        kind: NeighborTableKind,
+        if_id: Option<u32>,
+        params: NeighborTableParams,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -55,8 +150,12 @@ pub enum RtnlNeighborResponse {
     Failed,
     NotImplemented,
     NotFound,
+    AlreadyExists,
     Neighbors(Vec<NeighborEntry>),
     Neighbor(NeighborEntry),
+    FdbEntries(Vec<FdbEntry>),
+    Table(NeighborTableParams),
+    Error(RtnlError),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -69,6 +168,13 @@ impl RtnlNeighborClient {
         Self { client }
     }
 
+    pub fn send_raw(&self, request: RtnlNeighborRequest) -> io::Result<RtnlNeighborResponse> {
+        self.client.send_request(request)
+    }
+
+    // Create only, sent with NLM_F_EXCL: fails with AlreadyExists if the
+    // destination has an entry on the interface, whatever its link address or
+    // state. Use `change` to overwrite it.
     pub fn add(&self, entry: NeighborEntry) -> io::Result<()> {
         let res = self.client.send_request(RtnlNeighborRequest::Add(entry))?;
         handle_neighbor_response("Neighbor add", res, false)
@@ -94,6 +200,7 @@ impl RtnlNeighborClient {
             .send_request(RtnlNeighborRequest::List { if_id })?
         {
             RtnlNeighborResponse::Neighbors(entries) => Ok(entries),
+            RtnlNeighborResponse::Error(err) => Err(err.into_io_error("Neighbor list")),
             other => Err(io::Error::other(format!(
                 "Unexpected response for neighbor list: {:?}",
                 other
@@ -101,6 +208,8 @@ impl RtnlNeighborClient {
         }
     }
 
+    // With `if_id` the kernel looks the entry up directly; without it, all
+    // neighbors are listed to find the first one for `destination`.
     pub fn get(&self, destination: IpAddr, if_id: Option<u32>) -> io::Result<NeighborEntry> {
         match self
             .client
@@ -110,15 +219,147 @@ impl RtnlNeighborClient {
             RtnlNeighborResponse::NotFound => {
                 Err(io::Error::new(ErrorKind::NotFound, "Neighbor not found"))
             }
+            RtnlNeighborResponse::Error(err) => Err(err.into_io_error("Neighbor get")),
             other => Err(io::Error::other(format!(
                 "Unexpected response for neighbor get: {:?}",
                 other
             ))),
         }
     }
+
+    // Fails with AlreadyExists if an entry for the same MAC and VLAN exists.
+    pub fn fdb_add(&self, entry: FdbEntry) -> io::Result<()> {
+        validate_fdb_entry(&entry)?;
+        let res = self
+            .client
+            .send_request(RtnlNeighborRequest::FdbAdd(entry))?;
+        handle_neighbor_response("FDB add", res, false)
+    }
+
+    // Adds another remote to an existing VXLAN entry, such as a further VTEP
+    // to the all-zero MAC flood list.
+    pub fn fdb_append(&self, entry: FdbEntry) -> io::Result<()> {
+        validate_fdb_entry(&entry)?;
+        let res = self
+            .client
+            .send_request(RtnlNeighborRequest::FdbAppend(entry))?;
+        handle_neighbor_response("FDB append", res, false)
+    }
+
+    // VXLAN refuses to replace the all-zero MAC entry; append and delete its
+    // remotes instead.
+    pub fn fdb_replace(&self, entry: FdbEntry) -> io::Result<()> {
+        validate_fdb_entry(&entry)?;
+        let res = self
+            .client
+            .send_request(RtnlNeighborRequest::FdbReplace(entry))?;
+        handle_neighbor_response("FDB replace", res, false)
+    }
+
+    // On a VXLAN device with `destination` set, only that remote is removed.
+    pub fn fdb_delete(&self, entry: FdbEntry) -> io::Result<()> {
+        validate_fdb_entry(&entry)?;
+        let res = self
+            .client
+            .send_request(RtnlNeighborRequest::FdbDelete(entry))?;
+        handle_neighbor_response("FDB delete", res, false)
+    }
+
+    pub fn fdb_list(&self, if_id: Option<u32>) -> io::Result<Vec<FdbEntry>> {
+        match self
+            .client
+            .send_request(RtnlNeighborRequest::FdbList { if_id })?
+        {
+            RtnlNeighborResponse::FdbEntries(entries) => Ok(entries),
+            RtnlNeighborResponse::Error(err) => Err(err.into_io_error("FDB list")),
+            other => Err(io::Error::other(format!(
+                "Unexpected response for FDB list: {:?}",
+                other
+            ))),
+        }
+    }
+
+    // With `if_id`, the per-interface fields are those of that interface;
+    // otherwise they are the defaults new interfaces start with.
+    pub fn table_get(
+        &self,
+        kind: NeighborTableKind,
+        if_id: Option<u32>,
+    ) -> io::Result<NeighborTableParams> {
+        match self
+            .client
+            .send_request(RtnlNeighborRequest::TableGet { kind, if_id })?
+        {
+            RtnlNeighborResponse::Table(params) => Ok(params),
+            RtnlNeighborResponse::NotFound => Err(io::Error::new(
+                ErrorKind::NotFound,
+                "Neighbor table not found",
+            )),
+            RtnlNeighborResponse::Error(err) => Err(err.into_io_error("Neighbor table get")),
+            other => Err(io::Error::other(format!(
+                "Unexpected response for neighbor table get: {:?}",
+                other
+            ))),
+        }
+    }
+
+    // Changing the defaults does not affect existing interfaces; set them
+    // per `if_id` as well.
+    pub fn table_set(
+        &self,
+        kind: NeighborTableKind,
+        if_id: Option<u32>,
+        params: NeighborTableParams,
+    ) -> io::Result<()> {
+        let res = self.client.send_request(RtnlNeighborRequest::TableSet {
+            kind,
+            if_id,
+            params,
+        })?;
+        handle_neighbor_response("Neighbor table set", res, false)
+    }
+}
+
+impl NeighborTableKind {
+    fn name(self) -> &'static str {
+        match self {
+            NeighborTableKind::Arp => "arp_cache",
+            NeighborTableKind::Ndisc => "ndisc_cache",
+        }
+    }
+
+    fn address_family(self) -> AddressFamily {
+        match self {
+            NeighborTableKind::Arp => AddressFamily::Inet,
+            NeighborTableKind::Ndisc => AddressFamily::Inet6,
+        }
+    }
+}
+
+fn validate_fdb_entry(entry: &FdbEntry) -> io::Result<()> {
+    if entry.link_address.len() != ETH_ALEN {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "FDB link address must be {} bytes, got {}",
+                ETH_ALEN,
+                entry.link_address.len()
+            ),
+        ));
+    }
+    if let Some(vni) = entry.vni
+        && vni > VXLAN_VNI_MAX
+    {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("VNI {} is out of range", vni),
+        ));
+    }
+    Ok(())
 }
 
-pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::NeighbourHandle) {
+pub(crate) async fn run_server(mut server: Server, mut netlink: rtnetlink::Handle) {
+    let handle = netlink.neighbours();
     while let Some((req, respond)) = server.accept().await {
         let response = match req {
             RtnlNeighborRequest::Add(entry) => add_or_change_neighbor(&handle, entry, false).await,
@@ -128,8 +369,33 @@ pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::NeighbourH
             RtnlNeighborRequest::Delete(entry) => delete_neighbor(&handle, entry).await,
             RtnlNeighborRequest::List { if_id } => list_neighbors(&handle, if_id).await,
             RtnlNeighborRequest::Get { destination, if_id } => {
-                get_neighbor(&handle, destination, if_id).await
+                get_neighbor(&mut netlink, &handle, destination, if_id).await
             }
+            RtnlNeighborRequest::FdbAdd(entry) => map_request_result(
+                send_new_neighbor(&mut netlink, build_fdb_message(&entry), NLM_F_EXCL).await,
+                "FDB add",
+            ),
+            RtnlNeighborRequest::FdbAppend(entry) => map_request_result(
+                send_new_neighbor(&mut netlink, build_fdb_message(&entry), NLM_F_APPEND).await,
+                "FDB append",
+            ),
+            RtnlNeighborRequest::FdbReplace(entry) => map_request_result(
+                send_new_neighbor(&mut netlink, build_fdb_message(&entry), NLM_F_REPLACE).await,
+                "FDB replace",
+            ),
+            RtnlNeighborRequest::FdbDelete(entry) => map_request_result(
+                handle.del(build_fdb_message(&entry)).execute().await,
+                "FDB delete",
+            ),
+            RtnlNeighborRequest::FdbList { if_id } => list_fdb(&handle, if_id).await,
+            RtnlNeighborRequest::TableGet { kind, if_id } => {
+                get_neighbor_table(&mut netlink, kind, if_id).await
+            }
+            RtnlNeighborRequest::TableSet {
+                kind,
+                if_id,
+                params,
+            } => set_neighbor_table(&mut netlink, kind, if_id, params).await,
         };
         respond(response);
     }
@@ -147,11 +413,16 @@ fn handle_neighbor_response(
             ErrorKind::NotFound,
             format!("{}: entry not found", operation),
         )),
+        RtnlNeighborResponse::AlreadyExists => Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{}: entry already exists", operation),
+        )),
         RtnlNeighborResponse::Failed => Err(io::Error::other(format!("{} failed", operation))),
         RtnlNeighborResponse::NotImplemented => Err(io::Error::new(
             ErrorKind::Unsupported,
             format!("{} is not implemented", operation),
         )),
+        RtnlNeighborResponse::Error(err) => Err(err.into_io_error(operation)),
         other => Err(io::Error::other(format!(
             "{} returned unexpected response: {:?}",
             operation, other
@@ -188,19 +459,16 @@ async fn add_or_change_neighbor(
             let io_err = err_msg.to_io();
             match io_err.kind() {
                 ErrorKind::NotFound => RtnlNeighborResponse::NotFound,
-                ErrorKind::AlreadyExists => {
-                    warn!("Neighbor operation failed (already exists): {}", io_err);
-                    RtnlNeighborResponse::Failed
-                }
+                ErrorKind::AlreadyExists => RtnlNeighborResponse::AlreadyExists,
                 _ => {
                     warn!("Neighbor operation failed: {}", io_err);
-                    RtnlNeighborResponse::Failed
+                    RtnlNeighborResponse::Error(RtnlError::from_netlink(&err_msg))
                 }
             }
         }
         Err(err) => {
             warn!("Neighbor operation failed: {}", err);
-            RtnlNeighborResponse::Failed
+            RtnlNeighborResponse::Error(RtnlError::from_rtnetlink(&err))
         }
     }
 }
@@ -218,35 +486,51 @@ async fn delete_neighbor(
                 ErrorKind::NotFound => RtnlNeighborResponse::NotFound,
                 _ => {
                     warn!("Neighbor delete failed: {}", io_err);
-                    RtnlNeighborResponse::Failed
+                    RtnlNeighborResponse::Error(RtnlError::from_netlink(&err_msg))
                 }
             }
         }
         Err(err) => {
             warn!("Neighbor delete failed: {}", err);
-            RtnlNeighborResponse::Failed
+            RtnlNeighborResponse::Error(RtnlError::from_rtnetlink(&err))
         }
     }
 }
 
 fn build_delete_message(entry: &NeighborDelete) -> NeighbourMessage {
+    build_neighbor_message(
+        entry.if_id,
+        entry.destination,
+        entry.link_address.as_deref(),
+        entry.state,
+        entry.flags,
+    )
+}
+
+fn build_neighbor_message(
+    if_id: u32,
+    destination: IpAddr,
+    link_address: Option<&[u8]>,
+    state: Option<NeighbourState>,
+    flags: Option<NeighbourFlags>,
+) -> NeighbourMessage {
     let mut message = NeighbourMessage::default();
-    message.header.family = match entry.destination {
+    message.header.family = match destination {
         IpAddr::V4(_) => AddressFamily::Inet,
         IpAddr::V6(_) => AddressFamily::Inet6,
     };
-    message.header.ifindex = entry.if_id;
+    message.header.ifindex = if_id;
     message.header.kind = RouteType::Unspec;
 
-    if let Some(state) = entry.state {
+    if let Some(state) = state {
         message.header.state = state;
     }
 
-    if let Some(flags) = entry.flags {
+    if let Some(flags) = flags {
         message.header.flags = flags;
     }
 
-    let destination = match entry.destination {
+    let destination = match destination {
         IpAddr::V4(addr) => NeighbourAddress::Inet(addr),
         IpAddr::V6(addr) => NeighbourAddress::Inet6(addr),
     };
@@ -255,15 +539,46 @@ fn build_delete_message(entry: &NeighborDelete) -> NeighbourMessage {
         .attributes
         .push(NeighbourAttribute::Destination(destination));
 
-    if let Some(ref link_address) = entry.link_address {
+    if let Some(link_address) = link_address {
         message
             .attributes
-            .push(NeighbourAttribute::LinkLocalAddress(link_address.clone()));
+            .push(NeighbourAttribute::LinkLocalAddress(link_address.to_vec()));
     }
 
     message
 }
 
+impl TryFrom<NeighbourMessage> for NeighborEntry {
+    type Error = io::Error;
+
+    fn try_from(message: NeighbourMessage) -> io::Result<Self> {
+        neighbor_from_message(message).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                "Neighbor message has no IP destination",
+            )
+        })
+    }
+}
+
+impl From<&NeighborEntry> for NeighbourMessage {
+    fn from(entry: &NeighborEntry) -> Self {
+        build_neighbor_message(
+            entry.if_id,
+            entry.destination,
+            entry.link_address.as_deref(),
+            entry.state,
+            entry.flags,
+        )
+    }
+}
+
+impl From<&NeighborDelete> for NeighbourMessage {
+    fn from(entry: &NeighborDelete) -> Self {
+        build_delete_message(entry)
+    }
+}
+
 async fn list_neighbors(
     handle: &rtnetlink::NeighbourHandle,
     if_id: Option<u32>,
@@ -272,13 +587,13 @@ async fn list_neighbors(
         Ok(entries) => {
             let filtered: Vec<_> = entries
                 .into_iter()
-                .filter(|entry| if_id.map_or(true, |id| entry.if_id == id))
+                .filter(|entry| if_id.is_none_or(|id| entry.if_id == id))
                 .collect();
             RtnlNeighborResponse::Neighbors(filtered)
         }
         Err(err) => {
             warn!("Neighbor list failed: {}", err);
-            RtnlNeighborResponse::Failed
+            RtnlNeighborResponse::Error(RtnlError::from_rtnetlink(&err))
         }
     }
 }
@@ -328,20 +643,40 @@ fn neighbor_from_message(message: NeighbourMessage) -> Option<NeighborEntry> {
 }
 
 async fn get_neighbor(
+    netlink: &mut rtnetlink::Handle,
     handle: &rtnetlink::NeighbourHandle,
     destination: IpAddr,
     if_id: Option<u32>,
 ) -> RtnlNeighborResponse {
+    if let Some(if_id) = if_id {
+        match lookup_neighbor(netlink, destination, if_id).await {
+            Ok(Some(entry)) => return RtnlNeighborResponse::Neighbor(entry),
+            Ok(None) => return RtnlNeighborResponse::NotFound,
+            // Kernels before 5.0 only dump neighbors.
+            Err(rtnetlink::Error::NetlinkError(err_msg))
+                if err_msg.raw_code().abs() == EOPNOTSUPP => {}
+            Err(rtnetlink::Error::NetlinkError(err_msg))
+                if err_msg.to_io().kind() == ErrorKind::NotFound =>
+            {
+                return RtnlNeighborResponse::NotFound;
+            }
+            Err(err) => {
+                warn!("Neighbor get failed: {}", err);
+                return RtnlNeighborResponse::Error(RtnlError::from_rtnetlink(&err));
+            }
+        }
+    }
+
     match fetch_neighbors(handle).await {
         Ok(entries) => {
             let neighbor = entries.into_iter().find(|entry| {
                 if entry.destination != destination {
                     return false;
                 }
-                if let Some(index) = if_id {
-                    if entry.if_id != index {
-                        return false;
-                    }
+                if let Some(index) = if_id
+                    && entry.if_id != index
+                {
+                    return false;
                 }
                 true
             });
@@ -352,9 +687,35 @@ async fn get_neighbor(
         }
         Err(err) => {
             warn!("Neighbor get failed: {}", err);
-            RtnlNeighborResponse::Failed
+            RtnlNeighborResponse::Error(RtnlError::from_rtnetlink(&err))
+        }
+    }
+}
+
+// A non-dump RTM_GETNEIGH, which the kernel only accepts with the
+// destination and the interface and no state or flags.
+async fn lookup_neighbor(
+    netlink: &mut rtnetlink::Handle,
+    destination: IpAddr,
+    if_id: u32,
+) -> Result<Option<NeighborEntry>, rtnetlink::Error> {
+    let message = build_neighbor_message(if_id, destination, None, None, None);
+    let mut req = NetlinkMessage::from(RouteNetlinkMessage::GetNeighbour(message));
+    req.header.flags = NLM_F_REQUEST;
+    let mut response = netlink.request(req)?;
+    let mut entry = None;
+    while let Some(message) = response.next().await {
+        match message.payload {
+            NetlinkPayload::InnerMessage(RouteNetlinkMessage::NewNeighbour(message)) => {
+                entry = entry.or_else(|| neighbor_from_message(message));
+            }
+            NetlinkPayload::Error(err) if err.code.is_some() => {
+                return Err(rtnetlink::Error::NetlinkError(err));
+            }
+            _ => {}
         }
     }
+    Ok(entry)
 }
 
 async fn fetch_neighbors(
@@ -369,3 +730,310 @@ async fn fetch_neighbors(
     }
     Ok(entries)
 }
+
+fn build_fdb_message(entry: &FdbEntry) -> NeighbourMessage {
+    let mut message = NeighbourMessage::default();
+    message.header.family = AddressFamily::Bridge;
+    message.header.ifindex = entry.if_id;
+    message.header.kind = RouteType::Unspec;
+    message.header.state = entry.state.unwrap_or(NeighbourState::Permanent);
+    message.header.flags = entry.flags.unwrap_or(NeighbourFlags::Own);
+
+    message
+        .attributes
+        .push(NeighbourAttribute::LinkLocalAddress(
+            entry.link_address.clone(),
+        ));
+    if let Some(destination) = entry.destination {
+        let destination = match destination {
+            IpAddr::V4(addr) => NeighbourAddress::Inet(addr),
+            IpAddr::V6(addr) => NeighbourAddress::Inet6(addr),
+        };
+        message
+            .attributes
+            .push(NeighbourAttribute::Destination(destination));
+    }
+    if let Some(vlan) = entry.vlan {
+        message.attributes.push(NeighbourAttribute::Vlan(vlan));
+    }
+    if let Some(port) = entry.port {
+        message.attributes.push(NeighbourAttribute::Port(port));
+    }
+    if let Some(vni) = entry.vni {
+        message.attributes.push(NeighbourAttribute::Vni(vni));
+    }
+
+    message
+}
+
+fn fdb_from_message(message: NeighbourMessage) -> Option<FdbEntry> {
+    let NeighbourMessage {
+        header, attributes, ..
+    } = message;
+    if header.family != AddressFamily::Bridge {
+        return None;
+    }
+
+    let mut link_address = None;
+    let mut vlan = None;
+    let mut destination = None;
+    let mut port = None;
+    let mut vni = None;
+
+    for attr in attributes {
+        match attr {
+            NeighbourAttribute::LinkLocalAddress(addr) => link_address = Some(addr),
+            NeighbourAttribute::Vlan(value) => vlan = Some(value),
+            // Bridge family messages do not say which family NDA_DST is.
+            NeighbourAttribute::Destination(NeighbourAddress::Other(bytes)) => {
+                destination = if let Ok(octets) = <[u8; 4]>::try_from(bytes.as_slice()) {
+                    Some(IpAddr::from(octets))
+                } else {
+                    <[u8; 16]>::try_from(bytes.as_slice())
+                        .ok()
+                        .map(IpAddr::from)
+                };
+            }
+            NeighbourAttribute::Port(value) => port = Some(value),
+            NeighbourAttribute::Vni(value) => vni = Some(value),
+            _ => {}
+        }
+    }
+
+    Some(FdbEntry {
+        if_id: header.ifindex,
+        link_address: link_address?,
+        vlan,
+        destination,
+        port,
+        vni,
+        state: match header.state {
+            NeighbourState::None => None,
+            other => Some(other),
+        },
+        flags: if header.flags.is_empty() {
+            None
+        } else {
+            Some(header.flags)
+        },
+    })
+}
+
+async fn list_fdb(handle: &rtnetlink::NeighbourHandle, if_id: Option<u32>) -> RtnlNeighborResponse {
+    let mut request = handle.get();
+    request.message_mut().header.family = AddressFamily::Bridge;
+    match request.execute().try_collect::<Vec<_>>().await {
+        Ok(messages) => RtnlNeighborResponse::FdbEntries(
+            messages
+                .into_iter()
+                .filter_map(fdb_from_message)
+                .filter(|entry| if_id.is_none_or(|id| entry.if_id == id))
+                .collect(),
+        ),
+        Err(err) => {
+            warn!("FDB list failed: {}", err);
+            RtnlNeighborResponse::Error(RtnlError::from_rtnetlink(&err))
+        }
+    }
+}
+
+fn map_request_result(
+    result: Result<(), rtnetlink::Error>,
+    operation: &str,
+) -> RtnlNeighborResponse {
+    match result {
+        Ok(()) => RtnlNeighborResponse::Success,
+        Err(rtnetlink::Error::NetlinkError(err_msg)) => {
+            let io_err = err_msg.to_io();
+            match io_err.kind() {
+                ErrorKind::NotFound => RtnlNeighborResponse::NotFound,
+                ErrorKind::AlreadyExists => RtnlNeighborResponse::AlreadyExists,
+                _ => {
+                    warn!("{} failed: {}", operation, io_err);
+                    RtnlNeighborResponse::Error(RtnlError::from_netlink(&err_msg))
+                }
+            }
+        }
+        Err(err) => {
+            warn!("{} failed: {}", operation, err);
+            RtnlNeighborResponse::Error(RtnlError::from_rtnetlink(&err))
+        }
+    }
+}
+
+// NeighbourAddRequest only sends NLM_F_EXCL or NLM_F_REPLACE.
+async fn send_new_neighbor(
+    netlink: &mut rtnetlink::Handle,
+    message: NeighbourMessage,
+    flags: u16,
+) -> Result<(), rtnetlink::Error> {
+    send_request(
+        netlink,
+        RouteNetlinkMessage::NewNeighbour(message),
+        NLM_F_CREATE | flags,
+    )
+    .await
+}
+
+async fn send_request(
+    netlink: &mut rtnetlink::Handle,
+    message: RouteNetlinkMessage,
+    flags: u16,
+) -> Result<(), rtnetlink::Error> {
+    let mut req = NetlinkMessage::from(message);
+    req.header.flags = NLM_F_REQUEST | NLM_F_ACK | flags;
+    let mut response = netlink.request(req)?;
+    while let Some(message) = response.next().await {
+        if let NetlinkPayload::Error(err) = message.payload
+            && err.code.is_some()
+        {
+            return Err(rtnetlink::Error::NetlinkError(err));
+        }
+    }
+    Ok(())
+}
+
+// The kernel sends one message for the table itself, carrying the default
+// parameters, and one per interface with only its parameters.
+async fn get_neighbor_table(
+    netlink: &mut rtnetlink::Handle,
+    kind: NeighborTableKind,
+    if_id: Option<u32>,
+) -> RtnlNeighborResponse {
+    let mut message = NeighbourTableMessage::default();
+    message.header.family = kind.address_family();
+    let mut req = NetlinkMessage::from(RouteNetlinkMessage::GetNeighbourTable(message));
+    req.header.flags = NLM_F_REQUEST | NLM_F_DUMP;
+
+    let mut response = match netlink.request(req) {
+        Ok(response) => response,
+        Err(err) => {
+            warn!("Neighbor table get failed: {}", err);
+            return RtnlNeighborResponse::Error(RtnlError::from_rtnetlink(&err));
+        }
+    };
+
+    let mut params = NeighborTableParams::default();
+    let mut found_table = false;
+    let mut found_parms = false;
+    let name = kind.name();
+    while let Some(message) = response.next().await {
+        let message = match message.payload {
+            NetlinkPayload::InnerMessage(RouteNetlinkMessage::NewNeighbourTable(message)) => {
+                message
+            }
+            NetlinkPayload::Error(err) if err.code.is_some() => {
+                warn!("Neighbor table get failed: {}", err.to_io());
+                return RtnlNeighborResponse::Error(RtnlError::from_netlink(&err));
+            }
+            _ => continue,
+        };
+        if !message
+            .attributes
+            .iter()
+            .any(|attr| matches!(attr, NeighbourTableAttribute::Name(value) if value == name))
+        {
+            continue;
+        }
+        for attr in message.attributes {
+            match attr {
+                NeighbourTableAttribute::Threshold1(value) => {
+                    params.gc_thresh1 = Some(value);
+                    found_table = true;
+                }
+                NeighbourTableAttribute::Threshold2(value) => params.gc_thresh2 = Some(value),
+                NeighbourTableAttribute::Threshold3(value) => params.gc_thresh3 = Some(value),
+                NeighbourTableAttribute::GcInterval(value) => {
+                    params.gc_interval = Some(Duration::from_millis(value))
+                }
+                NeighbourTableAttribute::Parms(values) => {
+                    let index = values.iter().find_map(|value| match value {
+                        NeighbourTableParameter::Ifindex(index) => Some(*index),
+                        _ => None,
+                    });
+                    if index != if_id {
+                        continue;
+                    }
+                    found_parms = true;
+                    for value in values {
+                        match value {
+                            NeighbourTableParameter::BaseReachableTime(value) => {
+                                params.base_reachable_time = Some(Duration::from_millis(value))
+                            }
+                            NeighbourTableParameter::GcStaletime(value) => {
+                                params.gc_stale_time = Some(Duration::from_millis(value))
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if found_table && found_parms {
+        RtnlNeighborResponse::Table(params)
+    } else {
+        RtnlNeighborResponse::NotFound
+    }
+}
+
+async fn set_neighbor_table(
+    netlink: &mut rtnetlink::Handle,
+    kind: NeighborTableKind,
+    if_id: Option<u32>,
+    params: NeighborTableParams,
+) -> RtnlNeighborResponse {
+    let mut message = NeighbourTableMessage::default();
+    message.header.family = kind.address_family();
+    message
+        .attributes
+        .push(NeighbourTableAttribute::Name(kind.name().to_string()));
+    message
+        .attributes
+        .extend(params.gc_thresh1.map(NeighbourTableAttribute::Threshold1));
+    message
+        .attributes
+        .extend(params.gc_thresh2.map(NeighbourTableAttribute::Threshold2));
+    message
+        .attributes
+        .extend(params.gc_thresh3.map(NeighbourTableAttribute::Threshold3));
+    message.attributes.extend(
+        params
+            .gc_interval
+            .map(|value| NeighbourTableAttribute::GcInterval(duration_millis(value))),
+    );
+
+    let mut parms = Vec::new();
+    parms.extend(
+        params
+            .base_reachable_time
+            .map(|value| NeighbourTableParameter::BaseReachableTime(duration_millis(value))),
+    );
+    parms.extend(
+        params
+            .gc_stale_time
+            .map(|value| NeighbourTableParameter::GcStaletime(duration_millis(value))),
+    );
+    if !parms.is_empty() {
+        parms.extend(if_id.map(NeighbourTableParameter::Ifindex));
+        message
+            .attributes
+            .push(NeighbourTableAttribute::Parms(parms));
+    }
+
+    // NotFound for an interface without neighbor parameters.
+    map_request_result(
+        send_request(netlink, RouteNetlinkMessage::SetNeighbourTable(message), 0).await,
+        "Neighbor table set",
+    )
+}
+
+fn duration_millis(value: Duration) -> u64 {
+    u64::try_from(value.as_millis()).unwrap_or(u64::MAX)
+}
+
+const EOPNOTSUPP: i32 = 95;
+const ETH_ALEN: usize = 6;
+const VXLAN_VNI_MAX: u32 = (1 << 24) - 1;
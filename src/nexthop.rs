@@ -0,0 +1,628 @@
+use std::collections::HashSet;
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use ftth_common::channel::{AsyncWorldClient, AsyncWorldServer};
+use futures::StreamExt;
+use netlink_packet_core::{
+    DecodeError, DefaultNla, Emitable, ErrorMessage, NLM_F_ACK, NLM_F_CREATE, NLM_F_DUMP,
+    NLM_F_EXCL, NLM_F_REPLACE, NLM_F_REQUEST, NetlinkDeserializable, NetlinkHeader, NetlinkMessage,
+    NetlinkPayload, NetlinkSerializable, Nla, NlasIterator, Parseable,
+};
+use netlink_packet_route::route::RouteProtocol;
+use rtnetlink::proto::ConnectionHandle;
+use rtnetlink::sys::SocketAddr;
+use tracing::warn;
+
+use crate::error::RtnlError;
+
+pub(crate) type Client = AsyncWorldClient<RtnlNexthopRequest, RtnlNexthopResponse>;
+pub(crate) type Server = AsyncWorldServer<RtnlNexthopRequest, RtnlNexthopResponse>;
+pub(crate) type Handle = ConnectionHandle<NexthopMessage>;
+
+const RTM_NEWNEXTHOP: u16 = 104;
+const RTM_DELNEXTHOP: u16 = 105;
+const RTM_GETNEXTHOP: u16 = 106;
+
+const NHA_ID: u16 = 1;
+const NHA_GROUP: u16 = 2;
+const NHA_GROUP_TYPE: u16 = 3;
+const NHA_BLACKHOLE: u16 = 4;
+const NHA_OIF: u16 = 5;
+const NHA_GATEWAY: u16 = 6;
+
+const NEXTHOP_GRP_TYPE_MPATH: u16 = 0;
+const RTNH_F_ONLINK: u32 = 4;
+
+const AF_UNSPEC: u8 = 0;
+const AF_INET: u8 = 2;
+const AF_INET6: u8 = 10;
+
+const NHMSG_LEN: usize = 8;
+const NEXTHOP_GRP_LEN: usize = 8;
+// Group weights are sent as `weight - 1` in a single byte.
+const MAX_GROUP_WEIGHT: u16 = u8::MAX as u16 + 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NexthopFamily {
+    Ipv4,
+    Ipv6,
+}
+
+// A nexthop object, as managed with `ip nexthop`. Routes refer to it by
+// `id`, so changing the object moves every route using it at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nexthop {
+    pub id: u32,
+    pub kind: NexthopKind,
+    // Who installed the nexthop; unset leaves it unspecified.
+    pub protocol: Option<RouteProtocol>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NexthopKind {
+    // Reached through `gateway` on `if_id`, or directly on `if_id` when no
+    // gateway is given. An IPv4 nexthop may use an IPv6 gateway.
+    Unicast {
+        family: NexthopFamily,
+        if_id: u32,
+        gateway: Option<IpAddr>,
+        onlink: bool,
+    },
+    Blackhole(NexthopFamily),
+    // Multipath group over other nexthop objects, which must exist before
+    // the group is added.
+    Group(Vec<NexthopGroupMember>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NexthopGroupMember {
+    pub id: u32,
+    // 1 to 256.
+    pub weight: u16,
+}
+
+impl Nexthop {
+    pub fn gateway(id: u32, if_id: u32, gateway: IpAddr) -> Self {
+        let family = match gateway {
+            IpAddr::V4(_) => NexthopFamily::Ipv4,
+            IpAddr::V6(_) => NexthopFamily::Ipv6,
+        };
+        Self {
+            id,
+            kind: NexthopKind::Unicast {
+                family,
+                if_id,
+                gateway: Some(gateway),
+                onlink: false,
+            },
+            protocol: None,
+        }
+    }
+
+    pub fn group(id: u32, members: Vec<NexthopGroupMember>) -> Self {
+        Self {
+            id,
+            kind: NexthopKind::Group(members),
+            protocol: None,
+        }
+    }
+}
+
+impl NexthopGroupMember {
+    pub fn new(id: u32) -> Self {
+        Self { id, weight: 1 }
+    }
+}
+
+// netlink-packet-route has no RTM_*NEXTHOP messages, so `struct nhmsg` and
+// its attributes are encoded here and sent over a connection of their own.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct NexthopMessage {
+    message_type: u16,
+    family: u8,
+    scope: u8,
+    protocol: u8,
+    flags: u32,
+    attributes: Vec<DefaultNla>,
+}
+
+impl NetlinkSerializable for NexthopMessage {
+    fn message_type(&self) -> u16 {
+        self.message_type
+    }
+
+    fn buffer_len(&self) -> usize {
+        NHMSG_LEN + self.attributes.as_slice().buffer_len()
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) {
+        buffer[0] = self.family;
+        buffer[1] = self.scope;
+        buffer[2] = self.protocol;
+        buffer[3] = 0;
+        buffer[4..NHMSG_LEN].copy_from_slice(&self.flags.to_ne_bytes());
+        self.attributes.as_slice().emit(&mut buffer[NHMSG_LEN..]);
+    }
+}
+
+impl NetlinkDeserializable for NexthopMessage {
+    type Error = DecodeError;
+
+    fn deserialize(header: &NetlinkHeader, payload: &[u8]) -> Result<Self, DecodeError> {
+        if payload.len() < NHMSG_LEN {
+            return Err(DecodeError::from("Nexthop message is too short"));
+        }
+        let attributes = NlasIterator::new(&payload[NHMSG_LEN..])
+            .map(|nla| nla.and_then(|nla| DefaultNla::parse(&nla)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            message_type: header.message_type,
+            family: payload[0],
+            scope: payload[1],
+            protocol: payload[2],
+            flags: u32::from_ne_bytes([payload[4], payload[5], payload[6], payload[7]]),
+            attributes,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RtnlNexthopRequest {
+    Add(Nexthop),
+    Replace(Nexthop),
+    Delete(u32),
+    Get(u32),
+    List,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RtnlNexthopResponse {
+    Success,
+    NotFound,
+    AlreadyExists,
+    Nexthop(Nexthop),
+    Nexthops(Vec<Nexthop>),
+    Error(RtnlError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RtnlNexthopClient {
+    client: Client,
+}
+
+impl RtnlNexthopClient {
+    pub(crate) fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub fn send_raw(&self, request: RtnlNexthopRequest) -> io::Result<RtnlNexthopResponse> {
+        self.client.send_request(request)
+    }
+
+    // Fails with AlreadyExists when a nexthop with the same id exists.
+    pub fn add(&self, nexthop: Nexthop) -> io::Result<()> {
+        validate_nexthop(&nexthop)?;
+        let res = self.client.send_request(RtnlNexthopRequest::Add(nexthop))?;
+        handle_nexthop_response("Nexthop add", res)
+    }
+
+    // Creates the nexthop or replaces it in place. Replacing a group swaps
+    // its members in one step, without touching the routes that use it.
+    pub fn replace(&self, nexthop: Nexthop) -> io::Result<()> {
+        validate_nexthop(&nexthop)?;
+        let res = self
+            .client
+            .send_request(RtnlNexthopRequest::Replace(nexthop))?;
+        handle_nexthop_response("Nexthop replace", res)
+    }
+
+    pub fn delete(&self, id: u32) -> io::Result<()> {
+        let res = self.client.send_request(RtnlNexthopRequest::Delete(id))?;
+        handle_nexthop_response("Nexthop delete", res)
+    }
+
+    pub fn get(&self, id: u32) -> io::Result<Nexthop> {
+        match self.client.send_request(RtnlNexthopRequest::Get(id))? {
+            RtnlNexthopResponse::Nexthop(nexthop) => Ok(nexthop),
+            other => {
+                handle_nexthop_response("Nexthop get", other)?;
+                Err(io::Error::other("Nexthop get returned no nexthop"))
+            }
+        }
+    }
+
+    // Nexthops of kinds not modeled here, such as FDB nexthops, are skipped.
+    pub fn list(&self) -> io::Result<Vec<Nexthop>> {
+        match self.client.send_request(RtnlNexthopRequest::List)? {
+            RtnlNexthopResponse::Nexthops(nexthops) => Ok(nexthops),
+            RtnlNexthopResponse::Error(err) => Err(err.into_io_error("Nexthop list")),
+            other => Err(io::Error::other(format!(
+                "Unexpected response for nexthop list: {:?}",
+                other
+            ))),
+        }
+    }
+}
+
+fn handle_nexthop_response(operation: &str, response: RtnlNexthopResponse) -> io::Result<()> {
+    match response {
+        RtnlNexthopResponse::Success => Ok(()),
+        RtnlNexthopResponse::NotFound => Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("{}: nexthop not found", operation),
+        )),
+        RtnlNexthopResponse::AlreadyExists => Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{}: nexthop already exists", operation),
+        )),
+        RtnlNexthopResponse::Error(err) => Err(err.into_io_error(operation)),
+        other => Err(io::Error::other(format!(
+            "{} returned unexpected response: {:?}",
+            operation, other
+        ))),
+    }
+}
+
+fn validate_nexthop(nexthop: &Nexthop) -> io::Result<()> {
+    if nexthop.id == 0 {
+        return Err(invalid_input("Nexthop id must not be 0"));
+    }
+    match &nexthop.kind {
+        NexthopKind::Unicast {
+            family: NexthopFamily::Ipv6,
+            gateway: Some(IpAddr::V4(_)),
+            ..
+        } => Err(invalid_input("IPv6 nexthop cannot use an IPv4 gateway")),
+        NexthopKind::Group(members) => {
+            if members.is_empty() {
+                return Err(invalid_input("Nexthop group has no members"));
+            }
+            let mut ids = HashSet::new();
+            for member in members {
+                if member.id == nexthop.id {
+                    return Err(invalid_input("Nexthop group cannot contain itself"));
+                }
+                if !ids.insert(member.id) {
+                    return Err(invalid_input(format!(
+                        "Nexthop {} is listed twice in the group",
+                        member.id
+                    )));
+                }
+                if member.weight == 0 || member.weight > MAX_GROUP_WEIGHT {
+                    return Err(invalid_input(format!(
+                        "Nexthop group weight {} is outside 1..={}",
+                        member.weight, MAX_GROUP_WEIGHT
+                    )));
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn invalid_input(message: impl Into<String>) -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, message.into())
+}
+
+pub(crate) async fn run_server(mut server: Server, handle: Handle) {
+    while let Some((req, respond)) = server.accept().await {
+        let response = match req {
+            RtnlNexthopRequest::Add(nexthop) => {
+                let message = build_nexthop_message(&nexthop);
+                map_result(
+                    "Nexthop add",
+                    send_request(&handle, message, NLM_F_ACK | NLM_F_CREATE | NLM_F_EXCL)
+                        .await
+                        .map(drop),
+                )
+            }
+            RtnlNexthopRequest::Replace(nexthop) => {
+                let message = build_nexthop_message(&nexthop);
+                map_result(
+                    "Nexthop replace",
+                    send_request(&handle, message, NLM_F_ACK | NLM_F_CREATE | NLM_F_REPLACE)
+                        .await
+                        .map(drop),
+                )
+            }
+            RtnlNexthopRequest::Delete(id) => {
+                let message = id_message(RTM_DELNEXTHOP, id);
+                map_result(
+                    "Nexthop delete",
+                    send_request(&handle, message, NLM_F_ACK).await.map(drop),
+                )
+            }
+            RtnlNexthopRequest::Get(id) => get_nexthop(&handle, id).await,
+            RtnlNexthopRequest::List => list_nexthops(&handle).await,
+        };
+        respond(response);
+    }
+}
+
+async fn get_nexthop(handle: &Handle, id: u32) -> RtnlNexthopResponse {
+    match send_request(handle, id_message(RTM_GETNEXTHOP, id), 0).await {
+        Ok(messages) => match messages.iter().find_map(nexthop_from_message) {
+            Some(nexthop) => RtnlNexthopResponse::Nexthop(nexthop),
+            None => RtnlNexthopResponse::NotFound,
+        },
+        Err(err) => map_result("Nexthop get", Err(err)),
+    }
+}
+
+async fn list_nexthops(handle: &Handle) -> RtnlNexthopResponse {
+    let message = NexthopMessage {
+        message_type: RTM_GETNEXTHOP,
+        ..NexthopMessage::default()
+    };
+    match send_request(handle, message, NLM_F_DUMP).await {
+        Ok(messages) => RtnlNexthopResponse::Nexthops(
+            messages.iter().filter_map(nexthop_from_message).collect(),
+        ),
+        Err(err) => map_result("Nexthop list", Err(err)),
+    }
+}
+
+enum RequestError {
+    Netlink(ErrorMessage),
+    Other(String),
+}
+
+async fn send_request(
+    handle: &Handle,
+    message: NexthopMessage,
+    flags: u16,
+) -> Result<Vec<NexthopMessage>, RequestError> {
+    let mut req = NetlinkMessage::new(
+        NetlinkHeader::default(),
+        NetlinkPayload::InnerMessage(message),
+    );
+    req.header.flags = NLM_F_REQUEST | flags;
+    req.finalize();
+    let mut response = handle
+        .request(req, SocketAddr::new(0, 0))
+        .map_err(|err| RequestError::Other(err.to_string()))?;
+    let mut messages = Vec::new();
+    while let Some(message) = response.next().await {
+        match message.payload {
+            NetlinkPayload::InnerMessage(message) => messages.push(message),
+            NetlinkPayload::Error(err) if err.code.is_some() => {
+                return Err(RequestError::Netlink(err));
+            }
+            _ => {}
+        }
+    }
+    Ok(messages)
+}
+
+fn map_result(operation: &str, result: Result<(), RequestError>) -> RtnlNexthopResponse {
+    match result {
+        Ok(()) => RtnlNexthopResponse::Success,
+        Err(RequestError::Netlink(err_msg)) => match err_msg.to_io().kind() {
+            ErrorKind::NotFound => RtnlNexthopResponse::NotFound,
+            ErrorKind::AlreadyExists => RtnlNexthopResponse::AlreadyExists,
+            _ => {
+                warn!("{} failed: {}", operation, err_msg);
+                RtnlNexthopResponse::Error(RtnlError::from_netlink(&err_msg))
+            }
+        },
+        Err(RequestError::Other(err)) => {
+            warn!("{} failed: {}", operation, err);
+            RtnlNexthopResponse::Error(RtnlError::new(err))
+        }
+    }
+}
+
+fn id_message(message_type: u16, id: u32) -> NexthopMessage {
+    NexthopMessage {
+        message_type,
+        attributes: vec![u32_nla(NHA_ID, id)],
+        ..NexthopMessage::default()
+    }
+}
+
+fn u32_nla(kind: u16, value: u32) -> DefaultNla {
+    DefaultNla::new(kind, value.to_ne_bytes().to_vec())
+}
+
+fn family_code(family: NexthopFamily) -> u8 {
+    match family {
+        NexthopFamily::Ipv4 => AF_INET,
+        NexthopFamily::Ipv6 => AF_INET6,
+    }
+}
+
+fn build_nexthop_message(nexthop: &Nexthop) -> NexthopMessage {
+    let mut message = NexthopMessage {
+        message_type: RTM_NEWNEXTHOP,
+        protocol: nexthop.protocol.map(u8::from).unwrap_or_default(),
+        attributes: vec![u32_nla(NHA_ID, nexthop.id)],
+        ..NexthopMessage::default()
+    };
+    match &nexthop.kind {
+        NexthopKind::Unicast {
+            family,
+            if_id,
+            gateway,
+            onlink,
+        } => {
+            message.family = family_code(*family);
+            if *onlink {
+                message.flags |= RTNH_F_ONLINK;
+            }
+            message.attributes.push(u32_nla(NHA_OIF, *if_id));
+            if let Some(gateway) = gateway {
+                let octets = match gateway {
+                    IpAddr::V4(addr) => addr.octets().to_vec(),
+                    IpAddr::V6(addr) => addr.octets().to_vec(),
+                };
+                message
+                    .attributes
+                    .push(DefaultNla::new(NHA_GATEWAY, octets));
+            }
+        }
+        NexthopKind::Blackhole(family) => {
+            message.family = family_code(*family);
+            message
+                .attributes
+                .push(DefaultNla::new(NHA_BLACKHOLE, Vec::new()));
+        }
+        NexthopKind::Group(members) => {
+            message.family = AF_UNSPEC;
+            message.attributes.push(DefaultNla::new(
+                NHA_GROUP,
+                members.iter().flat_map(group_entry).collect(),
+            ));
+            message.attributes.push(DefaultNla::new(
+                NHA_GROUP_TYPE,
+                NEXTHOP_GRP_TYPE_MPATH.to_ne_bytes().to_vec(),
+            ));
+        }
+    }
+    message
+}
+
+// struct nexthop_grp: id, weight - 1, and three reserved bytes.
+fn group_entry(member: &NexthopGroupMember) -> [u8; NEXTHOP_GRP_LEN] {
+    let mut entry = [0; NEXTHOP_GRP_LEN];
+    entry[0..4].copy_from_slice(&member.id.to_ne_bytes());
+    entry[4] = (member.weight - 1) as u8;
+    entry
+}
+
+fn nla_value(nla: &DefaultNla) -> Vec<u8> {
+    let mut value = vec![0; nla.value_len()];
+    nla.emit_value(&mut value);
+    value
+}
+
+fn nla_u32(nla: &DefaultNla) -> Option<u32> {
+    Some(u32::from_ne_bytes(
+        nla_value(nla).get(0..4)?.try_into().ok()?,
+    ))
+}
+
+fn nexthop_from_message(message: &NexthopMessage) -> Option<Nexthop> {
+    let family = match message.family {
+        AF_INET => Some(NexthopFamily::Ipv4),
+        AF_INET6 => Some(NexthopFamily::Ipv6),
+        _ => None,
+    };
+    let mut id = None;
+    let mut if_id = None;
+    let mut gateway = None;
+    let mut blackhole = false;
+    let mut members = None;
+    for nla in &message.attributes {
+        match nla.kind() {
+            NHA_ID => id = nla_u32(nla),
+            NHA_OIF => if_id = nla_u32(nla),
+            NHA_BLACKHOLE => blackhole = true,
+            NHA_GATEWAY => {
+                let value = nla_value(nla);
+                gateway = match value.len() {
+                    4 => <[u8; 4]>::try_from(value.as_slice())
+                        .ok()
+                        .map(|octets| IpAddr::V4(Ipv4Addr::from(octets))),
+                    16 => <[u8; 16]>::try_from(value.as_slice())
+                        .ok()
+                        .map(|octets| IpAddr::V6(Ipv6Addr::from(octets))),
+                    _ => None,
+                };
+            }
+            NHA_GROUP => {
+                members = Some(
+                    nla_value(nla)
+                        .chunks_exact(NEXTHOP_GRP_LEN)
+                        .map(|entry| NexthopGroupMember {
+                            id: u32::from_ne_bytes([entry[0], entry[1], entry[2], entry[3]]),
+                            weight: entry[4] as u16 + 1,
+                        })
+                        .collect(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    let kind = match members {
+        Some(members) => NexthopKind::Group(members),
+        None if blackhole => NexthopKind::Blackhole(family?),
+        None => NexthopKind::Unicast {
+            family: family?,
+            if_id: if_id?,
+            gateway,
+            onlink: message.flags & RTNH_F_ONLINK != 0,
+        },
+    };
+    Some(Nexthop {
+        id: id?,
+        kind,
+        protocol: match message.protocol {
+            0 => None,
+            protocol => Some(RouteProtocol::from(protocol)),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(nexthop: &Nexthop) -> Option<Nexthop> {
+        let message = build_nexthop_message(nexthop);
+        let mut buffer = vec![0; message.buffer_len()];
+        message.serialize(&mut buffer);
+        let mut header = NetlinkHeader::default();
+        header.message_type = RTM_NEWNEXTHOP;
+        let decoded = NexthopMessage::deserialize(&header, &buffer).unwrap();
+        nexthop_from_message(&decoded)
+    }
+
+    #[test]
+    fn gateway_round_trip() {
+        let nexthop = Nexthop::gateway(1, 2, "2001:db8::1".parse().unwrap());
+        assert_eq!(round_trip(&nexthop), Some(nexthop));
+    }
+
+    #[test]
+    fn ipv4_via_ipv6_round_trip() {
+        let nexthop = Nexthop {
+            id: 3,
+            kind: NexthopKind::Unicast {
+                family: NexthopFamily::Ipv4,
+                if_id: 2,
+                gateway: Some("fe80::1".parse().unwrap()),
+                onlink: true,
+            },
+            protocol: Some(RouteProtocol::Static),
+        };
+        assert_eq!(round_trip(&nexthop), Some(nexthop));
+    }
+
+    #[test]
+    fn group_round_trip() {
+        let nexthop = Nexthop::group(
+            10,
+            vec![
+                NexthopGroupMember::new(1),
+                NexthopGroupMember { id: 2, weight: 256 },
+            ],
+        );
+        assert_eq!(round_trip(&nexthop), Some(nexthop));
+    }
+
+    #[test]
+    fn group_validation() {
+        let member = NexthopGroupMember::new(1);
+        assert!(validate_nexthop(&Nexthop::group(10, vec![member])).is_ok());
+        assert!(validate_nexthop(&Nexthop::group(10, Vec::new())).is_err());
+        assert!(validate_nexthop(&Nexthop::group(10, vec![member, member])).is_err());
+        assert!(validate_nexthop(&Nexthop::group(1, vec![member])).is_err());
+        let heavy = NexthopGroupMember { id: 1, weight: 257 };
+        assert!(validate_nexthop(&Nexthop::group(10, vec![heavy])).is_err());
+    }
+}
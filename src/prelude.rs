@@ -0,0 +1,52 @@
+pub use crate::RtnlClient;
+#[cfg(all(feature = "address", feature = "monitor"))]
+pub use crate::address::{AddressEvent, AddressWatcher};
+#[cfg(feature = "address")]
+pub use crate::address::{
+    AddressFilter, AddressInfo, AddressOptions, AddressSyncReport, IpFamily, RtnlAddressClient,
+};
+pub use crate::error::RtnlError;
+#[cfg(feature = "link")]
+pub use crate::link::{
+    BridgePort, CarrierStats, Interface, LinkDetail, LinkKind, LinkStats, LinkUpdate, MacAddr,
+    OffloadLimit, ProtoDown, RtnlLinkClient,
+};
+#[cfg(feature = "monitor")]
+pub use crate::monitor::{Ipv6PrefixInfo, RtnlEvent, RtnlGroup, RtnlMonitor};
+#[cfg(feature = "neighbor")]
+pub use crate::neighbor::{
+    FdbEntry, NeighborDelete, NeighborEntry, NeighborTableKind, NeighborTableParams,
+    RtnlNeighborClient,
+};
+#[cfg(feature = "nexthop")]
+pub use crate::nexthop::{
+    Nexthop, NexthopFamily, NexthopGroupMember, NexthopKind, RtnlNexthopClient,
+};
+#[cfg(feature = "route")]
+pub use crate::route::{
+    IpRoute, IpTunnelEncap, Ipv4Route, Ipv6Route, MplsNextHop, MplsRoute, MulticastOif,
+    MulticastRoute, MulticastRouteStats, RouteCacheInfo, RouteEncap, RouteFilter, RouteGateway,
+    RouteLookupOptions, RouteMetrics, RouteNextHopInfo, RoutePlan, RouteSet, RouteStatus,
+    RouteTableNames, RtnlRouteClient, Seg6LocalAction,
+};
+#[cfg(feature = "rule")]
+pub use crate::rule::{RoutingRule, RtnlRuleClient, RuleAction, RuleFamily};
+#[cfg(feature = "virtual-interface")]
+pub use crate::virtual_interface::{
+    EnsureAction, GeneveConfig, Gre6Config, GreConfig, GreFlags, Ip6TnlConfig, Ip6TnlMode,
+    Ip6TunnelFlags, IpIpConfig, MacsecCipherSuite, MacsecConfig, RtnlVirtualInterfaceClient,
+    SitConfig, VirtualInterfaceDelete, VirtualInterfaceInfo, VirtualInterfaceKind,
+    VirtualInterfaceSpec, VirtualInterfaceType, VirtualInterfaceUpdate, VlanConfig, VxlanConfig,
+};
+
+pub use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+#[cfg(feature = "address")]
+pub use netlink_packet_route::address::{AddressFlags, AddressScope};
+#[cfg(feature = "link")]
+pub use netlink_packet_route::link::{
+    BridgePortState, InfoKind, LinkFlags, LinkLayerType, State as OperState,
+};
+#[cfg(feature = "neighbor")]
+pub use netlink_packet_route::neighbour::{NeighbourFlags, NeighbourState};
+#[cfg(feature = "route")]
+pub use netlink_packet_route::route::{RouteNextHopFlags, RouteProtocol, RouteType, Seg6Mode};
@@ -1,17 +1,31 @@
 #![allow(unreachable_patterns)]
 
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::io::{self, ErrorKind};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
 
 use ftth_common::channel::{AsyncWorldClient, AsyncWorldServer};
-use futures::TryStreamExt;
-use tracing::warn;
+use futures::{StreamExt, TryStreamExt};
+use netlink_packet_core::{
+    DefaultNla, NLM_F_ACK, NLM_F_APPEND, NLM_F_CREATE, NLM_F_REPLACE, NLM_F_REQUEST,
+    NetlinkMessage, NetlinkPayload, Nla,
+};
 use netlink_packet_route::AddressFamily;
+use netlink_packet_route::RouteNetlinkMessage;
 use netlink_packet_route::route::{
-    RouteAddress, RouteAttribute, RouteMessage, RouteNextHop, RouteNextHopFlags, RouteType,
-    RouteVia,
+    MplsLabel, RouteAddress, RouteAttribute, RouteFlags, RouteIp6Tunnel, RouteLwEnCapType,
+    RouteLwTunnelEncap, RouteMessage, RouteMetric, RouteMplsIpTunnel, RouteNextHop,
+    RouteNextHopFlags, RouteProtocol, RouteSeg6IpTunnel, RouteType, RouteVia, Seg6Mode,
 };
 use rtnetlink::RouteMessageBuilder;
+use tracing::warn;
+
+use crate::error::RtnlError;
+
+const ENODEV: i32 = 19;
 
 pub(crate) type Client = AsyncWorldClient<RtnlRouteRequest, RtnlRouteResponse>;
 pub(crate) type Server = AsyncWorldServer<RtnlRouteRequest, RtnlRouteResponse>;
@@ -19,31 +33,932 @@ pub(crate) type Server = AsyncWorldServer<RtnlRouteRequest, RtnlRouteResponse>;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Ipv4Route {
     pub if_id: Option<u32>,
-    pub gateway: Option<IpAddr>,
+    // Resolved to `if_id` by the route server when `if_id` is unset.
+    pub if_name: Option<String>,
+    pub gateway: Option<RouteGateway>,
     pub source: Option<Ipv4Addr>,
     pub metric: Option<u32>,
+    // Matched against the DSCP bits of the packet's TOS byte; 0 matches any.
+    // Part of the route's identity along with the prefix and metric.
+    pub tos: u8,
     pub table: Option<u32>,
     pub route: crate::Ipv4Net,
     pub nexthops: Vec<RouteNextHopInfo>,
+    // Nexthop object or group the route uses instead of `if_id`, `gateway`
+    // and `nexthops`. The kernel still reports the resolved nexthops, which
+    // are left out when the route is sent back.
+    pub nexthop_id: Option<u32>,
+    // Blackhole, Unreachable, Prohibit and Throw routes carry no nexthop.
+    pub kind: RouteType,
+    // Who installed the route. Unset installs as static unless the client
+    // has a default protocol; ignored by `normalized()`.
+    pub protocol: Option<RouteProtocol>,
+    // Installed locked, so path MTU discovery cannot raise or lower it,
+    // unless `metrics.lock` says otherwise.
+    pub mtu: Option<u32>,
+    pub metrics: RouteMetrics,
+    // Treats the gateway as directly reachable on the output interface even
+    // when it is outside the interface's prefixes.
+    pub onlink: bool,
+    pub encap: Option<RouteEncap>,
+    pub status: RouteStatus,
+    pub cache_info: Option<RouteCacheInfo>,
+    // Attributes the kernel reported that the fields above do not model,
+    // such as the IPv6 router preference or other TCP metrics. They are sent
+    // back as they are, so changing a route read from the kernel keeps them;
+    // ignored by `normalized()`.
+    pub extra: Vec<RouteAttribute>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Ipv6Route {
     pub if_id: Option<u32>,
-    pub gateway: Option<IpAddr>,
+    // Resolved to `if_id` by the route server when `if_id` is unset.
+    pub if_name: Option<String>,
+    pub gateway: Option<RouteGateway>,
     pub source: Option<Ipv6Addr>,
     pub metric: Option<u32>,
     pub table: Option<u32>,
     pub route: crate::Ipv6Net,
     pub nexthops: Vec<RouteNextHopInfo>,
+    // Nexthop object or group the route uses instead of `if_id`, `gateway`
+    // and `nexthops`. The kernel still reports the resolved nexthops, which
+    // are left out when the route is sent back.
+    pub nexthop_id: Option<u32>,
+    // Blackhole, Unreachable, Prohibit and Throw routes carry no nexthop.
+    pub kind: RouteType,
+    // Who installed the route. Unset installs as static unless the client
+    // has a default protocol; ignored by `normalized()`.
+    pub protocol: Option<RouteProtocol>,
+    // Installed locked, so path MTU discovery cannot raise or lower it,
+    // unless `metrics.lock` says otherwise.
+    pub mtu: Option<u32>,
+    pub metrics: RouteMetrics,
+    // Treats the gateway as directly reachable on the output interface even
+    // when it is outside the interface's prefixes.
+    pub onlink: bool,
+    pub encap: Option<RouteEncap>,
+    // Seconds until the kernel removes the route; decoded routes report the
+    // time remaining.
+    pub expires: Option<u32>,
+    pub status: RouteStatus,
+    pub cache_info: Option<RouteCacheInfo>,
+    // Attributes the kernel reported that the fields above do not model,
+    // such as the IPv6 router preference or other TCP metrics. They are sent
+    // back as they are, so changing a route read from the kernel keeps them;
+    // ignored by `normalized()`.
+    pub extra: Vec<RouteAttribute>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum IpRoute {
+    V4(Ipv4Route),
+    V6(Ipv6Route),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RouteNextHopInfo {
     pub if_id: Option<u32>,
-    pub gateway: Option<IpAddr>,
+    pub if_name: Option<String>,
+    pub gateway: Option<RouteGateway>,
     pub weight: u32,
+    // Only Onlink and Pervasive can be set; the kernel's state bits are
+    // decoded into `status` instead.
     pub flags: RouteNextHopFlags,
+    pub status: RouteStatus,
+    // Per-nexthop encapsulation, such as the MPLS label stack pushed on
+    // packets sent through this nexthop.
+    pub encap: Option<RouteEncap>,
+}
+
+// A nexthop address as carried on the wire. `Via` is reported for RTA_VIA and
+// may be of the other family than the route, as for IPv4 routes over IPv6
+// nexthops (RFC 5549). Either variant is sent as RTA_GATEWAY when the address
+// is of the route's own family and as RTA_VIA otherwise; only IPv4 routes
+// accept RTA_VIA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RouteGateway {
+    Gateway(IpAddr),
+    Via(IpAddr),
+}
+
+// Forwarding state reported by the kernel for a route or one of its
+// nexthops. Ignored when installing routes and cleared by `normalized()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct RouteStatus {
+    pub dead: bool,
+    // The output interface has no carrier.
+    pub linkdown: bool,
+    // The gateway has no resolved neighbour entry.
+    pub unresolved: bool,
+    // Installed in hardware.
+    pub offload: bool,
+    // Hardware sends matching packets to the CPU.
+    pub trap: bool,
+    pub offload_failed: bool,
+    // A cached clone, such as an exception route from a PMTU update or a
+    // redirect, rather than a route from the FIB.
+    pub cloned: bool,
+}
+
+// Usage counters from RTA_CACHEINFO, reported by route lookups and for IPv6
+// routes in listings. Ignored when installing routes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct RouteCacheInfo {
+    // Time since the route was last used.
+    pub age: Duration,
+    pub expires: Option<Duration>,
+    pub used: u32,
+    pub error: u32,
+}
+
+// Lightweight tunnel encapsulation applied to packets using the route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RouteEncap {
+    // Label values to push, outermost first.
+    Mpls {
+        labels: Vec<u32>,
+        ttl: Option<u8>,
+    },
+    // SRv6 segment list, first segment to visit first.
+    Seg6 {
+        mode: Seg6Mode,
+        segments: Vec<Ipv6Addr>,
+    },
+    // SRv6 behaviour bound to the route's prefix as a local SID; IPv6 only.
+    Seg6Local(Seg6LocalAction),
+    Ip(IpTunnelEncap),
+}
+
+// Tunnel metadata for routes into a collect_md (external) tunnel device. The
+// family of `destination` selects ip or ip6 encapsulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpTunnelEncap {
+    pub id: Option<u64>,
+    pub destination: IpAddr,
+    pub source: Option<IpAddr>,
+    // TTL or hop limit.
+    pub ttl: Option<u8>,
+    // TOS or traffic class.
+    pub tos: Option<u8>,
+    pub csum: bool,
+    pub key: bool,
+    pub seq: bool,
+}
+
+impl IpTunnelEncap {
+    pub fn new(destination: IpAddr) -> Self {
+        Self {
+            id: None,
+            destination,
+            source: None,
+            ttl: None,
+            tos: None,
+            csum: false,
+            key: false,
+            seq: false,
+        }
+    }
+
+    fn flags(&self) -> u16 {
+        let mut bits = 0;
+        if self.csum {
+            bits |= TUNNEL_CSUM;
+        }
+        if self.key {
+            bits |= TUNNEL_KEY;
+        }
+        if self.seq {
+            bits |= TUNNEL_SEQ;
+        }
+        bits
+    }
+
+    // Dumps carry every attribute, with zero standing for unset.
+    fn without_zeroes(mut self) -> Self {
+        self.id = self.id.filter(|id| *id != 0);
+        self.source = self.source.filter(|addr| !addr.is_unspecified());
+        self.ttl = self.ttl.filter(|ttl| *ttl != 0);
+        self.tos = self.tos.filter(|tos| *tos != 0);
+        self
+    }
+
+    fn set_flags(&mut self, bits: u16) {
+        self.csum = bits & TUNNEL_CSUM != 0;
+        self.key = bits & TUNNEL_KEY != 0;
+        self.seq = bits & TUNNEL_SEQ != 0;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Seg6LocalAction {
+    End,
+    EndX { nexthop: Ipv6Addr },
+    EndT { table: u32 },
+    EndDx2 { if_id: u32 },
+    EndDx4 { nexthop: Ipv4Addr },
+    EndDx6 { nexthop: Ipv6Addr },
+    EndDt4 { vrf_table: u32 },
+    EndDt6 { table: u32 },
+}
+
+// A route in the MPLS label forwarding table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MplsRoute {
+    pub label: u32,
+    // Labels swapped in for `label`, outermost first; empty pops it.
+    pub out_labels: Vec<u32>,
+    pub via: Option<IpAddr>,
+    pub if_id: Option<u32>,
+    // Who installed the route. Unset installs as static unless the client
+    // has a default protocol.
+    pub protocol: Option<RouteProtocol>,
+    // Multipath nexthops; when set, `out_labels`, `via` and `if_id` are
+    // ignored.
+    pub nexthops: Vec<MplsNextHop>,
+}
+
+// One path of a multipath MPLS route. The kernel supports neither weights
+// nor flags on these.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MplsNextHop {
+    pub out_labels: Vec<u32>,
+    pub via: Option<IpAddr>,
+    pub if_id: Option<u32>,
+}
+
+// A multicast forwarding cache entry: packets from `source` to `group`
+// arriving on `iif` are copied to every interface in `oifs`. The interfaces
+// must already be multicast VIFs, which only the mroute socket of a multicast
+// routing daemon can add.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MulticastRoute {
+    // None for a (*,G) entry.
+    pub source: Option<IpAddr>,
+    pub group: IpAddr,
+    pub iif: u32,
+    pub oifs: Vec<MulticastOif>,
+    // Sent when installing, with the client's default protocol when unset,
+    // though the kernel reports every entry as `RouteProtocol::Mrouted`.
+    pub protocol: Option<RouteProtocol>,
+    // Reported by the kernel; ignored when installing routes.
+    pub stats: MulticastRouteStats,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MulticastOif {
+    pub if_id: u32,
+    // Packets are forwarded only when their TTL is larger than this.
+    pub ttl: u8,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MulticastRouteStats {
+    pub packets: u64,
+    pub bytes: u64,
+    // Packets that arrived on another interface than `iif`.
+    pub wrong_if: u64,
+}
+
+// Per-route TCP metrics; unset fields are left to the kernel defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteMetrics {
+    pub advmss: Option<u32>,
+    pub hoplimit: Option<u32>,
+    pub initcwnd: Option<u32>,
+    pub initrwnd: Option<u32>,
+    // Milliseconds.
+    pub rto_min: Option<u32>,
+    // Metrics locked against kernel updates, one `1 << RTAX_*` bit each, as
+    // reported by the kernel. When set, exactly these bits are installed;
+    // unset locks the MTU if the route has one.
+    pub lock: Option<u32>,
+}
+
+// Selects routes in a listing; unset fields match anything. The interface
+// and protocol are pushed into the dump request so the kernel skips other
+// routes, while the destination is matched in the worker.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteFilter {
+    // Matches the route's output interface or that of any of its nexthops.
+    pub if_id: Option<u32>,
+    pub protocol: Option<RouteProtocol>,
+    // Routes whose destination lies within this prefix.
+    pub destination: Option<crate::IpNet>,
+    // Lists only cached clones instead of FIB routes. Clones are left out
+    // otherwise, while unfiltered listings include both.
+    pub cloned: bool,
+}
+
+// Properties of the packet to look up, as with `ip route get ... from ...
+// iif ... mark ... tos ...`; unset fields are left out of the query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteLookupOptions {
+    // Looks the packet up as if it was received on this interface.
+    pub iif: Option<u32>,
+    pub mark: Option<u32>,
+    pub source: Option<IpAddr>,
+    pub tos: u8,
+    // Reports NotFound instead of a Local or Broadcast route, i.e. when the
+    // packet would be delivered to this host rather than forwarded.
+    pub skip_local: bool,
+    // Returns the FIB entry that matched, with its prefix and nexthops as
+    // installed, instead of the route resolved for this single destination,
+    // as `ip route get fibmatch` does.
+    pub fib_match: bool,
+}
+
+// A set of routes of both families, as listed from the kernel or as wanted
+// by a declarative configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteSet {
+    pub ipv4: Vec<Ipv4Route>,
+    pub ipv6: Vec<Ipv6Route>,
+}
+
+// Changes that turn one `RouteSet` into another. Deletions are listed with
+// the route as currently installed, so they can be sent as is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoutePlan {
+    pub add: RouteSet,
+    pub replace: RouteSet,
+    pub delete: RouteSet,
+}
+
+// Routing table names as configured for iproute2, so tables can be shown
+// and given by name. The kernel only knows the numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteTableNames {
+    names: HashMap<u32, String>,
+}
+
+pub(crate) const RT_TABLE_MAIN: u32 = 254;
+// Nexthop weights are sent as `weight - 1` in a single byte.
+const MAX_NEXTHOP_WEIGHT: u32 = u8::MAX as u32 + 1;
+const IPV4_DEFAULT_METRIC: u32 = 0;
+const IPV6_DEFAULT_METRIC: u32 = 1024;
+
+impl Ipv4Route {
+    pub fn normalized(&self) -> Self {
+        let mut route = self.clone();
+        route.route = route.route.trunc();
+        route.table = Some(route.table.unwrap_or(RT_TABLE_MAIN));
+        route.metric = Some(route.metric.unwrap_or(IPV4_DEFAULT_METRIC));
+        route.status = RouteStatus::default();
+        route.cache_info = None;
+        route.protocol = None;
+        route.extra.clear();
+        route.metrics.lock = Some(metrics_lock(route.mtu, &route.metrics));
+        if route.nexthop_id.is_some() {
+            route.if_id = None;
+            route.gateway = None;
+            route.nexthops.clear();
+        }
+        normalize_nexthops(
+            true,
+            &mut route.if_id,
+            &mut route.gateway,
+            &mut route.nexthops,
+        );
+        route
+    }
+
+    pub fn canonical_eq(&self, other: &Self) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl Ipv6Route {
+    pub fn normalized(&self) -> Self {
+        let mut route = self.clone();
+        route.route = route.route.trunc();
+        route.table = Some(route.table.unwrap_or(RT_TABLE_MAIN));
+        route.metric = Some(route.metric.unwrap_or(IPV6_DEFAULT_METRIC));
+        route.status = RouteStatus::default();
+        route.cache_info = None;
+        route.protocol = None;
+        route.extra.clear();
+        route.metrics.lock = Some(metrics_lock(route.mtu, &route.metrics));
+        // The remaining lifetime changes on every dump.
+        route.expires = None;
+        if route.nexthop_id.is_some() {
+            route.if_id = None;
+            route.gateway = None;
+            route.nexthops.clear();
+        }
+        normalize_nexthops(
+            false,
+            &mut route.if_id,
+            &mut route.gateway,
+            &mut route.nexthops,
+        );
+        route
+    }
+
+    pub fn canonical_eq(&self, other: &Self) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl IpRoute {
+    pub fn route(&self) -> crate::IpNet {
+        match self {
+            IpRoute::V4(route) => crate::IpNet::V4(route.route),
+            IpRoute::V6(route) => crate::IpNet::V6(route.route),
+        }
+    }
+
+    pub fn normalized(&self) -> Self {
+        match self {
+            IpRoute::V4(route) => IpRoute::V4(route.normalized()),
+            IpRoute::V6(route) => IpRoute::V6(route.normalized()),
+        }
+    }
+
+    pub fn canonical_eq(&self, other: &Self) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl From<Ipv4Route> for IpRoute {
+    fn from(route: Ipv4Route) -> Self {
+        IpRoute::V4(route)
+    }
+}
+
+impl From<Ipv6Route> for IpRoute {
+    fn from(route: Ipv6Route) -> Self {
+        IpRoute::V6(route)
+    }
+}
+
+impl RouteFilter {
+    pub fn if_id(if_id: u32) -> Self {
+        Self {
+            if_id: Some(if_id),
+            ..Self::default()
+        }
+    }
+
+    pub fn protocol(protocol: RouteProtocol) -> Self {
+        Self {
+            protocol: Some(protocol),
+            ..Self::default()
+        }
+    }
+
+    fn dump_message(&self, family: AddressFamily) -> RouteMessage {
+        let mut message = RouteMessage::default();
+        message.header.address_family = family;
+        if let Some(protocol) = self.protocol {
+            message.header.protocol = protocol;
+        }
+        if self.cloned {
+            message.header.flags |= RouteFlags::Cloned;
+        }
+        if let Some(if_id) = self.if_id {
+            message.attributes.push(RouteAttribute::Oif(if_id));
+        }
+        message
+    }
+
+    // The kernel ignores the dump filters when strict checking is not
+    // available, so every route is checked again here.
+    fn matches_message(&self, message: &RouteMessage) -> bool {
+        self.protocol
+            .is_none_or(|protocol| message.header.protocol == protocol)
+            && message.header.flags.contains(RouteFlags::Cloned) == self.cloned
+    }
+
+    fn matches_interface(&self, if_id: Option<u32>, nexthops: &[RouteNextHopInfo]) -> bool {
+        self.if_id.is_none_or(|wanted| {
+            if_id == Some(wanted) || nexthops.iter().any(|nh| nh.if_id == Some(wanted))
+        })
+    }
+
+    fn matches_ipv4(&self, route: &Ipv4Route) -> bool {
+        let destination_matches = match self.destination {
+            Some(crate::IpNet::V4(net)) => net.contains(&route.route),
+            Some(crate::IpNet::V6(_)) => false,
+            None => true,
+        };
+        destination_matches && self.matches_interface(route.if_id, &route.nexthops)
+    }
+
+    fn matches_ipv6(&self, route: &Ipv6Route) -> bool {
+        let destination_matches = match self.destination {
+            Some(crate::IpNet::V6(net)) => net.contains(&route.route),
+            Some(crate::IpNet::V4(_)) => false,
+            None => true,
+        };
+        destination_matches && self.matches_interface(route.if_id, &route.nexthops)
+    }
+}
+
+impl RouteSet {
+    pub fn is_empty(&self) -> bool {
+        self.ipv4.is_empty() && self.ipv6.is_empty()
+    }
+
+    // Routes are matched on prefix, table and metric after normalization. A
+    // matched route that is not `canonical_eq` to the desired one is
+    // replaced; other current routes sharing its key are deleted. Only the
+    // first desired route per key is used.
+    pub fn diff(current: &RouteSet, desired: &RouteSet) -> RoutePlan {
+        let mut plan = RoutePlan::default();
+        diff_routes(
+            &current.ipv4,
+            &desired.ipv4,
+            |route| (route.route, route.table, route.metric, route.tos),
+            Ipv4Route::normalized,
+            (
+                &mut plan.add.ipv4,
+                &mut plan.replace.ipv4,
+                &mut plan.delete.ipv4,
+            ),
+        );
+        diff_routes(
+            &current.ipv6,
+            &desired.ipv6,
+            |route| (route.route, route.table, route.metric),
+            Ipv6Route::normalized,
+            (
+                &mut plan.add.ipv6,
+                &mut plan.replace.ipv6,
+                &mut plan.delete.ipv6,
+            ),
+        );
+        plan
+    }
+}
+
+impl FromIterator<IpRoute> for RouteSet {
+    fn from_iter<I: IntoIterator<Item = IpRoute>>(iter: I) -> Self {
+        let mut set = RouteSet::default();
+        for route in iter {
+            match route {
+                IpRoute::V4(route) => set.ipv4.push(route),
+                IpRoute::V6(route) => set.ipv6.push(route),
+            }
+        }
+        set
+    }
+}
+
+impl RoutePlan {
+    pub fn is_empty(&self) -> bool {
+        self.add.is_empty() && self.replace.is_empty() && self.delete.is_empty()
+    }
+}
+
+type RoutePlanLists<'a, R> = (&'a mut Vec<R>, &'a mut Vec<R>, &'a mut Vec<R>);
+
+fn diff_routes<R, K>(
+    current: &[R],
+    desired: &[R],
+    key: impl Fn(&R) -> K,
+    normalized: impl Fn(&R) -> R,
+    (add, replace, delete): RoutePlanLists<'_, R>,
+) where
+    R: Clone + PartialEq,
+    K: Eq + Hash,
+{
+    let mut installed: HashMap<K, Vec<(R, &R)>> = HashMap::new();
+    for route in current {
+        let normal = normalized(route);
+        installed
+            .entry(key(&normal))
+            .or_default()
+            .push((normal, route));
+    }
+
+    let mut seen = HashSet::new();
+    for route in desired {
+        let normal = normalized(route);
+        if !seen.insert(key(&normal)) {
+            continue;
+        }
+        let Some(mut matches) = installed.remove(&key(&normal)) else {
+            add.push(route.clone());
+            continue;
+        };
+        match matches.iter().position(|(other, _)| *other == normal) {
+            Some(index) => {
+                matches.remove(index);
+            }
+            None => {
+                matches.remove(0);
+                replace.push(route.clone());
+            }
+        }
+        delete.extend(matches.into_iter().map(|(_, route)| route.clone()));
+    }
+
+    delete.extend(
+        current
+            .iter()
+            .filter(|route| installed.contains_key(&key(&normalized(route))))
+            .cloned(),
+    );
+}
+
+impl RouteNextHopInfo {
+    pub fn normalized(&self) -> Self {
+        let mut nexthop = self.clone();
+        nexthop.if_id = nexthop.if_id.filter(|id| *id != 0);
+        nexthop.weight = nexthop.weight.clamp(1, MAX_NEXTHOP_WEIGHT);
+        nexthop.status = RouteStatus::default();
+        nexthop
+    }
+}
+
+impl RouteGateway {
+    pub fn addr(&self) -> IpAddr {
+        match self {
+            RouteGateway::Gateway(addr) | RouteGateway::Via(addr) => *addr,
+        }
+    }
+
+    // A same-family `Via` is installed as RTA_GATEWAY and reported back as
+    // such.
+    fn normalized(self, ipv4_route: bool) -> Self {
+        let addr = self.addr();
+        if addr.is_ipv4() == ipv4_route {
+            RouteGateway::Gateway(addr)
+        } else {
+            RouteGateway::Via(addr)
+        }
+    }
+
+    fn to_attribute(self, ipv4_route: bool) -> RouteAttribute {
+        match self {
+            gw if gw.addr().is_ipv4() == ipv4_route => RouteAttribute::Gateway(gw.addr().into()),
+            RouteGateway::Gateway(IpAddr::V4(addr)) | RouteGateway::Via(IpAddr::V4(addr)) => {
+                RouteAttribute::Via(RouteVia::Inet(addr))
+            }
+            RouteGateway::Gateway(IpAddr::V6(addr)) | RouteGateway::Via(IpAddr::V6(addr)) => {
+                RouteAttribute::Via(RouteVia::Inet6(addr))
+            }
+        }
+    }
+
+    fn from_attribute(attr: &RouteAttribute) -> Option<Self> {
+        match attr {
+            RouteAttribute::Gateway(RouteAddress::Inet(addr)) => {
+                Some(RouteGateway::Gateway(IpAddr::V4(*addr)))
+            }
+            RouteAttribute::Gateway(RouteAddress::Inet6(addr)) => {
+                Some(RouteGateway::Gateway(IpAddr::V6(*addr)))
+            }
+            RouteAttribute::Via(RouteVia::Inet(addr)) => Some(RouteGateway::Via(IpAddr::V4(*addr))),
+            RouteAttribute::Via(RouteVia::Inet6(addr)) => {
+                Some(RouteGateway::Via(IpAddr::V6(*addr)))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl RouteCacheInfo {
+    fn from_message(info: &netlink_packet_route::route::RouteCacheInfo) -> Self {
+        Self {
+            age: clock_ticks(info.last_use),
+            expires: (info.expires != 0).then(|| clock_ticks(info.expires)),
+            used: info.used,
+            error: info.error,
+        }
+    }
+}
+
+impl RouteStatus {
+    // Single-path routes carry their nexthop's state in the route header.
+    fn from_route_flags(flags: RouteFlags) -> Self {
+        Self {
+            dead: flags.contains(RouteFlags::Dead),
+            linkdown: flags.contains(RouteFlags::Linkdown),
+            unresolved: flags.contains(RouteFlags::Unresolved),
+            offload: flags.intersects(RouteFlags::Offload | RouteFlags::RtOffload),
+            trap: flags.intersects(RouteFlags::Trap | RouteFlags::RtTrap),
+            offload_failed: flags.contains(RouteFlags::OffloadFailed),
+            cloned: flags.contains(RouteFlags::Cloned),
+        }
+    }
+
+    fn from_nexthop_flags(flags: RouteNextHopFlags) -> Self {
+        Self {
+            dead: flags.contains(RouteNextHopFlags::Dead),
+            linkdown: flags.contains(RouteNextHopFlags::Linkdown),
+            unresolved: flags.contains(RouteNextHopFlags::Unresolved),
+            offload: flags.contains(RouteNextHopFlags::Offload),
+            trap: flags.contains(RouteNextHopFlags::Trap),
+            offload_failed: false,
+            cloned: false,
+        }
+    }
+}
+
+const RT_TABLES_DIRS: [&str; 2] = ["/usr/share/iproute2", "/etc/iproute2"];
+
+impl Default for RouteTableNames {
+    fn default() -> Self {
+        let names = [
+            (0, "unspec"),
+            (253, "default"),
+            (254, "main"),
+            (255, "local"),
+        ];
+        Self {
+            names: names
+                .into_iter()
+                .map(|(id, name)| (id, name.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl RouteTableNames {
+    // Reads rt_tables and rt_tables.d/*.conf from /usr/share/iproute2 and
+    // then /etc/iproute2, later entries overriding earlier ones. Missing
+    // files are skipped; the reserved tables are always known.
+    pub fn load() -> io::Result<Self> {
+        let mut tables = Self::default();
+        for dir in RT_TABLES_DIRS {
+            let dir = std::path::Path::new(dir);
+            tables.load_file(&dir.join("rt_tables"))?;
+            let mut confs = match std::fs::read_dir(dir.join("rt_tables.d")) {
+                Ok(entries) => entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<io::Result<Vec<_>>>()?,
+                Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+                Err(err) => return Err(err),
+            };
+            confs.retain(|path| path.extension().is_some_and(|ext| ext == "conf"));
+            confs.sort();
+            for path in confs {
+                tables.load_file(&path)?;
+            }
+        }
+        Ok(tables)
+    }
+
+    fn load_file(&mut self, path: &std::path::Path) -> io::Result<()> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                self.parse(&contents);
+                Ok(())
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
+    // Adds `id name` lines in the rt_tables format; malformed lines are
+    // skipped as iproute2 does.
+    pub fn parse(&mut self, contents: &str) {
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut fields = line.split_whitespace();
+            let (Some(id), Some(name)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let id = match id.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex, 16),
+                None => id.parse(),
+            };
+            if let Ok(id) = id {
+                self.insert(id, name);
+            }
+        }
+    }
+
+    pub fn insert(&mut self, id: u32, name: &str) {
+        self.names.insert(id, name.to_string());
+    }
+
+    pub fn name(&self, id: u32) -> Option<&str> {
+        self.names.get(&id).map(String::as_str)
+    }
+
+    // Also accepts a table number, as `ip route ... table` does.
+    pub fn id(&self, name: &str) -> Option<u32> {
+        self.names
+            .iter()
+            .find(|(_, value)| *value == name)
+            .map(|(id, _)| *id)
+            .or_else(|| name.parse().ok())
+    }
+
+    // The table's name, or its number when it has none.
+    pub fn display(&self, id: u32) -> String {
+        self.name(id).map_or_else(|| id.to_string(), str::to_string)
+    }
+}
+
+// Only the identifying fields are hashed so the impls stay consistent with
+// `PartialEq`; hash `normalized()` values to key routes for reconciliation.
+impl Hash for Ipv4Route {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.route.hash(state);
+        self.table.hash(state);
+        self.metric.hash(state);
+        self.tos.hash(state);
+        self.if_id.hash(state);
+        self.gateway.hash(state);
+        self.nexthops.hash(state);
+        self.nexthop_id.hash(state);
+    }
+}
+
+impl Hash for Ipv6Route {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.route.hash(state);
+        self.table.hash(state);
+        self.metric.hash(state);
+        self.if_id.hash(state);
+        self.gateway.hash(state);
+        self.nexthops.hash(state);
+        self.nexthop_id.hash(state);
+    }
+}
+
+impl Hash for RouteNextHopInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.if_id.hash(state);
+        self.gateway.hash(state);
+        self.weight.hash(state);
+        self.flags.bits().hash(state);
+    }
+}
+
+fn normalize_nexthops(
+    ipv4_route: bool,
+    if_id: &mut Option<u32>,
+    gateway: &mut Option<RouteGateway>,
+    nexthops: &mut Vec<RouteNextHopInfo>,
+) {
+    *if_id = if_id.filter(|id| *id != 0);
+    if nexthops.len() == 1 && if_id.is_none() && gateway.is_none() {
+        let nexthop = nexthops.remove(0);
+        *if_id = nexthop.if_id.filter(|id| *id != 0);
+        *gateway = nexthop.gateway;
+    }
+    *gateway = gateway.map(|gw| gw.normalized(ipv4_route));
+    for nexthop in nexthops.iter_mut() {
+        *nexthop = nexthop.normalized();
+        nexthop.gateway = nexthop.gateway.map(|gw| gw.normalized(ipv4_route));
+    }
+    nexthops.sort_by_key(|nexthop| {
+        (
+            nexthop.if_id,
+            nexthop.gateway,
+            nexthop.weight,
+            nexthop.flags.bits(),
+        )
+    });
+}
+
+impl TryFrom<RouteMessage> for Ipv4Route {
+    type Error = io::Error;
+
+    fn try_from(message: RouteMessage) -> io::Result<Self> {
+        decode_ipv4_route(message)
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "Not a valid IPv4 route message"))
+    }
+}
+
+impl TryFrom<RouteMessage> for Ipv6Route {
+    type Error = io::Error;
+
+    fn try_from(message: RouteMessage) -> io::Result<Self> {
+        decode_ipv6_route(message)
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "Not a valid IPv6 route message"))
+    }
+}
+
+impl From<&Ipv4Route> for RouteMessage {
+    fn from(route: &Ipv4Route) -> Self {
+        build_ipv4_route_message(route)
+    }
+}
+
+impl From<Ipv4Route> for RouteMessage {
+    fn from(route: Ipv4Route) -> Self {
+        build_ipv4_route_message(&route)
+    }
+}
+
+impl From<&Ipv6Route> for RouteMessage {
+    fn from(route: &Ipv6Route) -> Self {
+        build_ipv6_route_message(route)
+    }
+}
+
+impl From<Ipv6Route> for RouteMessage {
+    fn from(route: Ipv6Route) -> Self {
+        build_ipv6_route_message(&route)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -51,16 +966,34 @@ pub struct RouteNextHopInfo {
 pub enum RtnlRouteRequest {
     Ipv4RouteList,
     Ipv6RouteList,
+    Ipv4RouteListFiltered(RouteFilter),
+    Ipv6RouteListFiltered(RouteFilter),
     Ipv4RouteAdd(Ipv4Route),
     Ipv4RouteReplace(Ipv4Route),
     Ipv6RouteAdd(Ipv6Route),
     Ipv6RouteReplace(Ipv6Route),
+    Ipv4RouteAppend(Ipv4Route),
+    Ipv6RouteAppend(Ipv6Route),
+    Ipv4RouteChange(Ipv4Route),
+    Ipv6RouteChange(Ipv6Route),
     Ipv4RouteDel(Ipv4Route),
     Ipv6RouteDel(Ipv6Route),
     Ipv4RouteGet(Ipv4Addr),
     Ipv6RouteGet(Ipv6Addr),
+    Ipv4RouteLookup(Ipv4Addr, RouteLookupOptions),
+    Ipv6RouteLookup(Ipv6Addr, RouteLookupOptions),
+    // Skips Local and Broadcast routes.
     Ipv4RouteGetByPrefix(crate::Ipv4Net),
     Ipv6RouteGetByPrefix(crate::Ipv6Net),
+    Ipv4RouteLookupByPrefix(crate::Ipv4Net, RouteLookupOptions),
+    Ipv6RouteLookupByPrefix(crate::Ipv6Net, RouteLookupOptions),
+    MplsRouteList,
+    MplsRouteAdd(MplsRoute),
+    MplsRouteReplace(MplsRoute),
+    MplsRouteDel(MplsRoute),
+    MulticastRouteList,
+    MulticastRouteAdd(MulticastRoute),
+    MulticastRouteDel(MulticastRoute),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -70,173 +1003,920 @@ pub enum RtnlRouteResponse {
     Failed,
     NotImplemented,
     NotFound,
+    AlreadyExists,
     Ipv4RouteList(Vec<Ipv4Route>),
     Ipv6RouteList(Vec<Ipv6Route>),
     Ipv4Route(Ipv4Route),
     Ipv6Route(Ipv6Route),
+    MplsRouteList(Vec<MplsRoute>),
+    MulticastRouteList(Vec<MulticastRoute>),
+    Error(RtnlError),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RtnlRouteClient {
     client: Client,
+    protocol: Option<u8>,
 }
 
 impl RtnlRouteClient {
     pub(crate) fn new(client: Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            protocol: None,
+        }
+    }
+
+    // Installs routes without a protocol of their own as `protocol`, so that
+    // `route_flush(protocol)` removes exactly what this client added.
+    pub fn with_protocol(mut self, protocol: RouteProtocol) -> Self {
+        self.protocol = Some(protocol.into());
+        self
+    }
+
+    pub fn protocol(&self) -> Option<RouteProtocol> {
+        self.protocol.map(RouteProtocol::from)
+    }
+
+    pub fn send_raw(&self, request: RtnlRouteRequest) -> io::Result<RtnlRouteResponse> {
+        self.client.send_request(request)
+    }
+
+    // Sent with NLM_F_EXCL: fails with AlreadyExists if a route with the same
+    // prefix, TOS and metric exists, whatever its nexthops.
+    pub fn ipv4_route_add(&self, mut route: Ipv4Route) -> io::Result<()> {
+        validate_nexthops(&route.nexthops)?;
+        validate_encap(route.encap.as_ref())?;
+        validate_tos(route.tos)?;
+        route.protocol = route.protocol.or(self.protocol());
+        let res = self
+            .client
+            .send_request(RtnlRouteRequest::Ipv4RouteAdd(route))?;
+        handle_route_status("IPv4 route add", res)
+    }
+
+    pub fn ipv4_route_replace(&self, mut route: Ipv4Route) -> io::Result<()> {
+        validate_nexthops(&route.nexthops)?;
+        validate_encap(route.encap.as_ref())?;
+        validate_tos(route.tos)?;
+        route.protocol = route.protocol.or(self.protocol());
+        let res = self
+            .client
+            .send_request(RtnlRouteRequest::Ipv4RouteReplace(route))?;
+        handle_route_status("IPv4 route replace", res)
+    }
+
+    // Adds another route next to existing ones with the same prefix and
+    // metric. IPv6 merges it into an ECMP route; IPv4 keeps it as a fallback
+    // behind the existing route.
+    pub fn ipv4_route_append(&self, mut route: Ipv4Route) -> io::Result<()> {
+        validate_nexthops(&route.nexthops)?;
+        validate_encap(route.encap.as_ref())?;
+        validate_tos(route.tos)?;
+        route.protocol = route.protocol.or(self.protocol());
+        let res = self
+            .client
+            .send_request(RtnlRouteRequest::Ipv4RouteAppend(route))?;
+        handle_route_status("IPv4 route append", res)
+    }
+
+    // Replaces an existing route without creating one; fails with NotFound
+    // if no route with the same prefix, TOS and metric exists.
+    pub fn ipv4_route_change(&self, mut route: Ipv4Route) -> io::Result<()> {
+        validate_nexthops(&route.nexthops)?;
+        validate_encap(route.encap.as_ref())?;
+        validate_tos(route.tos)?;
+        route.protocol = route.protocol.or(self.protocol());
+        let res = self
+            .client
+            .send_request(RtnlRouteRequest::Ipv4RouteChange(route))?;
+        handle_route_status("IPv4 route change", res)
+    }
+
+    pub fn ipv4_route_del(&self, route: Ipv4Route) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlRouteRequest::Ipv4RouteDel(route))?;
+        handle_route_status("IPv4 route delete", res)
+    }
+
+    pub fn ipv4_route_list(&self) -> io::Result<Vec<Ipv4Route>> {
+        match self.client.send_request(RtnlRouteRequest::Ipv4RouteList)? {
+            RtnlRouteResponse::Ipv4RouteList(routes) => Ok(routes),
+            RtnlRouteResponse::Error(err) => Err(err.into_io_error("IPv4 route list")),
+            other => Err(io::Error::other(format!(
+                "Unexpected response for IPv4 route list: {:?}",
+                other
+            ))),
+        }
+    }
+
+    pub fn ipv4_route_list_filtered(&self, filter: RouteFilter) -> io::Result<Vec<Ipv4Route>> {
+        if matches!(filter.destination, Some(crate::IpNet::V6(_))) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "IPv6 destination in IPv4 route filter",
+            ));
+        }
+        match self
+            .client
+            .send_request(RtnlRouteRequest::Ipv4RouteListFiltered(filter))?
+        {
+            RtnlRouteResponse::Ipv4RouteList(routes) => Ok(routes),
+            RtnlRouteResponse::Error(err) => Err(err.into_io_error("IPv4 route list")),
+            other => Err(io::Error::other(format!(
+                "Unexpected response for IPv4 route list: {:?}",
+                other
+            ))),
+        }
+    }
+
+    pub fn ipv4_route_get(&self, destination: Ipv4Addr) -> io::Result<Ipv4Route> {
+        self.ipv4_route_get_with_options(destination, RouteLookupOptions::default())
+    }
+
+    pub fn ipv4_route_get_with_options(
+        &self,
+        destination: Ipv4Addr,
+        options: RouteLookupOptions,
+    ) -> io::Result<Ipv4Route> {
+        if matches!(options.source, Some(IpAddr::V6(_))) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "IPv6 source in IPv4 route lookup",
+            ));
+        }
+        match self
+            .client
+            .send_request(RtnlRouteRequest::Ipv4RouteLookup(destination, options))?
+        {
+            RtnlRouteResponse::Ipv4Route(route) => Ok(route),
+            RtnlRouteResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Route not found"))
+            }
+            RtnlRouteResponse::Error(err) => Err(err.into_io_error("IPv4 route get")),
+            other => Err(io::Error::other(format!(
+                "Unexpected response for IPv4 route get: {:?}",
+                other
+            ))),
+        }
+    }
+
+    // Local and Broadcast routes are skipped, as they rarely are the prefix
+    // being asked for; use `ipv4_route_get_by_prefix_with_options` to
+    // include them.
+    pub fn ipv4_route_get_by_prefix(&self, prefix: crate::Ipv4Net) -> io::Result<Ipv4Route> {
+        let options = RouteLookupOptions {
+            skip_local: true,
+            ..RouteLookupOptions::default()
+        };
+        self.ipv4_route_get_by_prefix_with_options(prefix, options)
+    }
+
+    pub fn ipv4_route_get_by_prefix_with_options(
+        &self,
+        prefix: crate::Ipv4Net,
+        options: RouteLookupOptions,
+    ) -> io::Result<Ipv4Route> {
+        if matches!(options.source, Some(IpAddr::V6(_))) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "IPv6 source in IPv4 route lookup",
+            ));
+        }
+        match self
+            .client
+            .send_request(RtnlRouteRequest::Ipv4RouteLookupByPrefix(prefix, options))?
+        {
+            RtnlRouteResponse::Ipv4Route(route) => Ok(route),
+            RtnlRouteResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Route not found"))
+            }
+            RtnlRouteResponse::Error(err) => Err(err.into_io_error("IPv4 route get by prefix")),
+            other => Err(io::Error::other(format!(
+                "Unexpected response for IPv4 route get by prefix: {:?}",
+                other
+            ))),
+        }
+    }
+
+    // Every route whose prefix covers `destination`, in any table and of any
+    // type, most specific first. Unlike `ipv4_route_get`, this reads the
+    // tables instead of asking the kernel to resolve the lookup.
+    pub fn ipv4_route_get_all(&self, destination: Ipv4Addr) -> io::Result<Vec<Ipv4Route>> {
+        let mut routes: Vec<Ipv4Route> = self
+            .ipv4_route_list()?
+            .into_iter()
+            .filter(|route| route.route.contains(&destination))
+            .collect();
+        routes.sort_by_key(|route| Reverse(route.route.prefix_len()));
+        Ok(routes)
+    }
+
+    // Sent with NLM_F_EXCL: fails with AlreadyExists if a route with the same
+    // prefix, TOS and metric exists, whatever its nexthops.
+    pub fn ipv6_route_add(&self, mut route: Ipv6Route) -> io::Result<()> {
+        validate_nexthops(&route.nexthops)?;
+        validate_encap(route.encap.as_ref())?;
+        validate_ipv6_gateways(&route)?;
+        route.protocol = route.protocol.or(self.protocol());
+        let res = self
+            .client
+            .send_request(RtnlRouteRequest::Ipv6RouteAdd(route))?;
+        handle_route_status("IPv6 route add", res)
+    }
+
+    pub fn ipv6_route_replace(&self, mut route: Ipv6Route) -> io::Result<()> {
+        validate_nexthops(&route.nexthops)?;
+        validate_encap(route.encap.as_ref())?;
+        validate_ipv6_gateways(&route)?;
+        route.protocol = route.protocol.or(self.protocol());
+        let res = self
+            .client
+            .send_request(RtnlRouteRequest::Ipv6RouteReplace(route))?;
+        handle_route_status("IPv6 route replace", res)
+    }
+
+    // Adds another route next to existing ones with the same prefix and
+    // metric. IPv6 merges it into an ECMP route; IPv4 keeps it as a fallback
+    // behind the existing route.
+    pub fn ipv6_route_append(&self, mut route: Ipv6Route) -> io::Result<()> {
+        validate_nexthops(&route.nexthops)?;
+        validate_encap(route.encap.as_ref())?;
+        validate_ipv6_gateways(&route)?;
+        route.protocol = route.protocol.or(self.protocol());
+        let res = self
+            .client
+            .send_request(RtnlRouteRequest::Ipv6RouteAppend(route))?;
+        handle_route_status("IPv6 route append", res)
+    }
+
+    // Replaces an existing route without creating one; fails with NotFound
+    // if no route with the same prefix, TOS and metric exists.
+    pub fn ipv6_route_change(&self, mut route: Ipv6Route) -> io::Result<()> {
+        validate_nexthops(&route.nexthops)?;
+        validate_encap(route.encap.as_ref())?;
+        validate_ipv6_gateways(&route)?;
+        route.protocol = route.protocol.or(self.protocol());
+        let res = self
+            .client
+            .send_request(RtnlRouteRequest::Ipv6RouteChange(route))?;
+        handle_route_status("IPv6 route change", res)
+    }
+
+    pub fn ipv6_route_del(&self, route: Ipv6Route) -> io::Result<()> {
+        let res = self
+            .client
+            .send_request(RtnlRouteRequest::Ipv6RouteDel(route))?;
+        handle_route_status("IPv6 route delete", res)
+    }
+
+    // Includes cached clones (`status.cloned`); filtered listings leave them
+    // out.
+    pub fn ipv6_route_list(&self) -> io::Result<Vec<Ipv6Route>> {
+        match self.client.send_request(RtnlRouteRequest::Ipv6RouteList)? {
+            RtnlRouteResponse::Ipv6RouteList(routes) => Ok(routes),
+            RtnlRouteResponse::Error(err) => Err(err.into_io_error("IPv6 route list")),
+            other => Err(io::Error::other(format!(
+                "Unexpected response for IPv6 route list: {:?}",
+                other
+            ))),
+        }
+    }
+
+    pub fn ipv6_route_list_filtered(&self, filter: RouteFilter) -> io::Result<Vec<Ipv6Route>> {
+        if matches!(filter.destination, Some(crate::IpNet::V4(_))) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "IPv4 destination in IPv6 route filter",
+            ));
+        }
+        match self
+            .client
+            .send_request(RtnlRouteRequest::Ipv6RouteListFiltered(filter))?
+        {
+            RtnlRouteResponse::Ipv6RouteList(routes) => Ok(routes),
+            RtnlRouteResponse::Error(err) => Err(err.into_io_error("IPv6 route list")),
+            other => Err(io::Error::other(format!(
+                "Unexpected response for IPv6 route list: {:?}",
+                other
+            ))),
+        }
+    }
+
+    pub fn ipv6_route_get(&self, destination: Ipv6Addr) -> io::Result<Ipv6Route> {
+        self.ipv6_route_get_with_options(destination, RouteLookupOptions::default())
+    }
+
+    pub fn ipv6_route_get_with_options(
+        &self,
+        destination: Ipv6Addr,
+        options: RouteLookupOptions,
+    ) -> io::Result<Ipv6Route> {
+        if matches!(options.source, Some(IpAddr::V4(_))) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "IPv4 source in IPv6 route lookup",
+            ));
+        }
+        match self
+            .client
+            .send_request(RtnlRouteRequest::Ipv6RouteLookup(destination, options))?
+        {
+            RtnlRouteResponse::Ipv6Route(route) => Ok(route),
+            RtnlRouteResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Route not found"))
+            }
+            RtnlRouteResponse::Error(err) => Err(err.into_io_error("IPv6 route get")),
+            other => Err(io::Error::other(format!(
+                "Unexpected response for IPv6 route get: {:?}",
+                other
+            ))),
+        }
+    }
+
+    // Local and Broadcast routes are skipped, as they rarely are the prefix
+    // being asked for; use `ipv6_route_get_by_prefix_with_options` to
+    // include them.
+    pub fn ipv6_route_get_by_prefix(&self, prefix: crate::Ipv6Net) -> io::Result<Ipv6Route> {
+        let options = RouteLookupOptions {
+            skip_local: true,
+            ..RouteLookupOptions::default()
+        };
+        self.ipv6_route_get_by_prefix_with_options(prefix, options)
+    }
+
+    pub fn ipv6_route_get_by_prefix_with_options(
+        &self,
+        prefix: crate::Ipv6Net,
+        options: RouteLookupOptions,
+    ) -> io::Result<Ipv6Route> {
+        if matches!(options.source, Some(IpAddr::V4(_))) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "IPv4 source in IPv6 route lookup",
+            ));
+        }
+        match self
+            .client
+            .send_request(RtnlRouteRequest::Ipv6RouteLookupByPrefix(prefix, options))?
+        {
+            RtnlRouteResponse::Ipv6Route(route) => Ok(route),
+            RtnlRouteResponse::NotFound => {
+                Err(io::Error::new(ErrorKind::NotFound, "Route not found"))
+            }
+            RtnlRouteResponse::Error(err) => Err(err.into_io_error("IPv6 route get by prefix")),
+            other => Err(io::Error::other(format!(
+                "Unexpected response for IPv6 route get by prefix: {:?}",
+                other
+            ))),
+        }
+    }
+
+    // Every route whose prefix covers `destination`, in any table and of any
+    // type, most specific first. Unlike `ipv6_route_get`, this reads the
+    // tables instead of asking the kernel to resolve the lookup.
+    pub fn ipv6_route_get_all(&self, destination: Ipv6Addr) -> io::Result<Vec<Ipv6Route>> {
+        let mut routes: Vec<Ipv6Route> = self
+            .ipv6_route_list()?
+            .into_iter()
+            .filter(|route| route.route.contains(&destination))
+            .collect();
+        routes.sort_by_key(|route| Reverse(route.route.prefix_len()));
+        Ok(routes)
+    }
+
+    pub fn route_add(&self, route: IpRoute) -> io::Result<()> {
+        match route {
+            IpRoute::V4(route) => self.ipv4_route_add(route),
+            IpRoute::V6(route) => self.ipv6_route_add(route),
+        }
+    }
+
+    pub fn route_replace(&self, route: IpRoute) -> io::Result<()> {
+        match route {
+            IpRoute::V4(route) => self.ipv4_route_replace(route),
+            IpRoute::V6(route) => self.ipv6_route_replace(route),
+        }
+    }
+
+    pub fn route_append(&self, route: IpRoute) -> io::Result<()> {
+        match route {
+            IpRoute::V4(route) => self.ipv4_route_append(route),
+            IpRoute::V6(route) => self.ipv6_route_append(route),
+        }
+    }
+
+    pub fn route_change(&self, route: IpRoute) -> io::Result<()> {
+        match route {
+            IpRoute::V4(route) => self.ipv4_route_change(route),
+            IpRoute::V6(route) => self.ipv6_route_change(route),
+        }
+    }
+
+    pub fn route_del(&self, route: IpRoute) -> io::Result<()> {
+        match route {
+            IpRoute::V4(route) => self.ipv4_route_del(route),
+            IpRoute::V6(route) => self.ipv6_route_del(route),
+        }
+    }
+
+    // Deletes every IPv4 and IPv6 route installed with `protocol`, e.g. the
+    // leftovers of a previous run. Returns how many routes were removed.
+    pub fn route_flush(&self, protocol: RouteProtocol) -> io::Result<usize> {
+        let mut count = 0;
+        for route in self.route_list_filtered(RouteFilter::protocol(protocol))? {
+            match self.route_del(route) {
+                Ok(()) => count += 1,
+                // Already gone with an earlier route, or removed concurrently.
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(count)
+    }
+
+    // IPv4 routes first, then IPv6.
+    pub fn route_list(&self) -> io::Result<Vec<IpRoute>> {
+        let mut routes: Vec<IpRoute> = self
+            .ipv4_route_list()?
+            .into_iter()
+            .map(IpRoute::V4)
+            .collect();
+        routes.extend(self.ipv6_route_list()?.into_iter().map(IpRoute::V6));
+        Ok(routes)
     }
 
-    pub fn ipv4_route_add(&self, route: Ipv4Route) -> io::Result<()> {
-        let res = self
-            .client
-            .send_request(RtnlRouteRequest::Ipv4RouteAdd(route))?;
-        handle_route_status("IPv4 route add", res)
+    // Only dumps the family of `filter.destination` when it is set.
+    pub fn route_list_filtered(&self, filter: RouteFilter) -> io::Result<Vec<IpRoute>> {
+        let mut routes = Vec::new();
+        if !matches!(filter.destination, Some(crate::IpNet::V6(_))) {
+            let list = self.ipv4_route_list_filtered(filter.clone())?;
+            routes.extend(list.into_iter().map(IpRoute::V4));
+        }
+        if !matches!(filter.destination, Some(crate::IpNet::V4(_))) {
+            let list = self.ipv6_route_list_filtered(filter)?;
+            routes.extend(list.into_iter().map(IpRoute::V6));
+        }
+        Ok(routes)
     }
 
-    pub fn ipv4_route_replace(&self, route: Ipv4Route) -> io::Result<()> {
-        let res = self
-            .client
-            .send_request(RtnlRouteRequest::Ipv4RouteReplace(route))?;
-        handle_route_status("IPv4 route replace", res)
+    pub fn route_get(&self, destination: IpAddr) -> io::Result<IpRoute> {
+        self.route_get_with_options(destination, RouteLookupOptions::default())
     }
 
-    pub fn ipv4_route_del(&self, route: Ipv4Route) -> io::Result<()> {
-        let res = self
-            .client
-            .send_request(RtnlRouteRequest::Ipv4RouteDel(route))?;
-        handle_route_status("IPv4 route delete", res)
+    pub fn route_get_with_options(
+        &self,
+        destination: IpAddr,
+        options: RouteLookupOptions,
+    ) -> io::Result<IpRoute> {
+        match destination {
+            IpAddr::V4(addr) => self
+                .ipv4_route_get_with_options(addr, options)
+                .map(IpRoute::V4),
+            IpAddr::V6(addr) => self
+                .ipv6_route_get_with_options(addr, options)
+                .map(IpRoute::V6),
+        }
     }
 
-    pub fn ipv4_route_list(&self) -> io::Result<Vec<Ipv4Route>> {
-        match self.client.send_request(RtnlRouteRequest::Ipv4RouteList)? {
-            RtnlRouteResponse::Ipv4RouteList(routes) => Ok(routes),
-            other => Err(io::Error::other(format!(
-                "Unexpected response for IPv4 route list: {:?}",
-                other
-            ))),
+    pub fn route_get_by_prefix(&self, prefix: crate::IpNet) -> io::Result<IpRoute> {
+        match prefix {
+            crate::IpNet::V4(prefix) => self.ipv4_route_get_by_prefix(prefix).map(IpRoute::V4),
+            crate::IpNet::V6(prefix) => self.ipv6_route_get_by_prefix(prefix).map(IpRoute::V6),
         }
     }
 
-    pub fn ipv4_route_get(&self, destination: Ipv4Addr) -> io::Result<Ipv4Route> {
-        match self
-            .client
-            .send_request(RtnlRouteRequest::Ipv4RouteGet(destination))?
-        {
-            RtnlRouteResponse::Ipv4Route(route) => Ok(route),
-            RtnlRouteResponse::NotFound => {
-                Err(io::Error::new(ErrorKind::NotFound, "Route not found"))
-            }
-            other => Err(io::Error::other(format!(
-                "Unexpected response for IPv4 route get: {:?}",
-                other
-            ))),
+    pub fn route_get_by_prefix_with_options(
+        &self,
+        prefix: crate::IpNet,
+        options: RouteLookupOptions,
+    ) -> io::Result<IpRoute> {
+        match prefix {
+            crate::IpNet::V4(prefix) => self
+                .ipv4_route_get_by_prefix_with_options(prefix, options)
+                .map(IpRoute::V4),
+            crate::IpNet::V6(prefix) => self
+                .ipv6_route_get_by_prefix_with_options(prefix, options)
+                .map(IpRoute::V6),
         }
     }
 
-    pub fn ipv4_route_get_by_prefix(&self, prefix: crate::Ipv4Net) -> io::Result<Ipv4Route> {
-        match self
-            .client
-            .send_request(RtnlRouteRequest::Ipv4RouteGetByPrefix(prefix))?
-        {
-            RtnlRouteResponse::Ipv4Route(route) => Ok(route),
-            RtnlRouteResponse::NotFound => {
-                Err(io::Error::new(ErrorKind::NotFound, "Route not found"))
-            }
+    pub fn route_get_all(&self, destination: IpAddr) -> io::Result<Vec<IpRoute>> {
+        Ok(match destination {
+            IpAddr::V4(addr) => self
+                .ipv4_route_get_all(addr)?
+                .into_iter()
+                .map(IpRoute::V4)
+                .collect(),
+            IpAddr::V6(addr) => self
+                .ipv6_route_get_all(addr)?
+                .into_iter()
+                .map(IpRoute::V6)
+                .collect(),
+        })
+    }
+
+    pub fn mpls_route_list(&self) -> io::Result<Vec<MplsRoute>> {
+        match self.client.send_request(RtnlRouteRequest::MplsRouteList)? {
+            RtnlRouteResponse::MplsRouteList(routes) => Ok(routes),
+            RtnlRouteResponse::Error(err) => Err(err.into_io_error("MPLS route list")),
             other => Err(io::Error::other(format!(
-                "Unexpected response for IPv4 route get by prefix: {:?}",
+                "Unexpected response for MPLS route list: {:?}",
                 other
             ))),
         }
     }
 
-    pub fn ipv6_route_add(&self, route: Ipv6Route) -> io::Result<()> {
+    pub fn mpls_route_add(&self, mut route: MplsRoute) -> io::Result<()> {
+        validate_mpls_route(&route)?;
+        route.protocol = route.protocol.or(self.protocol());
         let res = self
             .client
-            .send_request(RtnlRouteRequest::Ipv6RouteAdd(route))?;
-        handle_route_status("IPv6 route add", res)
+            .send_request(RtnlRouteRequest::MplsRouteAdd(route))?;
+        handle_route_status("MPLS route add", res)
     }
 
-    pub fn ipv6_route_replace(&self, route: Ipv6Route) -> io::Result<()> {
+    pub fn mpls_route_replace(&self, mut route: MplsRoute) -> io::Result<()> {
+        validate_mpls_route(&route)?;
+        route.protocol = route.protocol.or(self.protocol());
         let res = self
             .client
-            .send_request(RtnlRouteRequest::Ipv6RouteReplace(route))?;
-        handle_route_status("IPv6 route replace", res)
+            .send_request(RtnlRouteRequest::MplsRouteReplace(route))?;
+        handle_route_status("MPLS route replace", res)
     }
 
-    pub fn ipv6_route_del(&self, route: Ipv6Route) -> io::Result<()> {
+    pub fn mpls_route_del(&self, route: MplsRoute) -> io::Result<()> {
+        validate_mpls_route(&route)?;
         let res = self
             .client
-            .send_request(RtnlRouteRequest::Ipv6RouteDel(route))?;
-        handle_route_status("IPv6 route delete", res)
-    }
-
-    pub fn ipv6_route_list(&self) -> io::Result<Vec<Ipv6Route>> {
-        match self.client.send_request(RtnlRouteRequest::Ipv6RouteList)? {
-            RtnlRouteResponse::Ipv6RouteList(routes) => Ok(routes),
-            other => Err(io::Error::other(format!(
-                "Unexpected response for IPv6 route list: {:?}",
-                other
-            ))),
-        }
+            .send_request(RtnlRouteRequest::MplsRouteDel(route))?;
+        handle_route_status("MPLS route delete", res)
     }
 
-    pub fn ipv6_route_get(&self, destination: Ipv6Addr) -> io::Result<Ipv6Route> {
+    // IPv4 and IPv6 multicast forwarding entries of all tables. Unresolved
+    // entries still waiting for the routing daemon are left out.
+    pub fn multicast_route_list(&self) -> io::Result<Vec<MulticastRoute>> {
         match self
             .client
-            .send_request(RtnlRouteRequest::Ipv6RouteGet(destination))?
+            .send_request(RtnlRouteRequest::MulticastRouteList)?
         {
-            RtnlRouteResponse::Ipv6Route(route) => Ok(route),
-            RtnlRouteResponse::NotFound => {
-                Err(io::Error::new(ErrorKind::NotFound, "Route not found"))
-            }
+            RtnlRouteResponse::MulticastRouteList(routes) => Ok(routes),
+            RtnlRouteResponse::Error(err) => Err(err.into_io_error("multicast route list")),
             other => Err(io::Error::other(format!(
-                "Unexpected response for IPv6 route get: {:?}",
+                "Unexpected response for multicast route list: {:?}",
                 other
             ))),
         }
     }
 
-    pub fn ipv6_route_get_by_prefix(&self, prefix: crate::Ipv6Net) -> io::Result<Ipv6Route> {
-        match self
+    // Installs into the default multicast table, replacing the outgoing
+    // interfaces of an existing entry with the same source, group and `iif`.
+    // The kernel only accepts IPv4 entries over netlink.
+    pub fn multicast_route_add(&self, mut route: MulticastRoute) -> io::Result<()> {
+        validate_multicast_route(&route)?;
+        route.protocol = route.protocol.or(self.protocol());
+        let res = self
             .client
-            .send_request(RtnlRouteRequest::Ipv6RouteGetByPrefix(prefix))?
-        {
-            RtnlRouteResponse::Ipv6Route(route) => Ok(route),
-            RtnlRouteResponse::NotFound => {
-                Err(io::Error::new(ErrorKind::NotFound, "Route not found"))
-            }
-            other => Err(io::Error::other(format!(
-                "Unexpected response for IPv6 route get by prefix: {:?}",
-                other
-            ))),
-        }
+            .send_request(RtnlRouteRequest::MulticastRouteAdd(route))?;
+        handle_route_status("multicast route add", res)
+    }
+
+    pub fn multicast_route_del(&self, route: MulticastRoute) -> io::Result<()> {
+        validate_multicast_route(&route)?;
+        let res = self
+            .client
+            .send_request(RtnlRouteRequest::MulticastRouteDel(route))?;
+        handle_route_status("multicast route delete", res)
     }
 }
 
-pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::RouteHandle) {
+// `dump_handle` is on a socket with strict checking enabled so the kernel
+// applies the filters of a dump request.
+pub(crate) async fn run_server(
+    mut server: Server,
+    mut netlink: rtnetlink::Handle,
+    dump_handle: rtnetlink::RouteHandle,
+) {
+    let handle = netlink.route();
+    let mut links = netlink.link();
     while let Some((req, respond)) = server.accept().await {
+        let req = match resolve_request_names(&mut links, req).await {
+            Ok(req) => req,
+            Err(response) => {
+                respond(response);
+                continue;
+            }
+        };
         let response = match req {
             RtnlRouteRequest::Ipv4RouteList => list_routes_v4(&handle).await,
             RtnlRouteRequest::Ipv6RouteList => list_routes_v6(&handle).await,
+            RtnlRouteRequest::Ipv4RouteListFiltered(filter) => {
+                list_routes_v4_filtered(&dump_handle, filter).await
+            }
+            RtnlRouteRequest::Ipv6RouteListFiltered(filter) => {
+                list_routes_v6_filtered(&dump_handle, filter).await
+            }
             RtnlRouteRequest::Ipv4RouteAdd(route) => add_route_v4(&handle, route, false).await,
             RtnlRouteRequest::Ipv4RouteReplace(route) => add_route_v4(&handle, route, true).await,
             RtnlRouteRequest::Ipv6RouteAdd(route) => add_route_v6(&handle, route, false).await,
             RtnlRouteRequest::Ipv6RouteReplace(route) => add_route_v6(&handle, route, true).await,
+            RtnlRouteRequest::Ipv4RouteAppend(route) => {
+                let message = build_ipv4_route_message(&route);
+                map_route_result(
+                    send_new_route(&mut netlink, message, NLM_F_CREATE | NLM_F_APPEND).await,
+                    "append IPv4 route",
+                )
+            }
+            RtnlRouteRequest::Ipv6RouteAppend(route) => {
+                let message = build_ipv6_route_message(&route);
+                map_route_result(
+                    send_new_route(&mut netlink, message, NLM_F_CREATE | NLM_F_APPEND).await,
+                    "append IPv6 route",
+                )
+            }
+            RtnlRouteRequest::Ipv4RouteChange(route) => {
+                let message = build_ipv4_route_message(&route);
+                map_route_result(
+                    send_new_route(&mut netlink, message, NLM_F_REPLACE).await,
+                    "change IPv4 route",
+                )
+            }
+            RtnlRouteRequest::Ipv6RouteChange(route) => {
+                let message = build_ipv6_route_message(&route);
+                map_route_result(
+                    send_new_route(&mut netlink, message, NLM_F_REPLACE).await,
+                    "change IPv6 route",
+                )
+            }
             RtnlRouteRequest::Ipv4RouteDel(route) => delete_route_v4(&handle, route).await,
             RtnlRouteRequest::Ipv6RouteDel(route) => delete_route_v6(&handle, route).await,
-            RtnlRouteRequest::Ipv4RouteGet(destination) => get_route_v4(&handle, destination).await,
-            RtnlRouteRequest::Ipv6RouteGet(destination) => get_route_v6(&handle, destination).await,
+            RtnlRouteRequest::Ipv4RouteGet(destination) => {
+                get_route_v4(&handle, destination, RouteLookupOptions::default()).await
+            }
+            RtnlRouteRequest::Ipv4RouteLookup(destination, options) => {
+                get_route_v4(&handle, destination, options).await
+            }
+            RtnlRouteRequest::Ipv6RouteGet(destination) => {
+                get_route_v6(&handle, destination, RouteLookupOptions::default()).await
+            }
+            RtnlRouteRequest::Ipv6RouteLookup(destination, options) => {
+                get_route_v6(&handle, destination, options).await
+            }
             RtnlRouteRequest::Ipv4RouteGetByPrefix(prefix) => {
-                get_route_v4_by_prefix(&handle, prefix).await
+                let options = RouteLookupOptions {
+                    skip_local: true,
+                    ..RouteLookupOptions::default()
+                };
+                get_route_v4_by_prefix(&handle, prefix, options).await
             }
             RtnlRouteRequest::Ipv6RouteGetByPrefix(prefix) => {
-                get_route_v6_by_prefix(&handle, prefix).await
+                let options = RouteLookupOptions {
+                    skip_local: true,
+                    ..RouteLookupOptions::default()
+                };
+                get_route_v6_by_prefix(&handle, prefix, options).await
+            }
+            RtnlRouteRequest::Ipv4RouteLookupByPrefix(prefix, options) => {
+                get_route_v4_by_prefix(&handle, prefix, options).await
+            }
+            RtnlRouteRequest::Ipv6RouteLookupByPrefix(prefix, options) => {
+                get_route_v6_by_prefix(&handle, prefix, options).await
+            }
+            RtnlRouteRequest::MplsRouteList => list_routes_mpls(&handle).await,
+            RtnlRouteRequest::MplsRouteAdd(route) => {
+                let message = build_mpls_route_message(&route);
+                map_route_result(handle.add(message).execute().await, "add MPLS route")
+            }
+            RtnlRouteRequest::MplsRouteReplace(route) => {
+                let message = build_mpls_route_message(&route);
+                map_route_result(
+                    handle.add(message).replace().execute().await,
+                    "replace MPLS route",
+                )
+            }
+            RtnlRouteRequest::MplsRouteDel(route) => {
+                let message = build_mpls_route_message(&route);
+                map_route_result(handle.del(message).execute().await, "delete MPLS route")
+            }
+            RtnlRouteRequest::MulticastRouteList => list_routes_multicast(&handle).await,
+            RtnlRouteRequest::MulticastRouteAdd(route) => {
+                add_route_multicast(&handle, &mut links, route).await
+            }
+            RtnlRouteRequest::MulticastRouteDel(route) => {
+                let message = build_multicast_route_message(&route);
+                map_route_result(
+                    handle.del(message).execute().await,
+                    "delete multicast route",
+                )
             }
         };
         respond(response);
     }
 }
 
+async fn resolve_request_names(
+    links: &mut rtnetlink::LinkHandle,
+    mut req: RtnlRouteRequest,
+) -> Result<RtnlRouteRequest, RtnlRouteResponse> {
+    let names = match &mut req {
+        RtnlRouteRequest::Ipv4RouteAdd(route)
+        | RtnlRouteRequest::Ipv4RouteReplace(route)
+        | RtnlRouteRequest::Ipv4RouteAppend(route)
+        | RtnlRouteRequest::Ipv4RouteChange(route)
+        | RtnlRouteRequest::Ipv4RouteDel(route) => {
+            Some((&mut route.if_id, &route.if_name, &mut route.nexthops))
+        }
+        RtnlRouteRequest::Ipv6RouteAdd(route)
+        | RtnlRouteRequest::Ipv6RouteReplace(route)
+        | RtnlRouteRequest::Ipv6RouteAppend(route)
+        | RtnlRouteRequest::Ipv6RouteChange(route)
+        | RtnlRouteRequest::Ipv6RouteDel(route) => {
+            Some((&mut route.if_id, &route.if_name, &mut route.nexthops))
+        }
+        _ => None,
+    };
+    let Some((if_id, if_name, nexthops)) = names else {
+        return Ok(req);
+    };
+    if if_id.is_none()
+        && let Some(name) = if_name
+    {
+        *if_id = Some(resolve_if_name(links, name).await?);
+    }
+    for nexthop in nexthops.iter_mut() {
+        if nexthop.if_id.is_none()
+            && let Some(name) = &nexthop.if_name
+        {
+            nexthop.if_id = Some(resolve_if_name(links, name).await?);
+        }
+    }
+    Ok(req)
+}
+
+async fn resolve_if_name(
+    links: &mut rtnetlink::LinkHandle,
+    if_name: &str,
+) -> Result<u32, RtnlRouteResponse> {
+    let response = links.get().match_name(if_name.to_string()).execute();
+    futures::pin_mut!(response);
+    match response.try_next().await {
+        Ok(Some(msg)) => Ok(msg.header.index),
+        Ok(None) => Err(RtnlRouteResponse::NotFound),
+        Err(rtnetlink::Error::NetlinkError(err_msg))
+            if err_msg.to_io().kind() == ErrorKind::NotFound
+                || err_msg.raw_code().abs() == ENODEV =>
+        {
+            Err(RtnlRouteResponse::NotFound)
+        }
+        Err(err) => Err(RtnlRouteResponse::Error(RtnlError::from_rtnetlink(&err))),
+    }
+}
+
+const MPLS_LABEL_MAX: u32 = (1 << 20) - 1;
+const RTNL_FAMILY_IPMR: u8 = 128;
+const RTNL_FAMILY_IP6MR: u8 = 129;
+const MAXVIFS: usize = 32;
+const IP_MR_VIF_PATH: &str = "/proc/net/ip_mr_vif";
+// Bounded by the 8-bit SRH length field, in units of 8 octets.
+const SEG6_MAX_SEGMENTS: usize = 127;
+
+fn validate_mpls_labels(labels: &[u32]) -> io::Result<()> {
+    match labels.iter().find(|label| **label > MPLS_LABEL_MAX) {
+        Some(label) => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("MPLS label {} is larger than {}", label, MPLS_LABEL_MAX),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn validate_mpls_route(route: &MplsRoute) -> io::Result<()> {
+    validate_mpls_labels(&[route.label])?;
+    validate_mpls_labels(&route.out_labels)?;
+    for nexthop in &route.nexthops {
+        validate_mpls_labels(&nexthop.out_labels)?;
+    }
+    Ok(())
+}
+
+// The kernel rejects the ECN bits in a route's TOS.
+fn validate_tos(tos: u8) -> io::Result<()> {
+    if tos & 0x03 != 0 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("TOS 0x{:02x} has ECN bits set", tos),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_multicast_route(route: &MulticastRoute) -> io::Result<()> {
+    let invalid = |message: String| Err(io::Error::new(ErrorKind::InvalidInput, message));
+    if route.group.is_ipv6() {
+        return Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "IPv6 multicast routes cannot be installed over netlink",
+        ));
+    }
+    if !route.group.is_multicast() {
+        return invalid(format!("{} is not a multicast group", route.group));
+    }
+    if let Some(source) = route.source
+        && source.is_ipv4() != route.group.is_ipv4()
+    {
+        return invalid(format!("source {} does not match group family", source));
+    }
+    if route.iif == 0 {
+        return invalid("multicast route needs an input interface".to_string());
+    }
+    if route.oifs.len() > MAXVIFS {
+        return invalid(format!(
+            "at most {} output interfaces are supported",
+            MAXVIFS
+        ));
+    }
+    // A threshold of 0 is how the kernel leaves a VIF out, and no packet
+    // passes 255.
+    match route
+        .oifs
+        .iter()
+        .find(|oif| oif.ttl == 0 || oif.ttl == u8::MAX)
+    {
+        Some(oif) => invalid(format!(
+            "TTL threshold {} of interface {} is out of range",
+            oif.ttl, oif.if_id
+        )),
+        None => Ok(()),
+    }
+}
+
+fn validate_encap(encap: Option<&RouteEncap>) -> io::Result<()> {
+    match encap {
+        Some(RouteEncap::Mpls { labels, .. }) => validate_mpls_labels(labels),
+        Some(RouteEncap::Seg6 { mode, segments }) => {
+            let slots = segments.len() + usize::from(*mode == Seg6Mode::Inline);
+            if segments.is_empty() || slots > SEG6_MAX_SEGMENTS {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "SRv6 segment list must have 1 to {} entries",
+                        SEG6_MAX_SEGMENTS
+                    ),
+                ));
+            }
+            Ok(())
+        }
+        Some(RouteEncap::Ip(encap)) => match encap.source {
+            Some(source) if source.is_ipv4() != encap.destination.is_ipv4() => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Tunnel source and destination must be of the same family",
+            )),
+            _ => Ok(()),
+        },
+        Some(RouteEncap::Seg6Local(_)) | None => Ok(()),
+    }
+}
+
+fn validate_nexthops(nexthops: &[RouteNextHopInfo]) -> io::Result<()> {
+    for nexthop in nexthops {
+        validate_encap(nexthop.encap.as_ref())?;
+    }
+    match nexthops
+        .iter()
+        .find(|nexthop| !(1..=MAX_NEXTHOP_WEIGHT).contains(&nexthop.weight))
+    {
+        Some(nexthop) => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Nexthop weight {} is outside 1..={}",
+                nexthop.weight, MAX_NEXTHOP_WEIGHT
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+// The kernel's IPv6 route path rejects RTA_VIA, so IPv6 routes cannot use
+// nexthops of another family.
+fn validate_ipv6_gateways(route: &Ipv6Route) -> io::Result<()> {
+    let gateways = route.gateway.iter().chain(
+        route
+            .nexthops
+            .iter()
+            .filter_map(|nexthop| nexthop.gateway.as_ref()),
+    );
+    for gw in gateways {
+        if gw.addr().is_ipv4() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("IPv4 nexthop {} on an IPv6 route", gw.addr()),
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn handle_route_status(op: &str, response: RtnlRouteResponse) -> io::Result<()> {
     match response {
         RtnlRouteResponse::Success => Ok(()),
@@ -244,11 +1924,16 @@ fn handle_route_status(op: &str, response: RtnlRouteResponse) -> io::Result<()>
             ErrorKind::NotFound,
             format!("{}: route not found", op),
         )),
+        RtnlRouteResponse::AlreadyExists => Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{}: route already exists", op),
+        )),
         RtnlRouteResponse::Failed => Err(io::Error::other(format!("{} failed", op))),
         RtnlRouteResponse::NotImplemented => Err(io::Error::new(
             ErrorKind::Unsupported,
             format!("{} not implemented", op),
         )),
+        RtnlRouteResponse::Error(err) => Err(err.into_io_error(op)),
         other => Err(io::Error::other(format!(
             "{} returned unexpected response: {:?}",
             op, other
@@ -271,7 +1956,7 @@ async fn list_routes_v4(handle: &rtnetlink::RouteHandle) -> RtnlRouteResponse {
             Ok(None) => break,
             Err(err) => {
                 warn!("Failed to list IPv4 routes: {}", err);
-                return RtnlRouteResponse::Failed;
+                return RtnlRouteResponse::Error(RtnlError::from_rtnetlink(&err));
             }
         }
     }
@@ -293,13 +1978,183 @@ async fn list_routes_v6(handle: &rtnetlink::RouteHandle) -> RtnlRouteResponse {
             Ok(None) => break,
             Err(err) => {
                 warn!("Failed to list IPv6 routes: {}", err);
-                return RtnlRouteResponse::Failed;
+                return RtnlRouteResponse::Error(RtnlError::from_rtnetlink(&err));
+            }
+        }
+    }
+    RtnlRouteResponse::Ipv6RouteList(routes)
+}
+
+async fn list_routes_v4_filtered(
+    handle: &rtnetlink::RouteHandle,
+    filter: RouteFilter,
+) -> RtnlRouteResponse {
+    let message = filter.dump_message(AddressFamily::Inet);
+    let stream = handle.get(message).execute();
+    futures::pin_mut!(stream);
+    let mut routes = Vec::new();
+    loop {
+        match stream.try_next().await {
+            Ok(Some(msg)) => {
+                if !filter.matches_message(&msg) {
+                    continue;
+                }
+                if let Some(route) = decode_ipv4_route(msg).filter(|r| filter.matches_ipv4(r)) {
+                    routes.push(route);
+                }
+            }
+            Ok(None) => break,
+            Err(err) => {
+                warn!("Failed to list IPv4 routes: {}", err);
+                return RtnlRouteResponse::Error(RtnlError::from_rtnetlink(&err));
+            }
+        }
+    }
+    RtnlRouteResponse::Ipv4RouteList(routes)
+}
+
+async fn list_routes_v6_filtered(
+    handle: &rtnetlink::RouteHandle,
+    filter: RouteFilter,
+) -> RtnlRouteResponse {
+    let message = filter.dump_message(AddressFamily::Inet6);
+    let stream = handle.get(message).execute();
+    futures::pin_mut!(stream);
+    let mut routes = Vec::new();
+    loop {
+        match stream.try_next().await {
+            Ok(Some(msg)) => {
+                if !filter.matches_message(&msg) {
+                    continue;
+                }
+                if let Some(route) = decode_ipv6_route(msg).filter(|r| filter.matches_ipv6(r)) {
+                    routes.push(route);
+                }
+            }
+            Ok(None) => break,
+            Err(err) => {
+                warn!("Failed to list IPv6 routes: {}", err);
+                return RtnlRouteResponse::Error(RtnlError::from_rtnetlink(&err));
             }
         }
     }
     RtnlRouteResponse::Ipv6RouteList(routes)
 }
 
+async fn list_routes_mpls(handle: &rtnetlink::RouteHandle) -> RtnlRouteResponse {
+    let message = RouteMessageBuilder::<MplsLabel>::new().build();
+    let stream = handle.get(message).execute();
+    futures::pin_mut!(stream);
+    let mut routes = Vec::new();
+    loop {
+        match stream.try_next().await {
+            Ok(Some(msg)) => {
+                if let Some(route) = decode_mpls_route(msg) {
+                    routes.push(route);
+                }
+            }
+            Ok(None) => break,
+            Err(err) => {
+                warn!("Failed to list MPLS routes: {}", err);
+                return RtnlRouteResponse::Error(RtnlError::from_rtnetlink(&err));
+            }
+        }
+    }
+    RtnlRouteResponse::MplsRouteList(routes)
+}
+
+async fn list_routes_multicast(handle: &rtnetlink::RouteHandle) -> RtnlRouteResponse {
+    let mut routes = Vec::new();
+    for family in [RTNL_FAMILY_IPMR, RTNL_FAMILY_IP6MR] {
+        let mut message = RouteMessage::default();
+        message.header.address_family = AddressFamily::Other(family);
+        let stream = handle.get(message).execute();
+        futures::pin_mut!(stream);
+        loop {
+            match stream.try_next().await {
+                Ok(Some(msg)) => {
+                    if let Some(route) = decode_multicast_route(msg) {
+                        routes.push(route);
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    warn!("Failed to list multicast routes: {}", err);
+                    return RtnlRouteResponse::Error(RtnlError::from_rtnetlink(&err));
+                }
+            }
+        }
+    }
+    RtnlRouteResponse::MulticastRouteList(routes)
+}
+
+async fn add_route_multicast(
+    handle: &rtnetlink::RouteHandle,
+    links: &mut rtnetlink::LinkHandle,
+    route: MulticastRoute,
+) -> RtnlRouteResponse {
+    let vifs = match multicast_vifs(links).await {
+        Ok(vifs) => vifs,
+        Err(response) => return response,
+    };
+    // The kernel takes one TTL threshold per VIF, by position.
+    let mut ttls = Vec::new();
+    for oif in &route.oifs {
+        let Some(&vif) = vifs.get(&oif.if_id) else {
+            return RtnlRouteResponse::Error(RtnlError::new(format!(
+                "interface {} is not a multicast VIF",
+                oif.if_id
+            )));
+        };
+        if ttls.len() <= vif {
+            ttls.resize(vif + 1, 0);
+        }
+        ttls[vif] = oif.ttl;
+    }
+    let mut message = build_multicast_route_message(&route);
+    if !ttls.is_empty() {
+        let nexthops = ttls
+            .into_iter()
+            .map(|ttl| {
+                let mut nexthop = RouteNextHop::default();
+                nexthop.hops = ttl;
+                nexthop
+            })
+            .collect();
+        message.attributes.push(RouteAttribute::MultiPath(nexthops));
+    }
+    map_route_result(handle.add(message).execute().await, "add multicast route")
+}
+
+// Maps interface indices to the VIF numbers of the default IPv4 multicast
+// table; VIFs are only listed in procfs.
+async fn multicast_vifs(
+    links: &mut rtnetlink::LinkHandle,
+) -> Result<HashMap<u32, usize>, RtnlRouteResponse> {
+    let contents = tokio::fs::read_to_string(IP_MR_VIF_PATH)
+        .await
+        .map_err(|err| {
+            RtnlRouteResponse::Error(RtnlError::new(format!(
+                "failed to read {}: {}",
+                IP_MR_VIF_PATH, err
+            )))
+        })?;
+    let mut vifs = HashMap::new();
+    // The first line is a header; entries start with the VIF number and name.
+    for line in contents.lines().skip(1) {
+        let mut fields = line.split_whitespace();
+        let (Some(vif), Some(name)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        if let Ok(vif) = vif.parse()
+            && let Ok(if_id) = resolve_if_name(links, name).await
+        {
+            vifs.insert(if_id, vif);
+        }
+    }
+    Ok(vifs)
+}
+
 async fn add_route_v4(
     handle: &rtnetlink::RouteHandle,
     route: Ipv4Route,
@@ -336,6 +2191,24 @@ async fn add_route_v6(
     )
 }
 
+async fn send_new_route(
+    netlink: &mut rtnetlink::Handle,
+    message: RouteMessage,
+    flags: u16,
+) -> Result<(), rtnetlink::Error> {
+    let mut req = NetlinkMessage::from(RouteNetlinkMessage::NewRoute(message));
+    req.header.flags = NLM_F_REQUEST | NLM_F_ACK | flags;
+    let mut response = netlink.request(req)?;
+    while let Some(message) = response.next().await {
+        if let NetlinkPayload::Error(err) = message.payload
+            && err.code.is_some()
+        {
+            return Err(rtnetlink::Error::NetlinkError(err));
+        }
+    }
+    Ok(())
+}
+
 async fn delete_route_v4(handle: &rtnetlink::RouteHandle, route: Ipv4Route) -> RtnlRouteResponse {
     let message = build_ipv4_route_message(&route);
     map_route_result(handle.del(message).execute().await, "delete IPv4 route")
@@ -346,18 +2219,25 @@ async fn delete_route_v6(handle: &rtnetlink::RouteHandle, route: Ipv6Route) -> R
     map_route_result(handle.del(message).execute().await, "delete IPv6 route")
 }
 
-async fn get_route_v4(handle: &rtnetlink::RouteHandle, destination: Ipv4Addr) -> RtnlRouteResponse {
-    let target = destination;
-    let message = build_route_message_v4(Some(destination), 32);
-    lookup_route_v4(handle, message, move |route| route.route.contains(&target)).await
+// The kernel answers with the route it would use for the packet, including
+// local and broadcast routes unless `skip_local` is set.
+async fn get_route_v4(
+    handle: &rtnetlink::RouteHandle,
+    destination: Ipv4Addr,
+    options: RouteLookupOptions,
+) -> RtnlRouteResponse {
+    let mut message = build_route_message_v4(Some(destination), 32);
+    apply_lookup_options(&mut message, &options);
+    lookup_route_v4(handle, message, options.skip_local, |_| true).await
 }
 
 async fn get_route_v4_by_prefix(
     handle: &rtnetlink::RouteHandle,
     prefix: crate::Ipv4Net,
+    options: RouteLookupOptions,
 ) -> RtnlRouteResponse {
     let target = prefix;
-    let message = build_route_message_v4(
+    let mut message = build_route_message_v4(
         if prefix.prefix_len() == 0 {
             None
         } else {
@@ -365,21 +2245,58 @@ async fn get_route_v4_by_prefix(
         },
         prefix.prefix_len(),
     );
-    lookup_route_v4(handle, message, move |route| route.route == target).await
+    apply_lookup_options(&mut message, &options);
+    lookup_route_v4(handle, message, options.skip_local, move |route| {
+        route.route == target
+    })
+    .await
+}
+
+async fn get_route_v6(
+    handle: &rtnetlink::RouteHandle,
+    destination: Ipv6Addr,
+    options: RouteLookupOptions,
+) -> RtnlRouteResponse {
+    let mut message = build_route_message_v6(Some(destination), 128);
+    apply_lookup_options(&mut message, &options);
+    lookup_route_v6(handle, message, options.skip_local, |_| true).await
 }
 
-async fn get_route_v6(handle: &rtnetlink::RouteHandle, destination: Ipv6Addr) -> RtnlRouteResponse {
-    let target = destination;
-    let message = build_route_message_v6(Some(destination), 128);
-    lookup_route_v6(handle, message, move |route| route.route.contains(&target)).await
+fn apply_lookup_options(message: &mut RouteMessage, options: &RouteLookupOptions) {
+    match (message.header.address_family, options.source) {
+        (AddressFamily::Inet, Some(IpAddr::V4(source))) => {
+            message.header.source_prefix_length = 32;
+            message
+                .attributes
+                .push(RouteAttribute::Source(RouteAddress::Inet(source)));
+        }
+        (AddressFamily::Inet6, Some(IpAddr::V6(source))) => {
+            message.header.source_prefix_length = 128;
+            message
+                .attributes
+                .push(RouteAttribute::Source(RouteAddress::Inet6(source)));
+        }
+        _ => {}
+    }
+    message.header.tos = options.tos;
+    if options.fib_match {
+        message.header.flags |= RouteFlags::FibMatch;
+    }
+    if let Some(iif) = options.iif {
+        message.attributes.push(RouteAttribute::Iif(iif));
+    }
+    if let Some(mark) = options.mark {
+        message.attributes.push(RouteAttribute::Mark(mark));
+    }
 }
 
 async fn get_route_v6_by_prefix(
     handle: &rtnetlink::RouteHandle,
     prefix: crate::Ipv6Net,
+    options: RouteLookupOptions,
 ) -> RtnlRouteResponse {
     let target = prefix;
-    let message = build_route_message_v6(
+    let mut message = build_route_message_v6(
         if prefix.prefix_len() == 0 {
             None
         } else {
@@ -387,7 +2304,11 @@ async fn get_route_v6_by_prefix(
         },
         prefix.prefix_len(),
     );
-    lookup_route_v6(handle, message, move |route| route.route == target).await
+    apply_lookup_options(&mut message, &options);
+    lookup_route_v6(handle, message, options.skip_local, move |route| {
+        route.route == target
+    })
+    .await
 }
 
 async fn lookup_route<F>(
@@ -408,9 +2329,19 @@ where
                 }
             }
             Ok(None) => return RtnlRouteResponse::NotFound,
+            Err(rtnetlink::Error::NetlinkError(err_msg))
+                if matches!(
+                    err_msg.to_io().kind(),
+                    ErrorKind::NotFound
+                        | ErrorKind::NetworkUnreachable
+                        | ErrorKind::HostUnreachable
+                ) =>
+            {
+                return RtnlRouteResponse::NotFound;
+            }
             Err(err) => {
                 warn!("Failed to get route: {}", err);
-                return RtnlRouteResponse::Failed;
+                return RtnlRouteResponse::Error(RtnlError::from_rtnetlink(&err));
             }
         }
     }
@@ -419,13 +2350,14 @@ where
 async fn lookup_route_v4<F>(
     handle: &rtnetlink::RouteHandle,
     message: RouteMessage,
+    skip_local: bool,
     predicate: F,
 ) -> RtnlRouteResponse
 where
     F: Fn(&Ipv4Route) -> bool,
 {
     lookup_route(handle, message, |msg| {
-        if matches!(msg.header.kind, RouteType::Local | RouteType::Broadcast) {
+        if skip_local && matches!(msg.header.kind, RouteType::Local | RouteType::Broadcast) {
             return None;
         }
         decode_ipv4_route(msg).and_then(|route| {
@@ -442,13 +2374,14 @@ where
 async fn lookup_route_v6<F>(
     handle: &rtnetlink::RouteHandle,
     message: RouteMessage,
+    skip_local: bool,
     predicate: F,
 ) -> RtnlRouteResponse
 where
     F: Fn(&Ipv6Route) -> bool,
 {
     lookup_route(handle, message, |msg| {
-        if matches!(msg.header.kind, RouteType::Local | RouteType::Broadcast) {
+        if skip_local && matches!(msg.header.kind, RouteType::Local | RouteType::Broadcast) {
             return None;
         }
         decode_ipv6_route(msg).and_then(|route| {
@@ -493,43 +2426,41 @@ fn map_route_result(result: Result<(), rtnetlink::Error>, op: &str) -> RtnlRoute
             let io_err = err_msg.to_io();
             match io_err.kind() {
                 ErrorKind::NotFound => RtnlRouteResponse::NotFound,
-                ErrorKind::AlreadyExists => {
-                    warn!("Route operation failed (already exists): {}", io_err);
-                    RtnlRouteResponse::Failed
-                }
+                ErrorKind::AlreadyExists => RtnlRouteResponse::AlreadyExists,
                 _ => {
-                    warn!("Route operation '{}' failed: {}", op, io_err);
-                    RtnlRouteResponse::Failed
+                    let error = RtnlError::from_netlink(&err_msg);
+                    warn!("Route operation '{}' failed: {}", op, error);
+                    RtnlRouteResponse::Error(error)
                 }
             }
         }
         Err(err) => {
             warn!("Route operation '{}' failed: {}", op, err);
-            RtnlRouteResponse::Failed
+            RtnlRouteResponse::Error(RtnlError::from_rtnetlink(&err))
         }
     }
 }
 
 fn build_ipv4_route_message(route: &Ipv4Route) -> RouteMessage {
     let mut builder = RouteMessageBuilder::<Ipv4Addr>::new()
-        .destination_prefix(route.route.addr(), route.route.prefix_len());
+        .destination_prefix(route.route.addr(), route.route.prefix_len())
+        .kind(route.kind);
 
-    if let Some(if_id) = route.if_id.filter(|id| *id != 0) {
+    // The kernel rejects a nexthop id together with any other nexthop
+    // attribute, including the resolved ones it reports for the route.
+    if let Some(id) = route.nexthop_id {
+        builder.get_mut().attributes.push(nexthop_id_attribute(id));
+    }
+
+    if let Some(if_id) = route
+        .if_id
+        .filter(|id| *id != 0 && route.nexthop_id.is_none())
+    {
         builder = builder.output_interface(if_id);
     }
 
-    if let Some(gw) = route.gateway {
-        match gw {
-            IpAddr::V4(addr) => {
-                builder = builder.gateway(addr);
-            }
-            IpAddr::V6(addr) => {
-                builder
-                    .get_mut()
-                    .attributes
-                    .push(RouteAttribute::Via(RouteVia::Inet6(addr)));
-            }
-        }
+    if let Some(gw) = route.gateway.filter(|_| route.nexthop_id.is_none()) {
+        builder.get_mut().attributes.push(gw.to_attribute(true));
     }
 
     if let Some(src) = route.source {
@@ -544,35 +2475,56 @@ fn build_ipv4_route_message(route: &Ipv4Route) -> RouteMessage {
         builder = builder.table_id(table);
     }
 
-    if !route.nexthops.is_empty() {
-        if let Some(multipath) = build_multipath_v4(&route.nexthops) {
-            builder = builder.multipath(multipath);
-        }
+    if let Some(protocol) = route.protocol {
+        builder = builder.protocol(protocol);
+    }
+
+    builder.get_mut().header.tos = route.tos;
+
+    if !route.nexthops.is_empty()
+        && route.nexthop_id.is_none()
+        && let Some(multipath) = build_multipath_v4(&route.nexthops)
+    {
+        builder = builder.multipath(multipath);
+    }
+
+    if let Some(metrics) = build_metrics(route.mtu, &route.metrics, &route.extra) {
+        builder.get_mut().attributes.push(metrics);
+    }
+
+    if route.onlink {
+        builder.get_mut().header.flags |= RouteFlags::Onlink;
+    }
+
+    if let Some(encap) = &route.encap {
+        builder.get_mut().attributes.extend(build_encap(encap));
     }
 
+    push_extra_attributes(builder.get_mut(), &route.extra);
+
     builder.build()
 }
 
 fn build_ipv6_route_message(route: &Ipv6Route) -> RouteMessage {
     let mut builder = RouteMessageBuilder::<Ipv6Addr>::new()
-        .destination_prefix(route.route.addr(), route.route.prefix_len());
+        .destination_prefix(route.route.addr(), route.route.prefix_len())
+        .kind(route.kind);
 
-    if let Some(if_id) = route.if_id.filter(|id| *id != 0) {
+    // The kernel rejects a nexthop id together with any other nexthop
+    // attribute, including the resolved ones it reports for the route.
+    if let Some(id) = route.nexthop_id {
+        builder.get_mut().attributes.push(nexthop_id_attribute(id));
+    }
+
+    if let Some(if_id) = route
+        .if_id
+        .filter(|id| *id != 0 && route.nexthop_id.is_none())
+    {
         builder = builder.output_interface(if_id);
     }
 
-    if let Some(gw) = route.gateway {
-        match gw {
-            IpAddr::V6(addr) => {
-                builder = builder.gateway(addr);
-            }
-            IpAddr::V4(addr) => {
-                builder
-                    .get_mut()
-                    .attributes
-                    .push(RouteAttribute::Via(RouteVia::Inet(addr)));
-            }
-        }
+    if let Some(gw) = route.gateway.filter(|_| route.nexthop_id.is_none()) {
+        builder.get_mut().attributes.push(gw.to_attribute(false));
     }
 
     if let Some(src) = route.source {
@@ -587,13 +2539,620 @@ fn build_ipv6_route_message(route: &Ipv6Route) -> RouteMessage {
         builder = builder.table_id(table);
     }
 
+    if let Some(protocol) = route.protocol {
+        builder = builder.protocol(protocol);
+    }
+
+    if !route.nexthops.is_empty()
+        && route.nexthop_id.is_none()
+        && let Some(multipath) = build_multipath_v6(&route.nexthops)
+    {
+        builder = builder.multipath(multipath);
+    }
+
+    if let Some(metrics) = build_metrics(route.mtu, &route.metrics, &route.extra) {
+        builder.get_mut().attributes.push(metrics);
+    }
+
+    if route.onlink {
+        builder.get_mut().header.flags |= RouteFlags::Onlink;
+    }
+
+    if let Some(encap) = &route.encap {
+        builder.get_mut().attributes.extend(build_encap(encap));
+    }
+
+    if let Some(expires) = route.expires {
+        builder
+            .get_mut()
+            .attributes
+            .push(RouteAttribute::Expires(expires));
+    }
+
+    push_extra_attributes(builder.get_mut(), &route.extra);
+
+    builder.build()
+}
+
+const RTAX_MTU: u32 = 2;
+const RTA_NH_ID: u16 = 30;
+
+const LWTUNNEL_IP_ID: u16 = 1;
+const LWTUNNEL_IP_DST: u16 = 2;
+const LWTUNNEL_IP_SRC: u16 = 3;
+const LWTUNNEL_IP_TTL: u16 = 4;
+const LWTUNNEL_IP_TOS: u16 = 5;
+const LWTUNNEL_IP_FLAGS: u16 = 6;
+
+const TUNNEL_CSUM: u16 = 0x01;
+const TUNNEL_KEY: u16 = 0x04;
+const TUNNEL_SEQ: u16 = 0x08;
+
+const SEG6_IPTUNNEL_SRH: u16 = 1;
+const IPV6_SRCRT_TYPE_4: u8 = 4;
+
+const SEG6_LOCAL_ACTION: u16 = 1;
+const SEG6_LOCAL_TABLE: u16 = 3;
+const SEG6_LOCAL_NH4: u16 = 4;
+const SEG6_LOCAL_NH6: u16 = 5;
+const SEG6_LOCAL_OIF: u16 = 7;
+const SEG6_LOCAL_VRFTABLE: u16 = 9;
+
+const SEG6_LOCAL_ACTION_END: u32 = 1;
+const SEG6_LOCAL_ACTION_END_X: u32 = 2;
+const SEG6_LOCAL_ACTION_END_T: u32 = 3;
+const SEG6_LOCAL_ACTION_END_DX2: u32 = 4;
+const SEG6_LOCAL_ACTION_END_DX6: u32 = 5;
+const SEG6_LOCAL_ACTION_END_DX4: u32 = 6;
+const SEG6_LOCAL_ACTION_END_DT6: u32 = 7;
+const SEG6_LOCAL_ACTION_END_DT4: u32 = 8;
+
+// Metrics from `extra` are merged in, as the kernel reads a single
+// RTA_METRICS.
+fn build_metrics(
+    mtu: Option<u32>,
+    metrics: &RouteMetrics,
+    extra: &[RouteAttribute],
+) -> Option<RouteAttribute> {
+    let mut values = Vec::new();
+    match metrics_lock(mtu, metrics) {
+        0 => {}
+        lock => values.push(RouteMetric::Lock(lock)),
+    }
+    values.extend(mtu.map(RouteMetric::Mtu));
+    values.extend(metrics.advmss.map(RouteMetric::Advmss));
+    values.extend(metrics.hoplimit.map(RouteMetric::Hoplimit));
+    values.extend(metrics.initcwnd.map(RouteMetric::InitCwnd));
+    values.extend(metrics.initrwnd.map(RouteMetric::InitRwnd));
+    values.extend(metrics.rto_min.map(RouteMetric::RtoMin));
+    for attr in extra {
+        if let RouteAttribute::Metrics(other) = attr {
+            values.extend(other.iter().cloned());
+        }
+    }
+    if values.is_empty() {
+        None
+    } else {
+        Some(RouteAttribute::Metrics(values))
+    }
+}
+
+fn push_extra_attributes(message: &mut RouteMessage, extra: &[RouteAttribute]) {
+    message.attributes.extend(
+        extra
+            .iter()
+            .filter(|attr| !matches!(attr, RouteAttribute::Metrics(_)))
+            .cloned(),
+    );
+}
+
+fn metrics_lock(mtu: Option<u32>, metrics: &RouteMetrics) -> u32 {
+    metrics
+        .lock
+        .unwrap_or(if mtu.is_some() { 1 << RTAX_MTU } else { 0 })
+}
+
+// Metrics without a field are returned as they are. The lock bitmap is kept
+// even when empty, as the kernel leaves it out when nothing is locked.
+fn decode_metrics(values: Vec<RouteMetric>) -> (Option<u32>, RouteMetrics, Vec<RouteMetric>) {
+    let mut mtu = None;
+    let mut metrics = RouteMetrics {
+        lock: Some(0),
+        ..RouteMetrics::default()
+    };
+    let mut other = Vec::new();
+    for value in values {
+        match value {
+            RouteMetric::Mtu(value) => mtu = Some(value),
+            RouteMetric::Advmss(value) => metrics.advmss = Some(value),
+            RouteMetric::Hoplimit(value) => metrics.hoplimit = Some(value),
+            RouteMetric::InitCwnd(value) => metrics.initcwnd = Some(value),
+            RouteMetric::InitRwnd(value) => metrics.initrwnd = Some(value),
+            RouteMetric::RtoMin(value) => metrics.rto_min = Some(value),
+            RouteMetric::Lock(value) => metrics.lock = Some(value),
+            value => other.push(value),
+        }
+    }
+    (mtu, metrics, other)
+}
+
+fn mpls_label_stack(labels: &[u32]) -> Vec<MplsLabel> {
+    let last = labels.len().saturating_sub(1);
+    labels
+        .iter()
+        .enumerate()
+        .map(|(index, label)| MplsLabel {
+            label: *label,
+            traffic_class: 0,
+            bottom_of_stack: index == last,
+            ttl: 0,
+        })
+        .collect()
+}
+
+fn build_encap(encap: &RouteEncap) -> [RouteAttribute; 2] {
+    match encap {
+        RouteEncap::Mpls { labels, ttl } => {
+            let mut values = vec![RouteMplsIpTunnel::Destination(mpls_label_stack(labels))];
+            values.extend(ttl.map(RouteMplsIpTunnel::Ttl));
+            [
+                RouteAttribute::EncapType(RouteLwEnCapType::Mpls),
+                RouteAttribute::Encap(values.into_iter().map(RouteLwTunnelEncap::Mpls).collect()),
+            ]
+        }
+        // netlink-packet-route cannot build a segment routing header, so it
+        // is emitted raw.
+        RouteEncap::Seg6 { mode, segments } => [
+            RouteAttribute::EncapType(RouteLwEnCapType::Seg6),
+            RouteAttribute::Encap(vec![RouteLwTunnelEncap::Other(DefaultNla::new(
+                SEG6_IPTUNNEL_SRH,
+                seg6_srh(*mode, segments),
+            ))]),
+        ],
+        RouteEncap::Seg6Local(action) => [
+            RouteAttribute::EncapType(RouteLwEnCapType::Seg6Local),
+            RouteAttribute::Encap(
+                seg6local_nlas(action)
+                    .into_iter()
+                    .map(RouteLwTunnelEncap::Other)
+                    .collect(),
+            ),
+        ],
+        RouteEncap::Ip(encap) => [
+            RouteAttribute::EncapType(match encap.destination {
+                IpAddr::V4(_) => RouteLwEnCapType::Ip,
+                IpAddr::V6(_) => RouteLwEnCapType::Ip6,
+            }),
+            RouteAttribute::Encap(
+                ip_tunnel_nlas(encap)
+                    .into_iter()
+                    .map(RouteLwTunnelEncap::Other)
+                    .collect(),
+            ),
+        ],
+    }
+}
+
+// The ip and ip6 attributes share their numbering, and netlink-packet-route
+// lacks the ip flavour and a public ip6 flags type, so both are emitted raw.
+fn ip_tunnel_nlas(encap: &IpTunnelEncap) -> Vec<DefaultNla> {
+    let octets = |addr: IpAddr| match addr {
+        IpAddr::V4(addr) => addr.octets().to_vec(),
+        IpAddr::V6(addr) => addr.octets().to_vec(),
+    };
+    let mut nlas = vec![DefaultNla::new(LWTUNNEL_IP_DST, octets(encap.destination))];
+    if let Some(id) = encap.id {
+        nlas.push(DefaultNla::new(LWTUNNEL_IP_ID, id.to_be_bytes().to_vec()));
+    }
+    if let Some(source) = encap.source {
+        nlas.push(DefaultNla::new(LWTUNNEL_IP_SRC, octets(source)));
+    }
+    if let Some(ttl) = encap.ttl {
+        nlas.push(DefaultNla::new(LWTUNNEL_IP_TTL, vec![ttl]));
+    }
+    if let Some(tos) = encap.tos {
+        nlas.push(DefaultNla::new(LWTUNNEL_IP_TOS, vec![tos]));
+    }
+    if encap.flags() != 0 {
+        nlas.push(DefaultNla::new(
+            LWTUNNEL_IP_FLAGS,
+            encap.flags().to_be_bytes().to_vec(),
+        ));
+    }
+    nlas
+}
+
+fn seg6_srh(mode: Seg6Mode, segments: &[Ipv6Addr]) -> Vec<u8> {
+    // The header lists segments last hop first. Inline mode reserves slot 0
+    // for the original destination, which the kernel fills in.
+    let mut slots: Vec<Ipv6Addr> = segments.iter().rev().copied().collect();
+    if mode == Seg6Mode::Inline {
+        slots.insert(0, Ipv6Addr::UNSPECIFIED);
+    }
+    let last = (slots.len() - 1) as u8;
+    let mut value = u32::from(mode).to_ne_bytes().to_vec();
+    let hdrlen = (slots.len() * 2) as u8;
+    // nexthdr, hdrlen, type, segments_left, first_segment, flags, tag
+    value.extend([0, hdrlen, IPV6_SRCRT_TYPE_4, last, last, 0, 0, 0]);
+    for slot in slots {
+        value.extend(slot.octets());
+    }
+    value
+}
+
+fn seg6local_nlas(action: &Seg6LocalAction) -> Vec<DefaultNla> {
+    let (code, param) = match action {
+        Seg6LocalAction::End => (SEG6_LOCAL_ACTION_END, None),
+        Seg6LocalAction::EndX { nexthop } => (
+            SEG6_LOCAL_ACTION_END_X,
+            Some(DefaultNla::new(SEG6_LOCAL_NH6, nexthop.octets().to_vec())),
+        ),
+        Seg6LocalAction::EndT { table } => (
+            SEG6_LOCAL_ACTION_END_T,
+            Some(DefaultNla::new(
+                SEG6_LOCAL_TABLE,
+                table.to_ne_bytes().to_vec(),
+            )),
+        ),
+        Seg6LocalAction::EndDx2 { if_id } => (
+            SEG6_LOCAL_ACTION_END_DX2,
+            Some(DefaultNla::new(
+                SEG6_LOCAL_OIF,
+                if_id.to_ne_bytes().to_vec(),
+            )),
+        ),
+        Seg6LocalAction::EndDx4 { nexthop } => (
+            SEG6_LOCAL_ACTION_END_DX4,
+            Some(DefaultNla::new(SEG6_LOCAL_NH4, nexthop.octets().to_vec())),
+        ),
+        Seg6LocalAction::EndDx6 { nexthop } => (
+            SEG6_LOCAL_ACTION_END_DX6,
+            Some(DefaultNla::new(SEG6_LOCAL_NH6, nexthop.octets().to_vec())),
+        ),
+        Seg6LocalAction::EndDt4 { vrf_table } => (
+            SEG6_LOCAL_ACTION_END_DT4,
+            Some(DefaultNla::new(
+                SEG6_LOCAL_VRFTABLE,
+                vrf_table.to_ne_bytes().to_vec(),
+            )),
+        ),
+        Seg6LocalAction::EndDt6 { table } => (
+            SEG6_LOCAL_ACTION_END_DT6,
+            Some(DefaultNla::new(
+                SEG6_LOCAL_TABLE,
+                table.to_ne_bytes().to_vec(),
+            )),
+        ),
+    };
+    std::iter::once(DefaultNla::new(
+        SEG6_LOCAL_ACTION,
+        code.to_ne_bytes().to_vec(),
+    ))
+    .chain(param)
+    .collect()
+}
+
+fn decode_encap(kind: RouteLwEnCapType, values: Vec<RouteLwTunnelEncap>) -> Option<RouteEncap> {
+    match kind {
+        RouteLwEnCapType::Mpls => decode_mpls_encap(values),
+        RouteLwEnCapType::Seg6 => values.into_iter().find_map(|value| match value {
+            RouteLwTunnelEncap::Seg6(RouteSeg6IpTunnel::Seg6(header)) => Some(RouteEncap::Seg6 {
+                mode: header.mode,
+                segments: header.segments,
+            }),
+            _ => None,
+        }),
+        // netlink-packet-route leaves seg6local attributes undecoded.
+        RouteLwEnCapType::Seg6Local => decode_seg6local_encap(values),
+        RouteLwEnCapType::Ip => decode_ip_encap(values),
+        RouteLwEnCapType::Ip6 => decode_ip6_encap(values),
+        _ => None,
+    }
+}
+
+fn decode_ip_encap(values: Vec<RouteLwTunnelEncap>) -> Option<RouteEncap> {
+    let mut destination = None;
+    let mut encap = IpTunnelEncap::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    for value in values {
+        let RouteLwTunnelEncap::Other(nla) = value else {
+            continue;
+        };
+        let mut bytes = vec![0u8; nla.value_len()];
+        nla.emit_value(&mut bytes);
+        match (nla.kind(), bytes.as_slice()) {
+            (LWTUNNEL_IP_ID, _) => encap.id = bytes.try_into().ok().map(u64::from_be_bytes),
+            (LWTUNNEL_IP_DST, _) => {
+                destination = <[u8; 4]>::try_from(bytes).ok().map(Ipv4Addr::from);
+            }
+            (LWTUNNEL_IP_SRC, _) => {
+                encap.source = <[u8; 4]>::try_from(bytes)
+                    .ok()
+                    .map(|octets| IpAddr::V4(octets.into()));
+            }
+            (LWTUNNEL_IP_TTL, [ttl]) => encap.ttl = Some(*ttl),
+            (LWTUNNEL_IP_TOS, [tos]) => encap.tos = Some(*tos),
+            (LWTUNNEL_IP_FLAGS, [hi, lo]) => encap.set_flags(u16::from_be_bytes([*hi, *lo])),
+            _ => {}
+        }
+    }
+    encap.destination = IpAddr::V4(destination?);
+    Some(RouteEncap::Ip(encap.without_zeroes()))
+}
+
+fn decode_ip6_encap(values: Vec<RouteLwTunnelEncap>) -> Option<RouteEncap> {
+    let mut destination = None;
+    let mut encap = IpTunnelEncap::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED));
+    for value in values {
+        match value {
+            RouteLwTunnelEncap::Ip6(RouteIp6Tunnel::Id(id)) => encap.id = Some(id),
+            RouteLwTunnelEncap::Ip6(RouteIp6Tunnel::Destination(addr)) => destination = Some(addr),
+            RouteLwTunnelEncap::Ip6(RouteIp6Tunnel::Source(addr)) => {
+                encap.source = Some(IpAddr::V6(addr));
+            }
+            RouteLwTunnelEncap::Ip6(RouteIp6Tunnel::Hoplimit(ttl)) => encap.ttl = Some(ttl),
+            RouteLwTunnelEncap::Ip6(RouteIp6Tunnel::Tc(tos)) => encap.tos = Some(tos),
+            RouteLwTunnelEncap::Ip6(RouteIp6Tunnel::Flags(flags)) => encap.set_flags(flags.bits()),
+            _ => {}
+        }
+    }
+    encap.destination = IpAddr::V6(destination?);
+    Some(RouteEncap::Ip(encap.without_zeroes()))
+}
+
+fn decode_seg6local_encap(values: Vec<RouteLwTunnelEncap>) -> Option<RouteEncap> {
+    let mut code = None;
+    let mut table = None;
+    let mut vrf_table = None;
+    let mut nh4 = None;
+    let mut nh6 = None;
+    let mut oif = None;
+    for value in values {
+        let RouteLwTunnelEncap::Other(nla) = value else {
+            continue;
+        };
+        let mut bytes = vec![0u8; nla.value_len()];
+        nla.emit_value(&mut bytes);
+        match nla.kind() {
+            SEG6_LOCAL_ACTION => code = bytes.try_into().ok().map(u32::from_ne_bytes),
+            SEG6_LOCAL_TABLE => table = bytes.try_into().ok().map(u32::from_ne_bytes),
+            SEG6_LOCAL_VRFTABLE => vrf_table = bytes.try_into().ok().map(u32::from_ne_bytes),
+            SEG6_LOCAL_OIF => oif = bytes.try_into().ok().map(u32::from_ne_bytes),
+            SEG6_LOCAL_NH4 => nh4 = <[u8; 4]>::try_from(bytes).ok().map(Ipv4Addr::from),
+            SEG6_LOCAL_NH6 => nh6 = <[u8; 16]>::try_from(bytes).ok().map(Ipv6Addr::from),
+            _ => {}
+        }
+    }
+    let action = match code? {
+        SEG6_LOCAL_ACTION_END => Seg6LocalAction::End,
+        SEG6_LOCAL_ACTION_END_X => Seg6LocalAction::EndX { nexthop: nh6? },
+        SEG6_LOCAL_ACTION_END_T => Seg6LocalAction::EndT { table: table? },
+        SEG6_LOCAL_ACTION_END_DX2 => Seg6LocalAction::EndDx2 { if_id: oif? },
+        SEG6_LOCAL_ACTION_END_DX4 => Seg6LocalAction::EndDx4 { nexthop: nh4? },
+        SEG6_LOCAL_ACTION_END_DX6 => Seg6LocalAction::EndDx6 { nexthop: nh6? },
+        SEG6_LOCAL_ACTION_END_DT4 => Seg6LocalAction::EndDt4 {
+            vrf_table: vrf_table?,
+        },
+        SEG6_LOCAL_ACTION_END_DT6 => Seg6LocalAction::EndDt6 { table: table? },
+        _ => return None,
+    };
+    Some(RouteEncap::Seg6Local(action))
+}
+
+fn decode_mpls_encap(values: Vec<RouteLwTunnelEncap>) -> Option<RouteEncap> {
+    let mut mpls: Option<(Vec<u32>, Option<u8>)> = None;
+    for value in values {
+        match value {
+            RouteLwTunnelEncap::Mpls(RouteMplsIpTunnel::Destination(labels)) => {
+                mpls.get_or_insert_default().0 = labels.iter().map(|l| l.label).collect();
+            }
+            RouteLwTunnelEncap::Mpls(RouteMplsIpTunnel::Ttl(ttl)) => {
+                mpls.get_or_insert_default().1 = Some(ttl);
+            }
+            _ => {}
+        }
+    }
+    mpls.map(|(labels, ttl)| RouteEncap::Mpls { labels, ttl })
+}
+
+fn build_mpls_route_message(route: &MplsRoute) -> RouteMessage {
+    let mut builder =
+        RouteMessageBuilder::<MplsLabel>::new().label(mpls_label_stack(&[route.label])[0]);
+    if let Some(protocol) = route.protocol {
+        builder = builder.protocol(protocol);
+    }
     if !route.nexthops.is_empty() {
-        if let Some(multipath) = build_multipath_v6(&route.nexthops) {
-            builder = builder.multipath(multipath);
+        return builder
+            .multipath(build_multipath_mpls(&route.nexthops))
+            .build();
+    }
+    if let Some(via) = route.via {
+        builder = builder.via(via);
+    }
+    if let Some(if_id) = route.if_id.filter(|id| *id != 0) {
+        builder = builder.output_interface(if_id);
+    }
+    builder
+        .output_mpls(mpls_label_stack(&route.out_labels))
+        .build()
+}
+
+fn build_multipath_mpls(entries: &[MplsNextHop]) -> Vec<RouteNextHop> {
+    entries
+        .iter()
+        .map(|entry| {
+            let mut route_entry = RouteNextHop::default();
+            route_entry.interface_index = entry.if_id.unwrap_or(0);
+            if let Some(via) = entry.via {
+                route_entry.attributes.push(RouteAttribute::Via(match via {
+                    IpAddr::V4(addr) => RouteVia::Inet(addr),
+                    IpAddr::V6(addr) => RouteVia::Inet6(addr),
+                }));
+            }
+            if !entry.out_labels.is_empty() {
+                route_entry
+                    .attributes
+                    .push(RouteAttribute::NewDestination(mpls_label_stack(
+                        &entry.out_labels,
+                    )));
+            }
+            route_entry
+        })
+        .collect()
+}
+
+fn decode_multipath_mpls(paths: Vec<RouteNextHop>) -> Vec<MplsNextHop> {
+    paths
+        .into_iter()
+        .map(|path| {
+            let mut nexthop = MplsNextHop {
+                out_labels: Vec::new(),
+                via: None,
+                if_id: (path.interface_index != 0).then_some(path.interface_index),
+            };
+            for attr in path.attributes {
+                match attr {
+                    RouteAttribute::NewDestination(labels) => {
+                        nexthop.out_labels = labels.iter().map(|l| l.label).collect();
+                    }
+                    RouteAttribute::Via(RouteVia::Inet(addr)) => {
+                        nexthop.via = Some(IpAddr::V4(addr))
+                    }
+                    RouteAttribute::Via(RouteVia::Inet6(addr)) => {
+                        nexthop.via = Some(IpAddr::V6(addr))
+                    }
+                    _ => {}
+                }
+            }
+            nexthop
+        })
+        .collect()
+}
+
+fn decode_mpls_route(message: RouteMessage) -> Option<MplsRoute> {
+    if message.header.address_family != AddressFamily::Mpls {
+        return None;
+    }
+
+    let mut label = None;
+    let mut out_labels = Vec::new();
+    let mut via = None;
+    let mut oif = None;
+    let mut nexthops = Vec::new();
+
+    for attr in message.attributes {
+        match attr {
+            RouteAttribute::Destination(RouteAddress::Mpls(value)) => label = Some(value.label),
+            RouteAttribute::MultiPath(paths) => nexthops = decode_multipath_mpls(paths),
+            RouteAttribute::NewDestination(labels) => {
+                out_labels = labels.iter().map(|l| l.label).collect();
+            }
+            RouteAttribute::Via(RouteVia::Inet(addr)) => via = Some(IpAddr::V4(addr)),
+            RouteAttribute::Via(RouteVia::Inet6(addr)) => via = Some(IpAddr::V6(addr)),
+            RouteAttribute::Oif(index) => oif = Some(index),
+            _ => {}
         }
     }
 
-    builder.build()
+    Some(MplsRoute {
+        label: label?,
+        out_labels,
+        via,
+        if_id: oif.filter(|id| *id != 0),
+        protocol: Some(message.header.protocol),
+        nexthops,
+    })
+}
+
+fn build_multicast_route_message(route: &MulticastRoute) -> RouteMessage {
+    let mut message = RouteMessage::default();
+    message.header.address_family = AddressFamily::Other(RTNL_FAMILY_IPMR);
+    message.header.kind = RouteType::Multicast;
+    if let Some(protocol) = route.protocol {
+        message.header.protocol = protocol;
+    }
+    message.header.destination_prefix_length = 32;
+    if let Some(source) = route.source {
+        message.header.source_prefix_length = 32;
+        message
+            .attributes
+            .push(RouteAttribute::Source(source.into()));
+    }
+    message
+        .attributes
+        .push(RouteAttribute::Destination(route.group.into()));
+    message.attributes.push(RouteAttribute::Iif(route.iif));
+    message
+}
+
+fn decode_multicast_route(message: RouteMessage) -> Option<MulticastRoute> {
+    if !matches!(
+        message.header.address_family,
+        AddressFamily::Other(RTNL_FAMILY_IPMR | RTNL_FAMILY_IP6MR)
+    ) {
+        return None;
+    }
+
+    let mut source = None;
+    let mut group = None;
+    let mut iif = None;
+    let mut oifs = Vec::new();
+    let mut stats = MulticastRouteStats::default();
+
+    for attr in message.attributes {
+        match attr {
+            RouteAttribute::Source(RouteAddress::Other(bytes)) => {
+                source = multicast_route_addr(&bytes)
+            }
+            RouteAttribute::Destination(RouteAddress::Other(bytes)) => {
+                group = multicast_route_addr(&bytes)
+            }
+            RouteAttribute::Iif(index) => iif = Some(index),
+            RouteAttribute::MultiPath(nexthops) => {
+                oifs = nexthops
+                    .iter()
+                    .map(|nexthop| MulticastOif {
+                        if_id: nexthop.interface_index,
+                        ttl: nexthop.hops,
+                    })
+                    .collect();
+            }
+            RouteAttribute::MfcStats(value) => {
+                stats = MulticastRouteStats {
+                    packets: value.packets,
+                    bytes: value.bytes,
+                    wrong_if: value.wrong_if,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    Some(MulticastRoute {
+        source: source.filter(|addr| !addr.is_unspecified()),
+        group: group?,
+        iif: iif?,
+        oifs,
+        protocol: Some(message.header.protocol),
+        stats,
+    })
+}
+
+fn multicast_route_addr(bytes: &[u8]) -> Option<IpAddr> {
+    if let Ok(octets) = <[u8; 4]>::try_from(bytes) {
+        Some(IpAddr::from(octets))
+    } else {
+        <[u8; 16]>::try_from(bytes).ok().map(IpAddr::from)
+    }
+}
+
+fn nexthop_id_attribute(id: u32) -> RouteAttribute {
+    RouteAttribute::Other(DefaultNla::new(RTA_NH_ID, id.to_ne_bytes().to_vec()))
+}
+
+fn nla_u32(nla: &DefaultNla) -> Option<u32> {
+    let mut bytes = vec![0u8; nla.value_len()];
+    nla.emit_value(&mut bytes);
+    bytes.try_into().ok().map(u32::from_ne_bytes)
 }
 
 fn decode_ipv4_route(message: RouteMessage) -> Option<Ipv4Route> {
@@ -603,20 +3162,26 @@ fn decode_ipv4_route(message: RouteMessage) -> Option<Ipv4Route> {
 
     let header = message.header;
     let mut destination = None;
-    let mut gateway: Option<IpAddr> = None;
+    let mut gateway = None;
     let mut source = None;
     let mut metric = None;
     let mut table = table_from_header(header.table);
     let mut oif = None;
     let mut nexthops = Vec::new();
+    let mut nexthop_id = None;
+    let mut mtu = None;
+    let mut metrics = RouteMetrics::default();
+    let mut encap_type = RouteLwEnCapType::None;
+    let mut encap = Vec::new();
+    let mut cache_info = None;
+    let mut extra = Vec::new();
 
     for attr in message.attributes {
         match attr {
             RouteAttribute::Destination(RouteAddress::Inet(addr)) => destination = Some(addr),
-            RouteAttribute::Gateway(RouteAddress::Inet(addr)) => gateway = Some(IpAddr::V4(addr)),
-            RouteAttribute::Gateway(RouteAddress::Inet6(addr)) => gateway = Some(IpAddr::V6(addr)),
-            RouteAttribute::Via(RouteVia::Inet(addr)) => gateway = Some(IpAddr::V4(addr)),
-            RouteAttribute::Via(RouteVia::Inet6(addr)) => gateway = Some(IpAddr::V6(addr)),
+            RouteAttribute::Gateway(_) | RouteAttribute::Via(_) => {
+                gateway = RouteGateway::from_attribute(&attr);
+            }
             RouteAttribute::PrefSource(RouteAddress::Inet(addr)) => source = Some(addr),
             RouteAttribute::Priority(value) => metric = Some(value),
             RouteAttribute::Oif(index) => oif = Some(index),
@@ -624,7 +3189,22 @@ fn decode_ipv4_route(message: RouteMessage) -> Option<Ipv4Route> {
             RouteAttribute::MultiPath(paths) => {
                 nexthops.extend(convert_multipath(paths));
             }
-            _ => {}
+            RouteAttribute::Metrics(values) => {
+                let other;
+                (mtu, metrics, other) = decode_metrics(values);
+                if !other.is_empty() {
+                    extra.push(RouteAttribute::Metrics(other));
+                }
+            }
+            RouteAttribute::EncapType(value) => encap_type = value,
+            RouteAttribute::Encap(values) => encap = values,
+            RouteAttribute::CacheInfo(info) => {
+                cache_info = Some(RouteCacheInfo::from_message(&info))
+            }
+            RouteAttribute::Other(nla) if nla.kind() == RTA_NH_ID => {
+                nexthop_id = nla_u32(&nla);
+            }
+            attr => extra.push(attr),
         }
     }
 
@@ -633,12 +3213,24 @@ fn decode_ipv4_route(message: RouteMessage) -> Option<Ipv4Route> {
 
     Some(Ipv4Route {
         if_id: oif.filter(|id| *id != 0),
+        if_name: None,
         gateway,
         source,
         metric,
+        tos: header.tos,
         table,
         route: net,
         nexthops,
+        nexthop_id,
+        kind: header.kind,
+        protocol: Some(header.protocol),
+        mtu,
+        metrics,
+        onlink: header.flags.contains(RouteFlags::Onlink),
+        encap: decode_encap_keeping_unknown(encap_type, encap, &mut extra),
+        status: RouteStatus::from_route_flags(header.flags),
+        cache_info,
+        extra,
     })
 }
 
@@ -649,20 +3241,27 @@ fn decode_ipv6_route(message: RouteMessage) -> Option<Ipv6Route> {
 
     let header = message.header;
     let mut destination = None;
-    let mut gateway: Option<IpAddr> = None;
+    let mut gateway = None;
     let mut source = None;
     let mut metric = None;
     let mut table = table_from_header(header.table);
     let mut oif = None;
     let mut nexthops = Vec::new();
+    let mut nexthop_id = None;
+    let mut mtu = None;
+    let mut metrics = RouteMetrics::default();
+    let mut encap_type = RouteLwEnCapType::None;
+    let mut encap = Vec::new();
+    let mut expires = None;
+    let mut cache_info = None;
+    let mut extra = Vec::new();
 
     for attr in message.attributes {
         match attr {
             RouteAttribute::Destination(RouteAddress::Inet6(addr)) => destination = Some(addr),
-            RouteAttribute::Gateway(RouteAddress::Inet(addr)) => gateway = Some(IpAddr::V4(addr)),
-            RouteAttribute::Gateway(RouteAddress::Inet6(addr)) => gateway = Some(IpAddr::V6(addr)),
-            RouteAttribute::Via(RouteVia::Inet(addr)) => gateway = Some(IpAddr::V4(addr)),
-            RouteAttribute::Via(RouteVia::Inet6(addr)) => gateway = Some(IpAddr::V6(addr)),
+            RouteAttribute::Gateway(_) | RouteAttribute::Via(_) => {
+                gateway = RouteGateway::from_attribute(&attr);
+            }
             RouteAttribute::PrefSource(RouteAddress::Inet6(addr)) => source = Some(addr),
             RouteAttribute::Priority(value) => metric = Some(value),
             RouteAttribute::Oif(index) => oif = Some(index),
@@ -670,7 +3269,26 @@ fn decode_ipv6_route(message: RouteMessage) -> Option<Ipv6Route> {
             RouteAttribute::MultiPath(paths) => {
                 nexthops.extend(convert_multipath(paths));
             }
-            _ => {}
+            RouteAttribute::Metrics(values) => {
+                let other;
+                (mtu, metrics, other) = decode_metrics(values);
+                if !other.is_empty() {
+                    extra.push(RouteAttribute::Metrics(other));
+                }
+            }
+            RouteAttribute::EncapType(value) => encap_type = value,
+            RouteAttribute::Encap(values) => encap = values,
+            RouteAttribute::CacheInfo(info) => {
+                // Reported in USER_HZ ticks.
+                if info.expires != 0 {
+                    expires = Some(info.expires.div_ceil(100));
+                }
+                cache_info = Some(RouteCacheInfo::from_message(&info));
+            }
+            RouteAttribute::Other(nla) if nla.kind() == RTA_NH_ID => {
+                nexthop_id = nla_u32(&nla);
+            }
+            attr => extra.push(attr),
         }
     }
 
@@ -679,15 +3297,46 @@ fn decode_ipv6_route(message: RouteMessage) -> Option<Ipv6Route> {
 
     Some(Ipv6Route {
         if_id: oif.filter(|id| *id != 0),
+        if_name: None,
         gateway,
         source,
         metric,
         table,
         route: net,
         nexthops,
+        nexthop_id,
+        kind: header.kind,
+        protocol: Some(header.protocol),
+        mtu,
+        metrics,
+        onlink: header.flags.contains(RouteFlags::Onlink),
+        encap: decode_encap_keeping_unknown(encap_type, encap, &mut extra),
+        status: RouteStatus::from_route_flags(header.flags),
+        cache_info,
+        expires,
+        extra,
     })
 }
 
+// Encapsulations `RouteEncap` cannot represent go to `extra` instead.
+fn decode_encap_keeping_unknown(
+    kind: RouteLwEnCapType,
+    values: Vec<RouteLwTunnelEncap>,
+    extra: &mut Vec<RouteAttribute>,
+) -> Option<RouteEncap> {
+    let encap = decode_encap(kind, values.clone());
+    if encap.is_none() && kind != RouteLwEnCapType::None {
+        extra.push(RouteAttribute::EncapType(kind));
+        extra.push(RouteAttribute::Encap(values));
+    }
+    encap
+}
+
+// The kernel reports cache times in USER_HZ ticks.
+fn clock_ticks(ticks: u32) -> Duration {
+    Duration::from_millis(u64::from(ticks) * 10)
+}
+
 fn table_from_header(value: u8) -> Option<u32> {
     if value == 0 { None } else { Some(value as u32) }
 }
@@ -696,17 +3345,15 @@ fn convert_multipath(paths: Vec<RouteNextHop>) -> Vec<RouteNextHopInfo> {
     let mut result = Vec::new();
     for path in paths {
         let mut gateway = None;
+        let mut encap_type = RouteLwEnCapType::None;
+        let mut encap = Vec::new();
         for attr in path.attributes {
             match attr {
-                RouteAttribute::Gateway(RouteAddress::Inet(addr)) => {
-                    gateway = Some(IpAddr::V4(addr))
-                }
-                RouteAttribute::Gateway(RouteAddress::Inet6(addr)) => {
-                    gateway = Some(IpAddr::V6(addr))
+                RouteAttribute::Gateway(_) | RouteAttribute::Via(_) => {
+                    gateway = RouteGateway::from_attribute(&attr);
                 }
-                RouteAttribute::Via(RouteVia::Inet(addr)) => gateway = Some(IpAddr::V4(addr)),
-                RouteAttribute::Via(RouteVia::Inet6(addr)) => gateway = Some(IpAddr::V6(addr)),
-                RouteAttribute::NewDestination(_) => {}
+                RouteAttribute::EncapType(value) => encap_type = value,
+                RouteAttribute::Encap(values) => encap = values,
                 _ => {}
             }
         }
@@ -719,9 +3366,12 @@ fn convert_multipath(paths: Vec<RouteNextHop>) -> Vec<RouteNextHopInfo> {
 
         result.push(RouteNextHopInfo {
             if_id,
+            if_name: None,
             gateway,
             weight,
-            flags: path.flags,
+            flags: path.flags & settable_nexthop_flags(),
+            status: RouteStatus::from_nexthop_flags(path.flags),
+            encap: decode_encap(encap_type, encap),
         });
     }
     result
@@ -731,23 +3381,17 @@ fn build_multipath_v4(entries: &[RouteNextHopInfo]) -> Option<Vec<RouteNextHop>>
     let mut nexthops = Vec::new();
     for entry in entries {
         let mut route_entry = RouteNextHop::default();
-        route_entry.flags = entry.flags;
+        route_entry.flags = entry.flags & settable_nexthop_flags();
         route_entry.hops = weight_to_hops(entry.weight);
         route_entry.interface_index = entry.if_id.unwrap_or(0);
         route_entry.attributes = Vec::new();
 
-        match entry.gateway {
-            Some(IpAddr::V4(addr)) => {
-                route_entry
-                    .attributes
-                    .push(RouteAttribute::Gateway(RouteAddress::Inet(addr)));
-            }
-            Some(IpAddr::V6(addr)) => {
-                route_entry
-                    .attributes
-                    .push(RouteAttribute::Via(RouteVia::Inet6(addr)));
-            }
-            None => {}
+        if let Some(gw) = entry.gateway {
+            route_entry.attributes.push(gw.to_attribute(true));
+        }
+
+        if let Some(encap) = &entry.encap {
+            route_entry.attributes.extend(build_encap(encap));
         }
 
         nexthops.push(route_entry);
@@ -764,23 +3408,17 @@ fn build_multipath_v6(entries: &[RouteNextHopInfo]) -> Option<Vec<RouteNextHop>>
     let mut nexthops = Vec::new();
     for entry in entries {
         let mut route_entry = RouteNextHop::default();
-        route_entry.flags = entry.flags;
+        route_entry.flags = entry.flags & settable_nexthop_flags();
         route_entry.hops = weight_to_hops(entry.weight);
         route_entry.interface_index = entry.if_id.unwrap_or(0);
         route_entry.attributes = Vec::new();
 
-        match entry.gateway {
-            Some(IpAddr::V6(addr)) => {
-                route_entry
-                    .attributes
-                    .push(RouteAttribute::Gateway(RouteAddress::Inet6(addr)));
-            }
-            Some(IpAddr::V4(addr)) => {
-                route_entry
-                    .attributes
-                    .push(RouteAttribute::Via(RouteVia::Inet(addr)));
-            }
-            None => {}
+        if let Some(gw) = entry.gateway {
+            route_entry.attributes.push(gw.to_attribute(false));
+        }
+
+        if let Some(encap) = &entry.encap {
+            route_entry.attributes.extend(build_encap(encap));
         }
 
         nexthops.push(route_entry);
@@ -793,6 +3431,184 @@ fn build_multipath_v6(entries: &[RouteNextHopInfo]) -> Option<Vec<RouteNextHop>>
     }
 }
 
+// Dead, Linkdown, Offload and the like are state reported by the kernel and
+// rejected on add, so decoded nexthops can be installed again as they are.
+fn settable_nexthop_flags() -> RouteNextHopFlags {
+    RouteNextHopFlags::Onlink | RouteNextHopFlags::Pervasive
+}
+
 fn weight_to_hops(weight: u32) -> u8 {
     weight.saturating_sub(1).min(u8::MAX as u32) as u8
 }
+
+#[cfg(test)]
+mod tests {
+    use netlink_packet_core::{Emitable, NlaBuffer, Parseable};
+    use netlink_packet_route::route::RouteMessageBuffer;
+
+    use super::*;
+
+    fn parse_srh(mode: Seg6Mode, segments: &[Ipv6Addr]) -> (Seg6Mode, Vec<Ipv6Addr>) {
+        let nla = DefaultNla::new(SEG6_IPTUNNEL_SRH, seg6_srh(mode, segments));
+        let mut buf = vec![0; nla.buffer_len()];
+        nla.emit(&mut buf);
+        match RouteSeg6IpTunnel::parse(&NlaBuffer::new(&buf)).unwrap() {
+            RouteSeg6IpTunnel::Seg6(header) => (header.mode, header.segments),
+            other => panic!("unexpected seg6 attribute {:?}", other),
+        }
+    }
+
+    fn segments(count: u16) -> Vec<Ipv6Addr> {
+        (1..=count)
+            .map(|i| Ipv6Addr::new(0x2001, 0xdb8, i, 0, 0, 0, 0, 1))
+            .collect()
+    }
+
+    #[test]
+    fn nexthop_id_replaces_resolved_nexthop() {
+        // The kernel reports the resolved interface and gateway along with
+        // the nexthop id.
+        let mut message = RouteMessageBuilder::<Ipv4Addr>::new()
+            .destination_prefix(Ipv4Addr::new(192, 0, 2, 0), 24)
+            .output_interface(2)
+            .gateway(Ipv4Addr::new(198, 51, 100, 1))
+            .build();
+        message.attributes.push(nexthop_id_attribute(7));
+
+        let route = decode_ipv4_route(message).unwrap();
+        assert_eq!(route.nexthop_id, Some(7));
+        assert!(route.extra.is_empty());
+
+        let rebuilt = build_ipv4_route_message(&route);
+        let nexthop_attributes: Vec<_> = rebuilt
+            .attributes
+            .iter()
+            .filter(|attr| {
+                matches!(
+                    attr,
+                    RouteAttribute::Oif(_)
+                        | RouteAttribute::Gateway(_)
+                        | RouteAttribute::MultiPath(_)
+                        | RouteAttribute::Other(_)
+                )
+            })
+            .collect();
+        assert_eq!(nexthop_attributes, vec![&nexthop_id_attribute(7)]);
+        assert!(route.canonical_eq(&Ipv4Route {
+            if_id: None,
+            gateway: None,
+            ..route.clone()
+        }));
+    }
+
+    #[test]
+    fn same_family_via_is_sent_as_gateway() {
+        let v4 = Ipv4Addr::new(192, 0, 2, 1);
+        let v6 = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        assert_eq!(
+            RouteGateway::Via(IpAddr::V4(v4)).to_attribute(true),
+            RouteAttribute::Gateway(RouteAddress::Inet(v4))
+        );
+        assert_eq!(
+            RouteGateway::Via(IpAddr::V6(v6)).to_attribute(false),
+            RouteAttribute::Gateway(RouteAddress::Inet6(v6))
+        );
+        assert_eq!(
+            RouteGateway::Via(IpAddr::V6(v6)).to_attribute(true),
+            RouteAttribute::Via(RouteVia::Inet6(v6))
+        );
+    }
+
+    #[test]
+    fn ipv6_route_rejects_ipv4_nexthop() {
+        let message = RouteMessageBuilder::<Ipv6Addr>::new()
+            .destination_prefix(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 64)
+            .build();
+        let mut route = decode_ipv6_route(message).unwrap();
+        route.gateway = Some(RouteGateway::Via(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))));
+        let err = validate_ipv6_gateways(&route).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        route.gateway = Some(RouteGateway::Via(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+        assert!(validate_ipv6_gateways(&route).is_ok());
+    }
+
+    #[test]
+    fn mpls_multipath_round_trip() {
+        let route = MplsRoute {
+            label: 100,
+            out_labels: Vec::new(),
+            via: None,
+            if_id: None,
+            protocol: Some(RouteProtocol::Static),
+            nexthops: vec![
+                MplsNextHop {
+                    out_labels: vec![200, 300],
+                    via: Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))),
+                    if_id: Some(2),
+                },
+                MplsNextHop {
+                    out_labels: Vec::new(),
+                    via: Some(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))),
+                    if_id: Some(3),
+                },
+            ],
+        };
+        let message = build_mpls_route_message(&route);
+        let mut buf = vec![0; message.buffer_len()];
+        message.emit(&mut buf);
+        let parsed = RouteMessage::parse(&RouteMessageBuffer::new(&buf)).unwrap();
+        assert_eq!(decode_mpls_route(parsed), Some(route));
+    }
+
+    #[test]
+    fn metrics_lock_round_trip() {
+        let message = RouteMessageBuilder::<Ipv4Addr>::new()
+            .destination_prefix(Ipv4Addr::new(192, 0, 2, 0), 24)
+            .build();
+        let mut route = decode_ipv4_route(message).unwrap();
+        route.mtu = Some(1400);
+        let locks = |route: &Ipv4Route| {
+            let message = build_ipv4_route_message(route);
+            let rebuilt = decode_ipv4_route(message).unwrap();
+            (rebuilt.metrics.lock, rebuilt.mtu)
+        };
+        // An MTU set without an explicit lock is installed locked.
+        assert_eq!(locks(&route), (Some(1 << RTAX_MTU), Some(1400)));
+
+        // An unlocked MTU read back from the kernel stays unlocked.
+        route.metrics.lock = Some(0);
+        assert_eq!(locks(&route), (Some(0), Some(1400)));
+
+        // Other lock bits survive.
+        route.metrics.lock = Some(1 << 8 | 1 << RTAX_MTU);
+        assert_eq!(locks(&route), (Some(1 << 8 | 1 << RTAX_MTU), Some(1400)));
+
+        let mut wanted = route.clone();
+        wanted.metrics.lock = None;
+        route.metrics.lock = Some(1 << RTAX_MTU);
+        assert!(wanted.canonical_eq(&route));
+    }
+
+    #[test]
+    fn seg6_srh_round_trip() {
+        for mode in [Seg6Mode::Encap, Seg6Mode::Inline] {
+            for count in [1, 3] {
+                let segments = segments(count);
+                assert_eq!(parse_srh(mode, &segments), (mode, segments));
+            }
+        }
+    }
+
+    #[test]
+    fn seg6_srh_header() {
+        let srh = seg6_srh(Seg6Mode::Inline, &segments(3));
+        // Inline mode adds a slot for the original destination.
+        assert_eq!(srh.len(), 12 + 16 * 4);
+        assert_eq!(&srh[4..12], &[0, 8, IPV6_SRCRT_TYPE_4, 3, 3, 0, 0, 0]);
+        assert_eq!(&srh[12..28], &Ipv6Addr::UNSPECIFIED.octets());
+
+        let srh = seg6_srh(Seg6Mode::Encap, &segments(1));
+        assert_eq!(srh.len(), 12 + 16);
+        assert_eq!(&srh[4..12], &[0, 2, IPV6_SRCRT_TYPE_4, 0, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,287 @@
+#![allow(unreachable_patterns)]
+
+use std::io::{self, ErrorKind};
+use std::net::IpAddr;
+
+use ftth_common::channel::{AsyncWorldClient, AsyncWorldServer};
+use futures::TryStreamExt;
+use tracing::warn;
+
+use netlink_packet_route::AddressFamily;
+use netlink_packet_route::route::RouteHeader;
+use netlink_packet_route::rule::{RuleAttribute, RuleMessage};
+use rtnetlink::IpVersion;
+
+pub use netlink_packet_route::rule::RuleAction;
+
+use crate::IpNet;
+use crate::error::RtnlError;
+
+pub(crate) type Client = AsyncWorldClient<RtnlRuleRequest, RtnlRuleResponse>;
+pub(crate) type Server = AsyncWorldServer<RtnlRuleRequest, RtnlRuleResponse>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RuleFamily {
+    Ipv4,
+    Ipv6,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingRule {
+    pub family: RuleFamily,
+    pub priority: Option<u32>,
+    pub source: Option<IpNet>,
+    pub destination: Option<IpNet>,
+    pub table: Option<u32>,
+    pub fw_mark: Option<u32>,
+    pub iif: Option<String>,
+    pub oif: Option<String>,
+    pub action: RuleAction,
+}
+
+impl RoutingRule {
+    pub fn new(family: RuleFamily) -> Self {
+        Self {
+            family,
+            priority: None,
+            source: None,
+            destination: None,
+            table: None,
+            fw_mark: None,
+            iif: None,
+            oif: None,
+            action: RuleAction::ToTable,
+        }
+    }
+
+    pub fn from_source(source: IpNet, table: u32) -> Self {
+        let family = match source {
+            IpNet::V4(_) => RuleFamily::Ipv4,
+            IpNet::V6(_) => RuleFamily::Ipv6,
+        };
+        Self {
+            source: Some(source.trunc()),
+            table: Some(table),
+            ..Self::new(family)
+        }
+    }
+}
+
+impl From<&RoutingRule> for RuleMessage {
+    fn from(rule: &RoutingRule) -> Self {
+        let mut message = RuleMessage::default();
+        message.header.family = match rule.family {
+            RuleFamily::Ipv4 => AddressFamily::Inet,
+            RuleFamily::Ipv6 => AddressFamily::Inet6,
+        };
+        message.header.action = rule.action;
+        message.header.table = RouteHeader::RT_TABLE_UNSPEC;
+
+        if let Some(table) = rule.table {
+            if table > 255 {
+                message.attributes.push(RuleAttribute::Table(table));
+            } else {
+                message.header.table = table as u8;
+            }
+        }
+        if let Some(priority) = rule.priority {
+            message.attributes.push(RuleAttribute::Priority(priority));
+        }
+        if let Some(source) = rule.source {
+            message.header.src_len = source.prefix_len();
+            message
+                .attributes
+                .push(RuleAttribute::Source(source.addr()));
+        }
+        if let Some(destination) = rule.destination {
+            message.header.dst_len = destination.prefix_len();
+            message
+                .attributes
+                .push(RuleAttribute::Destination(destination.addr()));
+        }
+        if let Some(fw_mark) = rule.fw_mark {
+            message.attributes.push(RuleAttribute::FwMark(fw_mark));
+        }
+        if let Some(iif) = &rule.iif {
+            message.attributes.push(RuleAttribute::Iifname(iif.clone()));
+        }
+        if let Some(oif) = &rule.oif {
+            message.attributes.push(RuleAttribute::Oifname(oif.clone()));
+        }
+        message
+    }
+}
+
+impl TryFrom<RuleMessage> for RoutingRule {
+    type Error = io::Error;
+
+    fn try_from(message: RuleMessage) -> io::Result<Self> {
+        rule_from_message(&message)
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "Unsupported rule family"))
+    }
+}
+
+fn rule_from_message(message: &RuleMessage) -> Option<RoutingRule> {
+    let family = match message.header.family {
+        AddressFamily::Inet => RuleFamily::Ipv4,
+        AddressFamily::Inet6 => RuleFamily::Ipv6,
+        _ => return None,
+    };
+    let mut rule = RoutingRule::new(family);
+    rule.action = message.header.action;
+    if message.header.table != RouteHeader::RT_TABLE_UNSPEC {
+        rule.table = Some(message.header.table as u32);
+    }
+
+    for attr in message.attributes.iter() {
+        match attr {
+            RuleAttribute::Table(table) => rule.table = Some(*table),
+            RuleAttribute::Priority(priority) => rule.priority = Some(*priority),
+            RuleAttribute::Source(addr) => {
+                rule.source = prefix(*addr, message.header.src_len);
+            }
+            RuleAttribute::Destination(addr) => {
+                rule.destination = prefix(*addr, message.header.dst_len);
+            }
+            RuleAttribute::FwMark(mark) => rule.fw_mark = Some(*mark),
+            RuleAttribute::Iifname(name) => rule.iif = Some(name.clone()),
+            RuleAttribute::Oifname(name) => rule.oif = Some(name.clone()),
+            _ => {}
+        }
+    }
+    Some(rule)
+}
+
+fn prefix(addr: IpAddr, prefix_len: u8) -> Option<IpNet> {
+    IpNet::new(addr, prefix_len).ok()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RtnlRuleRequest {
+    Add(RoutingRule),
+    Delete(RoutingRule),
+    List(RuleFamily),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RtnlRuleResponse {
+    Success,
+    Failed,
+    NotImplemented,
+    NotFound,
+    Rules(Vec<RoutingRule>),
+    Error(RtnlError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RtnlRuleClient {
+    client: Client,
+}
+
+impl RtnlRuleClient {
+    pub(crate) fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub fn send_raw(&self, request: RtnlRuleRequest) -> io::Result<RtnlRuleResponse> {
+        self.client.send_request(request)
+    }
+
+    pub fn add(&self, rule: RoutingRule) -> io::Result<()> {
+        let res = self.client.send_request(RtnlRuleRequest::Add(rule))?;
+        handle_rule_response("Rule add", res)
+    }
+
+    pub fn delete(&self, rule: RoutingRule) -> io::Result<()> {
+        let res = self.client.send_request(RtnlRuleRequest::Delete(rule))?;
+        handle_rule_response("Rule delete", res)
+    }
+
+    pub fn list(&self, family: RuleFamily) -> io::Result<Vec<RoutingRule>> {
+        match self.client.send_request(RtnlRuleRequest::List(family))? {
+            RtnlRuleResponse::Rules(rules) => Ok(rules),
+            RtnlRuleResponse::Error(err) => Err(err.into_io_error("Rule list")),
+            other => Err(io::Error::other(format!(
+                "Unexpected response for rule list: {:?}",
+                other
+            ))),
+        }
+    }
+}
+
+fn handle_rule_response(operation: &str, response: RtnlRuleResponse) -> io::Result<()> {
+    match response {
+        RtnlRuleResponse::Success => Ok(()),
+        RtnlRuleResponse::NotFound => Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("{}: rule not found", operation),
+        )),
+        RtnlRuleResponse::Failed => Err(io::Error::other(format!("{} failed", operation))),
+        RtnlRuleResponse::NotImplemented => Err(io::Error::new(
+            ErrorKind::Unsupported,
+            format!("{} is not implemented", operation),
+        )),
+        RtnlRuleResponse::Error(err) => Err(err.into_io_error(operation)),
+        other => Err(io::Error::other(format!(
+            "{} returned unexpected response: {:?}",
+            operation, other
+        ))),
+    }
+}
+
+pub(crate) async fn run_server(mut server: Server, handle: rtnetlink::RuleHandle) {
+    while let Some((req, respond)) = server.accept().await {
+        let response = match req {
+            RtnlRuleRequest::Add(rule) => add_rule(&handle, &rule).await,
+            RtnlRuleRequest::Delete(rule) => delete_rule(&handle, &rule).await,
+            RtnlRuleRequest::List(family) => list_rules(&handle, family).await,
+        };
+        respond(response);
+    }
+}
+
+async fn add_rule(handle: &rtnetlink::RuleHandle, rule: &RoutingRule) -> RtnlRuleResponse {
+    let mut request = handle.add();
+    *request.message_mut() = RuleMessage::from(rule);
+    map_rule_result("Rule add", request.execute().await)
+}
+
+async fn delete_rule(handle: &rtnetlink::RuleHandle, rule: &RoutingRule) -> RtnlRuleResponse {
+    let result = handle.del(RuleMessage::from(rule)).execute().await;
+    map_rule_result("Rule delete", result)
+}
+
+fn map_rule_result(operation: &str, result: Result<(), rtnetlink::Error>) -> RtnlRuleResponse {
+    match result {
+        Ok(()) => RtnlRuleResponse::Success,
+        Err(rtnetlink::Error::NetlinkError(err_msg)) => {
+            if err_msg.to_io().kind() == ErrorKind::NotFound {
+                return RtnlRuleResponse::NotFound;
+            }
+            warn!("{} failed: {}", operation, err_msg);
+            RtnlRuleResponse::Error(RtnlError::from_netlink(&err_msg))
+        }
+        Err(err) => {
+            warn!("{} failed: {}", operation, err);
+            RtnlRuleResponse::Error(RtnlError::from_rtnetlink(&err))
+        }
+    }
+}
+
+async fn list_rules(handle: &rtnetlink::RuleHandle, family: RuleFamily) -> RtnlRuleResponse {
+    let version = match family {
+        RuleFamily::Ipv4 => IpVersion::V4,
+        RuleFamily::Ipv6 => IpVersion::V6,
+    };
+    match handle.get(version).execute().try_collect::<Vec<_>>().await {
+        Ok(messages) => {
+            RtnlRuleResponse::Rules(messages.iter().filter_map(rule_from_message).collect())
+        }
+        Err(err) => {
+            warn!("Rule list failed: {}", err);
+            RtnlRuleResponse::Error(RtnlError::from_rtnetlink(&err))
+        }
+    }
+}
@@ -1,16 +1,22 @@
 #![allow(unreachable_patterns)]
 
+use std::collections::HashMap;
 use std::io::{self, ErrorKind};
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use ftth_common::channel::{AsyncWorldClient, AsyncWorldServer};
 use futures::TryStreamExt;
-use netlink_packet_core::{DefaultNla, Nla};
+use futures::future::join_all;
+use netlink_packet_core::{DefaultNla, ErrorMessage, Nla};
 use netlink_packet_route::link::{
-    InfoData, InfoGreTap, InfoGreTap6, InfoGreTun, InfoGreTun6, InfoKind, InfoVlan, LinkMessage,
+    InfoData, InfoGeneve, InfoGreTap, InfoGreTap6, InfoGreTun, InfoGreTun6, InfoKind, InfoMacSec,
+    InfoSitTun, InfoVlan, InfoVxlan, LinkAttribute, LinkInfo, LinkMessage, MacSecCipherId,
 };
 use rtnetlink::{LinkMessageBuilder, LinkUnspec};
 
+use crate::error::RtnlError;
+use crate::{Ipv4Net, Ipv6Net};
+
 pub(crate) type Client =
     AsyncWorldClient<RtnlVirtualInterfaceRequest, RtnlVirtualInterfaceResponse>;
 pub(crate) type Server =
@@ -20,9 +26,11 @@ pub(crate) type Server =
 #[non_exhaustive]
 pub enum RtnlVirtualInterfaceRequest {
     Create(VirtualInterfaceSpec),
+    CreateMany(Vec<VirtualInterfaceSpec>),
     Configure(VirtualInterfaceUpdate),
     Delete(VirtualInterfaceDelete),
     GetIndexByName(String),
+    List(Option<VirtualInterfaceType>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -32,6 +40,10 @@ pub enum RtnlVirtualInterfaceResponse {
     Failed,
     NotFound,
     Index(u32),
+    List(Vec<VirtualInterfaceInfo>),
+    // One response per spec of `CreateMany`, in order.
+    Batch(Vec<RtnlVirtualInterfaceResponse>),
+    Error(RtnlError),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -44,14 +56,81 @@ impl RtnlVirtualInterfaceClient {
         Self { client }
     }
 
-    pub fn create(&self, spec: VirtualInterfaceSpec) -> io::Result<()> {
+    pub fn send_raw(
+        &self,
+        request: RtnlVirtualInterfaceRequest,
+    ) -> io::Result<RtnlVirtualInterfaceResponse> {
+        self.client.send_request(request)
+    }
+
+    // Returns the ifindex of the new interface, resolved by the server right
+    // after the kernel acknowledged the creation.
+    pub fn create(&self, spec: VirtualInterfaceSpec) -> io::Result<u32> {
+        validate_create_kind(&spec.kind)?;
         let res = self
             .client
             .send_request(RtnlVirtualInterfaceRequest::Create(spec))?;
-        handle_basic_response("Create virtual interface", res)
+        handle_create_response(res)
+    }
+
+    // Sends the creation requests for all of `specs` without waiting for
+    // each one to be acknowledged, then resolves the new ifindexes from a
+    // single link dump. Results are in the order of `specs`; a failure on
+    // one interface does not stop the others. Invalid specs are rejected
+    // here and not sent.
+    pub fn create_many(
+        &self,
+        specs: Vec<VirtualInterfaceSpec>,
+    ) -> io::Result<Vec<io::Result<u32>>> {
+        let mut results = Vec::with_capacity(specs.len());
+        let mut valid = Vec::new();
+        for spec in specs {
+            match validate_create_kind(&spec.kind) {
+                Ok(()) => {
+                    results.push(None);
+                    valid.push(spec);
+                }
+                Err(err) => results.push(Some(Err(err))),
+            }
+        }
+        if valid.is_empty() {
+            return Ok(results.into_iter().flatten().collect());
+        }
+        match self
+            .client
+            .send_request(RtnlVirtualInterfaceRequest::CreateMany(valid))?
+        {
+            RtnlVirtualInterfaceResponse::Batch(responses) => {
+                let mut responses = responses.into_iter().map(handle_create_response);
+                Ok(results
+                    .into_iter()
+                    .map(|result| {
+                        result.unwrap_or_else(|| {
+                            responses.next().unwrap_or_else(|| {
+                                Err(io::Error::other(
+                                    "Create virtual interfaces returned too few results",
+                                ))
+                            })
+                        })
+                    })
+                    .collect())
+            }
+            RtnlVirtualInterfaceResponse::Error(err) => {
+                Err(err.into_io_error("Create virtual interfaces"))
+            }
+            other => Err(io::Error::other(format!(
+                "Unexpected response while creating virtual interfaces: {:?}",
+                other
+            ))),
+        }
     }
 
     pub fn configure(&self, update: VirtualInterfaceUpdate) -> io::Result<()> {
+        match &update.kind {
+            Some(VirtualInterfaceKind::Vxlan(cfg)) => validate_vxlan(cfg)?,
+            Some(VirtualInterfaceKind::Geneve(cfg)) => validate_geneve(cfg)?,
+            _ => {}
+        }
         let res = self
             .client
             .send_request(RtnlVirtualInterfaceRequest::Configure(update))?;
@@ -76,12 +155,82 @@ impl RtnlVirtualInterfaceClient {
                 ErrorKind::NotFound,
                 format!("Virtual interface {name} not found"),
             )),
+            RtnlVirtualInterfaceResponse::Error(err) => {
+                Err(err.into_io_error("Get virtual interface index"))
+            }
             other => Err(io::Error::other(format!(
                 "Unexpected response while fetching index: {:?}",
                 other
             ))),
         }
     }
+
+    // Creates the interface, or reconfigures an existing one of the same kind
+    // whose decoded configuration differs from `spec.kind`. Only the kind
+    // configuration is compared, after filling in what the kernel chooses
+    // for fields `spec` leaves unset; MTU, MAC address and master are applied
+    // on creation only.
+    pub fn ensure(&self, spec: VirtualInterfaceSpec) -> io::Result<(u32, EnsureAction)> {
+        let existing = self
+            .list(Some(spec.kind.interface_type()))?
+            .into_iter()
+            .find(|info| info.name == spec.name);
+
+        let Some(existing) = existing else {
+            let name = spec.name.clone();
+            return match self.create(spec) {
+                Ok(if_id) => Ok((if_id, EnsureAction::Created)),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => Err(io::Error::new(
+                    ErrorKind::AlreadyExists,
+                    format!("Interface {name} exists with a different kind"),
+                )),
+                Err(err) => Err(err),
+            };
+        };
+        Ok((existing.if_id, self.ensure_listed(spec, &existing)?))
+    }
+
+    // The update half of `ensure`, for an interface of the same kind already
+    // found by `list`, so that many interfaces can be reconciled against a
+    // single dump.
+    pub(crate) fn ensure_listed(
+        &self,
+        spec: VirtualInterfaceSpec,
+        existing: &VirtualInterfaceInfo,
+    ) -> io::Result<EnsureAction> {
+        if spec.kind.with_kernel_defaults(&existing.kind) == existing.kind {
+            return Ok(EnsureAction::Unchanged);
+        }
+        self.configure(VirtualInterfaceUpdate {
+            if_id: existing.if_id,
+            new_name: None,
+            kind: Some(spec.kind),
+            admin_up: Some(spec.admin_up),
+        })?;
+        Ok(EnsureAction::Updated)
+    }
+
+    // Lists tunnels and VLANs with their configuration decoded from a
+    // single link dump. Devices of kinds this module cannot create are
+    // skipped.
+    pub fn list(
+        &self,
+        kind_filter: Option<VirtualInterfaceType>,
+    ) -> io::Result<Vec<VirtualInterfaceInfo>> {
+        match self
+            .client
+            .send_request(RtnlVirtualInterfaceRequest::List(kind_filter))?
+        {
+            RtnlVirtualInterfaceResponse::List(list) => Ok(list),
+            RtnlVirtualInterfaceResponse::Error(err) => {
+                Err(err.into_io_error("List virtual interfaces"))
+            }
+            other => Err(io::Error::other(format!(
+                "Unexpected response while listing virtual interfaces: {:?}",
+                other
+            ))),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -89,13 +238,28 @@ pub struct VirtualInterfaceSpec {
     pub name: String,
     pub kind: VirtualInterfaceKind,
     pub admin_up: bool,
+    pub num_tx_queues: Option<u32>,
+    pub num_rx_queues: Option<u32>,
+    pub mtu: Option<u32>,
+    pub mac_addr: Option<[u8; 6]>,
+    // Bridge or bond to enslave the new interface to.
+    pub master: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnsureAction {
+    Created,
+    Updated,
+    Unchanged,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct VirtualInterfaceUpdate {
     pub if_id: u32,
     pub new_name: Option<String>,
-    pub kind: VirtualInterfaceKind,
+    // When unset, no IFLA_LINKINFO is sent and the tunnel parameters are
+    // left as they are.
+    pub kind: Option<VirtualInterfaceKind>,
     pub admin_up: Option<bool>,
 }
 
@@ -113,7 +277,132 @@ pub enum VirtualInterfaceKind {
     Ip6Gretap(Gre6Config),
     IpIp(IpIpConfig),
     Ip6Tnl(Ip6TnlConfig),
+    Sit(SitConfig),
     Vlan(VlanConfig),
+    Vxlan(VxlanConfig),
+    Geneve(GeneveConfig),
+    Macsec(MacsecConfig),
+}
+
+impl VirtualInterfaceKind {
+    pub fn interface_type(&self) -> VirtualInterfaceType {
+        match self {
+            VirtualInterfaceKind::Gre(_) => VirtualInterfaceType::Gre,
+            VirtualInterfaceKind::Gretap(_) => VirtualInterfaceType::Gretap,
+            VirtualInterfaceKind::Ip6Gre(_) => VirtualInterfaceType::Ip6Gre,
+            VirtualInterfaceKind::Ip6Gretap(_) => VirtualInterfaceType::Ip6Gretap,
+            VirtualInterfaceKind::IpIp(_) => VirtualInterfaceType::IpIp,
+            VirtualInterfaceKind::Ip6Tnl(_) => VirtualInterfaceType::Ip6Tnl,
+            VirtualInterfaceKind::Sit(_) => VirtualInterfaceType::Sit,
+            VirtualInterfaceKind::Vlan(_) => VirtualInterfaceType::Vlan,
+            VirtualInterfaceKind::Vxlan(_) => VirtualInterfaceType::Vxlan,
+            VirtualInterfaceKind::Geneve(_) => VirtualInterfaceType::Geneve,
+            VirtualInterfaceKind::Macsec(_) => VirtualInterfaceType::Macsec,
+        }
+    }
+
+    // Takes the values the kernel picks on its own from `existing` for the
+    // fields left unset here, and puts GRE keys in the form they are decoded
+    // in, so that a spec compares equal to the decoded configuration of the
+    // interface it created.
+    fn with_kernel_defaults(&self, existing: &VirtualInterfaceKind) -> VirtualInterfaceKind {
+        let mut kind = self.clone();
+        match (&mut kind, existing) {
+            (VirtualInterfaceKind::Gre(cfg), VirtualInterfaceKind::Gre(_))
+            | (VirtualInterfaceKind::Gretap(cfg), VirtualInterfaceKind::Gretap(_)) => {
+                (cfg.key, cfg.ikey, cfg.okey) = reported_gre_keys(cfg.key, cfg.ikey, cfg.okey);
+            }
+            (VirtualInterfaceKind::Ip6Gre(cfg), VirtualInterfaceKind::Ip6Gre(existing))
+            | (VirtualInterfaceKind::Ip6Gretap(cfg), VirtualInterfaceKind::Ip6Gretap(existing)) => {
+                (cfg.key, cfg.ikey, cfg.okey) = reported_gre_keys(cfg.key, cfg.ikey, cfg.okey);
+                cfg.flags = cfg
+                    .flags
+                    .with_kernel_hop_limit(cfg.hop_limit, existing.flags);
+            }
+            (VirtualInterfaceKind::Ip6Tnl(cfg), VirtualInterfaceKind::Ip6Tnl(existing)) => {
+                cfg.flags = cfg
+                    .flags
+                    .with_kernel_hop_limit(cfg.hop_limit, existing.flags);
+                cfg.mode = cfg.mode.or(existing.mode);
+            }
+            (VirtualInterfaceKind::Sit(cfg), VirtualInterfaceKind::Sit(existing)) => {
+                // Every sit device reports the 6to4 prefix unless 6RD is
+                // configured.
+                cfg.ip6rd_prefix = cfg.ip6rd_prefix.or(existing
+                    .ip6rd_prefix
+                    .filter(|prefix| *prefix == sit_6to4_prefix()));
+            }
+            (VirtualInterfaceKind::Vxlan(cfg), VirtualInterfaceKind::Vxlan(existing)) => {
+                cfg.dstport = cfg.dstport.or(existing.dstport);
+            }
+            (VirtualInterfaceKind::Geneve(cfg), VirtualInterfaceKind::Geneve(existing)) => {
+                cfg.dstport = cfg.dstport.or(existing.dstport);
+            }
+            (VirtualInterfaceKind::Macsec(cfg), VirtualInterfaceKind::Macsec(existing)) => {
+                cfg.sci = cfg.sci.or(existing.sci);
+                // The port is only reported as part of the SCI.
+                cfg.port = existing.port;
+                cfg.cipher_suite = cfg.cipher_suite.or(existing.cipher_suite);
+            }
+            _ => {}
+        }
+        kind
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VirtualInterfaceType {
+    Gre,
+    Gretap,
+    Ip6Gre,
+    Ip6Gretap,
+    IpIp,
+    Ip6Tnl,
+    Sit,
+    Vlan,
+    Vxlan,
+    Geneve,
+    Macsec,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirtualInterfaceInfo {
+    pub if_id: u32,
+    pub name: String,
+    pub kind: VirtualInterfaceKind,
+}
+
+// GRE header options for one direction. The key bit is also set
+// implicitly whenever a key is configured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GreFlags {
+    pub csum: bool,
+    pub seq: bool,
+    pub key: bool,
+}
+
+impl GreFlags {
+    fn bits(self, key: Option<u32>) -> u16 {
+        let mut bits = 0;
+        if self.csum {
+            bits |= GRE_CSUM;
+        }
+        if self.key || key.is_some() {
+            bits |= GRE_KEY;
+        }
+        if self.seq {
+            bits |= GRE_SEQ;
+        }
+        bits
+    }
+
+    fn from_bits(bits: u16) -> Self {
+        Self {
+            csum: bits & GRE_CSUM != 0,
+            seq: bits & GRE_SEQ != 0,
+            key: bits & GRE_KEY != 0,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -122,11 +411,19 @@ pub struct GreConfig {
     pub remote: Ipv4Addr,
     pub ttl: Option<u8>,
     pub tos: Option<u8>,
+    // Sets both directions; `ikey`/`okey` take precedence when present.
     pub key: Option<u32>,
+    pub ikey: Option<u32>,
+    pub okey: Option<u32>,
+    pub iflags: GreFlags,
+    pub oflags: GreFlags,
     pub encap_limit: Option<u8>,
     pub pmtudisc: bool,
     pub ignore_df: bool,
     pub link: Option<u32>,
+    // Metadata-collect mode: endpoints and keys come from per-packet tunnel
+    // metadata (tc/lwt), so the fields above other than `link` are ignored.
+    pub external: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -135,11 +432,18 @@ pub struct Gre6Config {
     pub remote: Ipv6Addr,
     pub hop_limit: Option<u8>,
     pub traffic_class: Option<u8>,
+    // Sets both directions; `ikey`/`okey` take precedence when present.
     pub key: Option<u32>,
+    pub ikey: Option<u32>,
+    pub okey: Option<u32>,
+    pub iflags: GreFlags,
+    pub oflags: GreFlags,
     pub encap_limit: Option<u8>,
     pub pmtudisc: bool,
     pub ignore_df: bool,
     pub link: Option<u32>,
+    pub flags: Ip6TunnelFlags,
+    pub external: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -151,6 +455,9 @@ pub struct IpIpConfig {
     pub encap_limit: Option<u8>,
     pub pmtudisc: bool,
     pub link: Option<u32>,
+    // Metadata-collect mode: endpoints come from per-packet tunnel metadata,
+    // so the fields above other than `link` are ignored.
+    pub external: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -163,6 +470,96 @@ pub struct Ip6TnlConfig {
     pub encap_limit: Option<u8>,
     pub pmtudisc: bool,
     pub link: Option<u32>,
+    pub mode: Option<Ip6TnlMode>,
+    pub flags: Ip6TunnelFlags,
+    pub external: bool,
+}
+
+// Per-packet inheritance options shared by ip6gre and ip6tnl, so that QoS
+// markings of the inner packet survive encapsulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Ip6TunnelFlags {
+    pub inherit_tclass: bool,
+    pub inherit_flowlabel: bool,
+    // Not a kernel flag: the kernel inherits the hop limit when it is 0,
+    // so this overrides the configured hop limit.
+    pub inherit_hop_limit: bool,
+    pub copy_dscp_on_decap: bool,
+}
+
+impl Ip6TunnelFlags {
+    fn bits(self) -> u32 {
+        let mut bits = 0;
+        if self.inherit_tclass {
+            bits |= IP6_TNL_F_USE_ORIG_TCLASS;
+        }
+        if self.inherit_flowlabel {
+            bits |= IP6_TNL_F_USE_ORIG_FLOWLABEL;
+        }
+        if self.copy_dscp_on_decap {
+            bits |= IP6_TNL_F_RCV_DSCP_COPY;
+        }
+        bits
+    }
+
+    fn from_bits(bits: u32, hop_limit: Option<u8>) -> Self {
+        Self {
+            inherit_tclass: bits & IP6_TNL_F_USE_ORIG_TCLASS != 0,
+            inherit_flowlabel: bits & IP6_TNL_F_USE_ORIG_FLOWLABEL != 0,
+            inherit_hop_limit: hop_limit == Some(0),
+            copy_dscp_on_decap: bits & IP6_TNL_F_RCV_DSCP_COPY != 0,
+        }
+    }
+
+    // Without a hop limit the kernel stores 0, which reads back as inherit.
+    fn with_kernel_hop_limit(self, hop_limit: Option<u8>, existing: Self) -> Self {
+        if hop_limit.is_none() && !self.inherit_hop_limit {
+            Self {
+                inherit_hop_limit: existing.inherit_hop_limit,
+                ..self
+            }
+        } else {
+            self
+        }
+    }
+
+    fn hop_limit(self, hop_limit: Option<u8>) -> Option<u8> {
+        if self.inherit_hop_limit {
+            Some(0)
+        } else {
+            hop_limit
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitConfig {
+    pub local: Ipv4Addr,
+    pub remote: Ipv4Addr,
+    pub ttl: Option<u8>,
+    pub tos: Option<u8>,
+    pub pmtudisc: bool,
+    pub link: Option<u32>,
+    pub ip6rd_prefix: Option<Ipv6Net>,
+    pub ip6rd_relay_prefix: Option<Ipv4Net>,
+    // No `external`: the kernel has no metadata-collect mode for sit.
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ip6TnlMode {
+    Any,
+    IpIp6,
+    Ip6Ip6,
+}
+
+impl Ip6TnlMode {
+    fn proto(self) -> u8 {
+        match self {
+            Ip6TnlMode::Any => 0,
+            Ip6TnlMode::IpIp6 => IPPROTO_IPIP,
+            Ip6TnlMode::Ip6Ip6 => IPPROTO_IPV6,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -171,7 +568,131 @@ pub struct VlanConfig {
     pub vlan_id: Option<u16>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct VxlanConfig {
+    pub vni: u32,
+    pub local: Option<IpAddr>,
+    // Unicast peer; mutually exclusive with `group`.
+    pub remote: Option<IpAddr>,
+    // Multicast group joined on the underlay `link`.
+    pub group: Option<IpAddr>,
+    pub dstport: Option<u16>,
+    pub learning: bool,
+    pub ttl: Option<u8>,
+    pub link: Option<u32>,
+    // Metadata-collect mode: VNI and peers come from tunnel metadata, so
+    // `vni`, `remote` and `group` are not sent.
+    pub external: bool,
+}
+
+impl VxlanConfig {
+    pub fn new(vni: u32) -> Self {
+        Self {
+            vni,
+            local: None,
+            remote: None,
+            group: None,
+            dstport: None,
+            learning: true,
+            ttl: None,
+            link: None,
+            external: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneveConfig {
+    pub vni: u32,
+    pub remote: Option<IpAddr>,
+    pub ttl: Option<u8>,
+    pub tos: Option<u8>,
+    pub dstport: Option<u16>,
+    // Metadata-collect mode: VNI and peer come from tunnel metadata, so
+    // `vni` and `remote` are not sent.
+    pub external: bool,
+}
+
+impl GeneveConfig {
+    pub fn new(vni: u32) -> Self {
+        Self {
+            vni,
+            remote: None,
+            ttl: None,
+            tos: None,
+            dstport: None,
+            external: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacsecCipherSuite {
+    GcmAes128,
+    GcmAes256,
+    GcmAesXpn128,
+    GcmAesXpn256,
+}
+
+impl MacsecCipherSuite {
+    fn to_cipher_id(self) -> MacSecCipherId {
+        match self {
+            MacsecCipherSuite::GcmAes128 => MacSecCipherId::GcmAes128,
+            MacsecCipherSuite::GcmAes256 => MacSecCipherId::GcmAes256,
+            MacsecCipherSuite::GcmAesXpn128 => MacSecCipherId::GcmAesXpn128,
+            MacsecCipherSuite::GcmAesXpn256 => MacSecCipherId::GcmAesXpn256,
+        }
+    }
+
+    fn from_cipher_id(id: MacSecCipherId) -> Option<Self> {
+        match u64::from(id) {
+            MACSEC_CIPHER_ID_GCM_AES_128 | MACSEC_DEFAULT_CIPHER_ID => {
+                Some(MacsecCipherSuite::GcmAes128)
+            }
+            MACSEC_CIPHER_ID_GCM_AES_256 => Some(MacsecCipherSuite::GcmAes256),
+            MACSEC_CIPHER_ID_GCM_AES_XPN_128 => Some(MacsecCipherSuite::GcmAesXpn128),
+            MACSEC_CIPHER_ID_GCM_AES_XPN_256 => Some(MacsecCipherSuite::GcmAesXpn256),
+            _ => None,
+        }
+    }
+}
+
+// Only the link itself is managed here; secure channels and keys are left
+// to an external key agreement daemon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacsecConfig {
+    pub link: Option<u32>,
+    // Explicit SCI; when unset the kernel derives it from the parent MAC
+    // address and `port`.
+    pub sci: Option<u64>,
+    pub port: Option<u16>,
+    pub cipher_suite: Option<MacsecCipherSuite>,
+    pub encrypt: bool,
+}
+
+impl MacsecConfig {
+    pub fn new(link: u32) -> Self {
+        Self {
+            link: Some(link),
+            sci: None,
+            port: None,
+            cipher_suite: None,
+            encrypt: true,
+        }
+    }
+}
+
+const VXLAN_VNI_MAX: u32 = 0x00ff_ffff;
+
+const MACSEC_DEFAULT_CIPHER_ID: u64 = 0x0080_0200_0100_0001;
+const MACSEC_CIPHER_ID_GCM_AES_128: u64 = 0x0080_c200_0100_0001;
+const MACSEC_CIPHER_ID_GCM_AES_256: u64 = 0x0080_c200_0100_0002;
+const MACSEC_CIPHER_ID_GCM_AES_XPN_128: u64 = 0x0080_c200_0100_0003;
+const MACSEC_CIPHER_ID_GCM_AES_XPN_256: u64 = 0x0080_c200_0100_0004;
+
 const IFLA_GRE_LINK: u16 = 1;
+const IFLA_GRE_IFLAGS: u16 = 2;
+const IFLA_GRE_OFLAGS: u16 = 3;
 const IFLA_GRE_IKEY: u16 = 4;
 const IFLA_GRE_OKEY: u16 = 5;
 const IFLA_GRE_LOCAL: u16 = 6;
@@ -180,8 +701,18 @@ const IFLA_GRE_TTL: u16 = 8;
 const IFLA_GRE_TOS: u16 = 9;
 const IFLA_GRE_PMTUDISC: u16 = 10;
 const IFLA_GRE_ENCAP_LIMIT: u16 = 11;
+const IFLA_GRE_FLAGS: u16 = 13;
+const IFLA_GRE_COLLECT_METADATA: u16 = 18;
 const IFLA_GRE_IGNORE_DF: u16 = 19;
 
+const GRE_CSUM: u16 = 0x8000;
+const GRE_KEY: u16 = 0x2000;
+const GRE_SEQ: u16 = 0x1000;
+
+const IP6_TNL_F_USE_ORIG_TCLASS: u32 = 0x2;
+const IP6_TNL_F_USE_ORIG_FLOWLABEL: u32 = 0x4;
+const IP6_TNL_F_RCV_DSCP_COPY: u32 = 0x10;
+
 const IFLA_IPTUN_LINK: u16 = 1;
 const IFLA_IPTUN_LOCAL: u16 = 2;
 const IFLA_IPTUN_REMOTE: u16 = 3;
@@ -189,15 +720,38 @@ const IFLA_IPTUN_TTL: u16 = 4;
 const IFLA_IPTUN_TOS: u16 = 5;
 const IFLA_IPTUN_ENCAP_LIMIT: u16 = 6;
 const IFLA_IPTUN_FLOWINFO: u16 = 7;
+const IFLA_IPTUN_FLAGS: u16 = 8;
+const IFLA_IPTUN_PROTO: u16 = 9;
 const IFLA_IPTUN_PMTUDISC: u16 = 10;
+const IFLA_IPTUN_6RD_PREFIX: u16 = 11;
+const IFLA_IPTUN_6RD_RELAY_PREFIX: u16 = 12;
+const IFLA_IPTUN_6RD_PREFIXLEN: u16 = 13;
+const IFLA_IPTUN_6RD_RELAY_PREFIXLEN: u16 = 14;
+const IFLA_IPTUN_COLLECT_METADATA: u16 = 19;
+
+const IPPROTO_IPIP: u8 = 4;
+const IPPROTO_IPV6: u8 = 41;
 
 const NLA_HEADER_LEN: usize = 4;
+const NLA_TYPE_MASK: u16 = 0x3fff;
 const NLA_ALIGNTO: usize = 4;
 
 fn align_nla(len: usize) -> usize {
     (len + NLA_ALIGNTO - 1) & !(NLA_ALIGNTO - 1)
 }
 
+fn handle_create_response(response: RtnlVirtualInterfaceResponse) -> io::Result<u32> {
+    match response {
+        RtnlVirtualInterfaceResponse::Index(index) => Ok(index),
+        other => {
+            handle_basic_response("Create virtual interface", other)?;
+            Err(io::Error::other(
+                "Create virtual interface returned no index",
+            ))
+        }
+    }
+}
+
 fn handle_basic_response(op: &str, response: RtnlVirtualInterfaceResponse) -> io::Result<()> {
     match response {
         RtnlVirtualInterfaceResponse::Success => Ok(()),
@@ -206,6 +760,7 @@ fn handle_basic_response(op: &str, response: RtnlVirtualInterfaceResponse) -> io
             format!("{}: target not found", op),
         )),
         RtnlVirtualInterfaceResponse::Failed => Err(io::Error::other(format!("{} failed", op))),
+        RtnlVirtualInterfaceResponse::Error(err) => Err(err.into_io_error(op)),
         other => Err(io::Error::other(format!(
             "{} returned unexpected response: {:?}",
             op, other
@@ -221,27 +776,47 @@ pub(crate) async fn run_server(mut server: Server, mut handle: rtnetlink::LinkHa
                     Ok(msg) => msg,
                     Err(err) => {
                         tracing::warn!("Failed to build virtual interface {}: {}", spec.name, err);
-                        respond(RtnlVirtualInterfaceResponse::Failed);
+                        respond(RtnlVirtualInterfaceResponse::Error(RtnlError::new(
+                            err.to_string(),
+                        )));
                         continue;
                     }
                 };
 
                 match handle.add(message).execute().await {
-                    Ok(()) => respond(RtnlVirtualInterfaceResponse::Success),
+                    Ok(()) => match resolve_index_by_name(&mut handle, &spec.name).await {
+                        Ok(Some(index)) => respond(RtnlVirtualInterfaceResponse::Index(index)),
+                        Ok(None) => respond(RtnlVirtualInterfaceResponse::NotFound),
+                        Err(err) => {
+                            tracing::warn!(
+                                "Failed to resolve created virtual interface {}: {}",
+                                spec.name,
+                                err
+                            );
+                            respond(RtnlVirtualInterfaceResponse::Failed);
+                        }
+                    },
                     Err(rtnetlink::Error::NetlinkError(err_msg)) => {
                         tracing::warn!(
                             "Netlink error creating virtual interface {}: {}",
                             spec.name,
                             err_msg
                         );
-                        respond(netlink_error_to_response(err_msg.to_io()));
+                        respond(netlink_error_to_response(&err_msg));
                     }
                     Err(err) => {
                         tracing::warn!("Failed to create virtual interface {}: {}", spec.name, err);
-                        respond(RtnlVirtualInterfaceResponse::Failed);
+                        respond(RtnlVirtualInterfaceResponse::Error(
+                            RtnlError::from_rtnetlink(&err),
+                        ));
                     }
                 }
             }
+            RtnlVirtualInterfaceRequest::CreateMany(specs) => {
+                respond(RtnlVirtualInterfaceResponse::Batch(
+                    create_many(&mut handle, &specs).await,
+                ));
+            }
             RtnlVirtualInterfaceRequest::Configure(update) => {
                 let message = match build_update_message(&update) {
                     Ok(msg) => msg,
@@ -251,7 +826,9 @@ pub(crate) async fn run_server(mut server: Server, mut handle: rtnetlink::LinkHa
                             update.if_id,
                             err
                         );
-                        respond(RtnlVirtualInterfaceResponse::Failed);
+                        respond(RtnlVirtualInterfaceResponse::Error(RtnlError::new(
+                            err.to_string(),
+                        )));
                         continue;
                     }
                 };
@@ -259,7 +836,7 @@ pub(crate) async fn run_server(mut server: Server, mut handle: rtnetlink::LinkHa
                 match handle.set(message).execute().await {
                     Ok(()) => respond(RtnlVirtualInterfaceResponse::Success),
                     Err(rtnetlink::Error::NetlinkError(err_msg)) => {
-                        respond(netlink_error_to_response(err_msg.to_io()));
+                        respond(netlink_error_to_response(&err_msg));
                     }
                     Err(err) => {
                         tracing::warn!(
@@ -267,30 +844,56 @@ pub(crate) async fn run_server(mut server: Server, mut handle: rtnetlink::LinkHa
                             update.if_id,
                             err
                         );
-                        respond(RtnlVirtualInterfaceResponse::Failed);
+                        respond(RtnlVirtualInterfaceResponse::Error(
+                            RtnlError::from_rtnetlink(&err),
+                        ));
                     }
                 }
             }
             RtnlVirtualInterfaceRequest::Delete(delete) => {
-                let result = match resolve_delete_target(&mut handle, &delete).await {
-                    Ok(index) => handle.del(index).execute().await.map_err(|err| match err {
-                        rtnetlink::Error::NetlinkError(e) => e.to_io(),
-                        other => io::Error::other(other.to_string()),
-                    }),
-                    Err(err) => Err(err),
+                let index = match resolve_delete_target(&mut handle, &delete).await {
+                    Ok(index) => index,
+                    Err(err) if err.kind() == ErrorKind::NotFound => {
+                        respond(RtnlVirtualInterfaceResponse::NotFound);
+                        continue;
+                    }
+                    Err(err) => {
+                        tracing::warn!("Failed to delete virtual interface: {}", err);
+                        respond(RtnlVirtualInterfaceResponse::Failed);
+                        continue;
+                    }
                 };
 
-                match result {
+                match handle.del(index).execute().await {
                     Ok(()) => respond(RtnlVirtualInterfaceResponse::Success),
-                    Err(err) if err.kind() == ErrorKind::NotFound => {
-                        respond(RtnlVirtualInterfaceResponse::NotFound)
+                    Err(rtnetlink::Error::NetlinkError(err_msg)) => {
+                        tracing::warn!("Failed to delete virtual interface: {}", err_msg);
+                        respond(netlink_error_to_response(&err_msg));
                     }
                     Err(err) => {
                         tracing::warn!("Failed to delete virtual interface: {}", err);
-                        respond(RtnlVirtualInterfaceResponse::Failed);
+                        respond(RtnlVirtualInterfaceResponse::Error(
+                            RtnlError::from_rtnetlink(&err),
+                        ));
                     }
                 }
             }
+            RtnlVirtualInterfaceRequest::List(kind_filter) => {
+                match handle.get().execute().try_collect::<Vec<_>>().await {
+                    Ok(messages) => respond(RtnlVirtualInterfaceResponse::List(
+                        messages
+                            .iter()
+                            .filter_map(virtual_interface_from_message)
+                            .filter(|info| {
+                                kind_filter.is_none_or(|kind| info.kind.interface_type() == kind)
+                            })
+                            .collect(),
+                    )),
+                    Err(err) => respond(RtnlVirtualInterfaceResponse::Error(
+                        RtnlError::from_rtnetlink(&err),
+                    )),
+                }
+            }
             RtnlVirtualInterfaceRequest::GetIndexByName(name) => {
                 match resolve_index_by_name(&mut handle, &name).await {
                     Ok(Some(index)) => respond(RtnlVirtualInterfaceResponse::Index(index)),
@@ -305,10 +908,82 @@ pub(crate) async fn run_server(mut server: Server, mut handle: rtnetlink::LinkHa
     }
 }
 
-fn netlink_error_to_response(err: io::Error) -> RtnlVirtualInterfaceResponse {
-    match err.kind() {
+async fn create_many(
+    handle: &mut rtnetlink::LinkHandle,
+    specs: &[VirtualInterfaceSpec],
+) -> Vec<RtnlVirtualInterfaceResponse> {
+    // `None` marks an interface the kernel created, whose index is filled
+    // in from the dump below.
+    let requests = specs.iter().map(|spec| {
+        let handle = &*handle;
+        async move {
+            let message = match build_create_message(spec) {
+                Ok(msg) => msg,
+                Err(err) => {
+                    tracing::warn!("Failed to build virtual interface {}: {}", spec.name, err);
+                    return Some(RtnlVirtualInterfaceResponse::Error(RtnlError::new(
+                        err.to_string(),
+                    )));
+                }
+            };
+            match handle.add(message).execute().await {
+                Ok(()) => None,
+                Err(rtnetlink::Error::NetlinkError(err_msg)) => {
+                    tracing::warn!(
+                        "Netlink error creating virtual interface {}: {}",
+                        spec.name,
+                        err_msg
+                    );
+                    Some(netlink_error_to_response(&err_msg))
+                }
+                Err(err) => {
+                    tracing::warn!("Failed to create virtual interface {}: {}", spec.name, err);
+                    Some(RtnlVirtualInterfaceResponse::Error(
+                        RtnlError::from_rtnetlink(&err),
+                    ))
+                }
+            }
+        }
+    });
+    let results = join_all(requests).await;
+    if results.iter().all(Option::is_some) {
+        return results.into_iter().flatten().collect();
+    }
+
+    let indexes = match handle.get().execute().try_collect::<Vec<_>>().await {
+        Ok(messages) => Ok(messages
+            .into_iter()
+            .filter_map(|msg| {
+                msg.attributes.iter().find_map(|attr| match attr {
+                    LinkAttribute::IfName(name) => Some((name.clone(), msg.header.index)),
+                    _ => None,
+                })
+            })
+            .collect::<HashMap<_, _>>()),
+        Err(err) => {
+            tracing::warn!("Failed to resolve created virtual interfaces: {}", err);
+            Err(err)
+        }
+    };
+    results
+        .into_iter()
+        .zip(specs)
+        .map(|(result, spec)| {
+            result.unwrap_or_else(|| match &indexes {
+                Ok(indexes) => match indexes.get(&spec.name) {
+                    Some(index) => RtnlVirtualInterfaceResponse::Index(*index),
+                    None => RtnlVirtualInterfaceResponse::NotFound,
+                },
+                Err(err) => RtnlVirtualInterfaceResponse::Error(RtnlError::from_rtnetlink(err)),
+            })
+        })
+        .collect()
+}
+
+fn netlink_error_to_response(err: &ErrorMessage) -> RtnlVirtualInterfaceResponse {
+    match err.to_io().kind() {
         ErrorKind::NotFound => RtnlVirtualInterfaceResponse::NotFound,
-        _ => RtnlVirtualInterfaceResponse::Failed,
+        _ => RtnlVirtualInterfaceResponse::Error(RtnlError::from_netlink(err)),
     }
 }
 
@@ -327,6 +1002,26 @@ fn build_create_message(spec: &VirtualInterfaceSpec) -> io::Result<LinkMessage>
         builder = builder.link(link);
     }
 
+    if let Some(queues) = spec.num_tx_queues {
+        builder = builder.append_extra_attribute(LinkAttribute::NumTxQueues(queues));
+    }
+
+    if let Some(queues) = spec.num_rx_queues {
+        builder = builder.append_extra_attribute(LinkAttribute::NumRxQueues(queues));
+    }
+
+    if let Some(mtu) = spec.mtu {
+        builder = builder.mtu(mtu);
+    }
+
+    if let Some(mac_addr) = spec.mac_addr {
+        builder = builder.address(mac_addr.to_vec());
+    }
+
+    if let Some(master) = spec.master {
+        builder = builder.controller(master);
+    }
+
     Ok(builder.build())
 }
 
@@ -343,22 +1038,95 @@ fn validate_create_kind(kind: &VirtualInterfaceKind) -> io::Result<()> {
             }
             Ok(())
         }
+        VirtualInterfaceKind::Vxlan(cfg) => validate_vxlan(cfg),
+        VirtualInterfaceKind::Geneve(cfg) => validate_geneve(cfg),
+        VirtualInterfaceKind::Macsec(cfg) => {
+            if cfg.link.is_none() {
+                return Err(io::Error::other(
+                    "MACsec creation requires a parent interface (--dev)",
+                ));
+            }
+            if cfg.sci.is_some() && cfg.port.is_some() {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "MACsec sci and port are mutually exclusive",
+                ));
+            }
+            Ok(())
+        }
         _ => Ok(()),
     }
 }
 
+fn validate_vxlan(cfg: &VxlanConfig) -> io::Result<()> {
+    let invalid = |message: &str| io::Error::new(ErrorKind::InvalidInput, message.to_string());
+    if cfg.vni > VXLAN_VNI_MAX {
+        return Err(invalid("VXLAN VNI must fit in 24 bits"));
+    }
+    if cfg.external && (cfg.remote.is_some() || cfg.group.is_some()) {
+        return Err(invalid("External VXLAN takes peers from metadata"));
+    }
+    if cfg.remote.is_some() && cfg.group.is_some() {
+        return Err(invalid("VXLAN remote and group are mutually exclusive"));
+    }
+    if let Some(group) = cfg.group {
+        if !group.is_multicast() {
+            return Err(invalid("VXLAN group must be a multicast address"));
+        }
+        if cfg.link.is_none() {
+            return Err(invalid("VXLAN multicast group requires an underlay device"));
+        }
+    }
+    if cfg.remote.is_some_and(|remote| remote.is_multicast()) {
+        return Err(invalid("VXLAN remote must be a unicast address"));
+    }
+    if let (Some(local), Some(peer)) = (cfg.local, cfg.remote.or(cfg.group))
+        && local.is_ipv4() != peer.is_ipv4()
+    {
+        return Err(invalid("VXLAN local and remote address families differ"));
+    }
+    Ok(())
+}
+
+fn validate_geneve(cfg: &GeneveConfig) -> io::Result<()> {
+    let invalid = |message: &str| io::Error::new(ErrorKind::InvalidInput, message.to_string());
+    if cfg.vni > VXLAN_VNI_MAX {
+        return Err(invalid("GENEVE VNI must fit in 24 bits"));
+    }
+    if cfg.external && cfg.remote.is_some() {
+        return Err(invalid("External GENEVE takes peers from metadata"));
+    }
+    if cfg.remote.is_some_and(|remote| remote.is_multicast()) {
+        return Err(invalid("GENEVE remote must be a unicast address"));
+    }
+    Ok(())
+}
+
 fn build_update_message(update: &VirtualInterfaceUpdate) -> io::Result<LinkMessage> {
-    let info_kind = virtual_interface_kind_to_info_kind(&update.kind);
-    let mut builder = LinkMessageBuilder::<LinkUnspec>::new_with_info_kind(info_kind)
+    let mut builder = LinkUnspec::new_with_index(update.if_id);
+
+    if let Some(kind) = &update.kind {
+        // The kernel refuses to change the SCI or cipher suite of an existing
+        // MACsec link, so only the mutable attributes are sent.
+        let info_data = match kind {
+            VirtualInterfaceKind::Macsec(cfg) => {
+                InfoData::MacSec(vec![InfoMacSec::Encrypt(cfg.encrypt as u8)])
+            }
+            kind => build_info_data(kind)?,
+        };
+        builder = LinkMessageBuilder::<LinkUnspec>::new_with_info_kind(
+            virtual_interface_kind_to_info_kind(kind),
+        )
         .index(update.if_id)
-        .set_info_data(build_info_data(&update.kind)?);
+        .set_info_data(info_data);
 
-    if let Some(name) = &update.new_name {
-        builder = builder.name(name.clone());
+        if let Some(link) = virtual_interface_link(kind) {
+            builder = builder.link(link);
+        }
     }
 
-    if let Some(link) = virtual_interface_link(&update.kind) {
-        builder = builder.link(link);
+    if let Some(name) = &update.new_name {
+        builder = builder.name(name.clone());
     }
 
     if let Some(up) = update.admin_up {
@@ -402,7 +1170,11 @@ fn virtual_interface_kind_to_info_kind(kind: &VirtualInterfaceKind) -> InfoKind
         VirtualInterfaceKind::Ip6Gretap(_) => InfoKind::GreTap6,
         VirtualInterfaceKind::IpIp(_) => InfoKind::IpTun,
         VirtualInterfaceKind::Ip6Tnl(_) => InfoKind::Other("ip6tnl".into()),
+        VirtualInterfaceKind::Sit(_) => InfoKind::SitTun,
         VirtualInterfaceKind::Vlan(_) => InfoKind::Vlan,
+        VirtualInterfaceKind::Vxlan(_) => InfoKind::Vxlan,
+        VirtualInterfaceKind::Geneve(_) => InfoKind::Geneve,
+        VirtualInterfaceKind::Macsec(_) => InfoKind::MacSec,
     }
 }
 
@@ -412,7 +1184,12 @@ fn virtual_interface_link(kind: &VirtualInterfaceKind) -> Option<u32> {
         VirtualInterfaceKind::Ip6Gre(cfg) | VirtualInterfaceKind::Ip6Gretap(cfg) => cfg.link,
         VirtualInterfaceKind::IpIp(cfg) => cfg.link,
         VirtualInterfaceKind::Ip6Tnl(cfg) => cfg.link,
+        VirtualInterfaceKind::Sit(cfg) => cfg.link,
         VirtualInterfaceKind::Vlan(cfg) => cfg.base_ifindex,
+        // VXLAN carries its underlay device in IFLA_VXLAN_LINK instead.
+        VirtualInterfaceKind::Vxlan(_) => None,
+        VirtualInterfaceKind::Geneve(_) => None,
+        VirtualInterfaceKind::Macsec(cfg) => cfg.link,
     }
 }
 
@@ -438,6 +1215,9 @@ fn build_info_data(kind: &VirtualInterfaceKind) -> io::Result<InfoData> {
             let nlas = iptunnel_v6_nlas(cfg);
             Ok(InfoData::Other(encode_default_nlas(&nlas)))
         }
+        VirtualInterfaceKind::Sit(cfg) => Ok(InfoData::SitTun(
+            sit_nlas(cfg).into_iter().map(InfoSitTun::Other).collect(),
+        )),
         VirtualInterfaceKind::Vlan(cfg) => {
             let mut infos = Vec::new();
             if let Some(id) = cfg.vlan_id {
@@ -445,10 +1225,86 @@ fn build_info_data(kind: &VirtualInterfaceKind) -> io::Result<InfoData> {
             }
             Ok(InfoData::Vlan(infos))
         }
+        VirtualInterfaceKind::Vxlan(cfg) => Ok(InfoData::Vxlan(vxlan_infos(cfg))),
+        VirtualInterfaceKind::Geneve(cfg) => Ok(InfoData::Geneve(geneve_infos(cfg))),
+        VirtualInterfaceKind::Macsec(cfg) => Ok(InfoData::MacSec(macsec_infos(cfg))),
+    }
+}
+
+fn macsec_infos(cfg: &MacsecConfig) -> Vec<InfoMacSec> {
+    let mut infos = Vec::new();
+    if let Some(sci) = cfg.sci {
+        infos.push(InfoMacSec::Sci(sci));
+    }
+    if let Some(port) = cfg.port {
+        infos.push(InfoMacSec::Port(port));
     }
+    if let Some(cipher_suite) = cfg.cipher_suite {
+        infos.push(InfoMacSec::CipherSuite(cipher_suite.to_cipher_id()));
+    }
+    infos.push(InfoMacSec::Encrypt(cfg.encrypt as u8));
+    infos
+}
+
+fn vxlan_infos(cfg: &VxlanConfig) -> Vec<InfoVxlan> {
+    let mut infos = Vec::new();
+    if cfg.external {
+        infos.push(InfoVxlan::CollectMetadata(true));
+    } else {
+        infos.push(InfoVxlan::Id(cfg.vni));
+    }
+    match cfg.local {
+        Some(IpAddr::V4(addr)) => infos.push(InfoVxlan::Local(addr)),
+        Some(IpAddr::V6(addr)) => infos.push(InfoVxlan::Local6(addr)),
+        None => {}
+    }
+    match cfg.remote.or(cfg.group).filter(|_| !cfg.external) {
+        Some(IpAddr::V4(addr)) => infos.push(InfoVxlan::Group(addr)),
+        Some(IpAddr::V6(addr)) => infos.push(InfoVxlan::Group6(addr)),
+        None => {}
+    }
+    if let Some(port) = cfg.dstport {
+        infos.push(InfoVxlan::Port(port));
+    }
+    infos.push(InfoVxlan::Learning(cfg.learning));
+    if let Some(ttl) = cfg.ttl {
+        infos.push(InfoVxlan::Ttl(ttl));
+    }
+    if let Some(link) = cfg.link {
+        infos.push(InfoVxlan::Link(link));
+    }
+    infos
+}
+
+fn geneve_infos(cfg: &GeneveConfig) -> Vec<InfoGeneve> {
+    let mut infos = Vec::new();
+    if cfg.external {
+        infos.push(InfoGeneve::CollectMetadata);
+    } else {
+        infos.push(InfoGeneve::Id(cfg.vni));
+        match cfg.remote {
+            Some(IpAddr::V4(addr)) => infos.push(InfoGeneve::Remote(addr)),
+            Some(IpAddr::V6(addr)) => infos.push(InfoGeneve::Remote6(addr)),
+            None => {}
+        }
+    }
+    if let Some(port) = cfg.dstport {
+        infos.push(InfoGeneve::Port(port));
+    }
+    if let Some(ttl) = cfg.ttl {
+        infos.push(InfoGeneve::Ttl(ttl));
+    }
+    if let Some(tos) = cfg.tos {
+        infos.push(InfoGeneve::Tos(tos));
+    }
+    infos
 }
 
 fn gre_nlas(cfg: &GreConfig) -> Vec<DefaultNla> {
+    if cfg.external {
+        return external_nlas(IFLA_GRE_COLLECT_METADATA, IFLA_GRE_LINK, cfg.link);
+    }
+
     let mut nlas = Vec::new();
     nlas.push(DefaultNla::new(IFLA_GRE_LOCAL, cfg.local.octets().to_vec()));
     nlas.push(DefaultNla::new(
@@ -464,11 +1320,12 @@ fn gre_nlas(cfg: &GreConfig) -> Vec<DefaultNla> {
         nlas.push(DefaultNla::new(IFLA_GRE_TOS, vec![tos]));
     }
 
-    if let Some(key) = cfg.key {
-        let bytes = key.to_be_bytes().to_vec();
-        nlas.push(DefaultNla::new(IFLA_GRE_IKEY, bytes.clone()));
-        nlas.push(DefaultNla::new(IFLA_GRE_OKEY, bytes));
-    }
+    nlas.extend(gre_key_nlas(
+        cfg.ikey.or(cfg.key),
+        cfg.okey.or(cfg.key),
+        cfg.iflags,
+        cfg.oflags,
+    ));
 
     let limit = cfg.encap_limit.unwrap_or(0xff);
     nlas.push(DefaultNla::new(IFLA_GRE_ENCAP_LIMIT, vec![limit]));
@@ -490,7 +1347,32 @@ fn gre_nlas(cfg: &GreConfig) -> Vec<DefaultNla> {
     nlas
 }
 
+// netlink-packet-route has no typed IFLA_GRE_* attributes, so the flags go
+// out as raw big-endian GRE header bits like the rest of the GRE options.
+fn gre_key_nlas(
+    ikey: Option<u32>,
+    okey: Option<u32>,
+    iflags: GreFlags,
+    oflags: GreFlags,
+) -> Vec<DefaultNla> {
+    let mut nlas = vec![
+        DefaultNla::new(IFLA_GRE_IFLAGS, iflags.bits(ikey).to_be_bytes().to_vec()),
+        DefaultNla::new(IFLA_GRE_OFLAGS, oflags.bits(okey).to_be_bytes().to_vec()),
+    ];
+    if let Some(key) = ikey {
+        nlas.push(DefaultNla::new(IFLA_GRE_IKEY, key.to_be_bytes().to_vec()));
+    }
+    if let Some(key) = okey {
+        nlas.push(DefaultNla::new(IFLA_GRE_OKEY, key.to_be_bytes().to_vec()));
+    }
+    nlas
+}
+
 fn gre6_nlas(cfg: &Gre6Config) -> Vec<DefaultNla> {
+    if cfg.external {
+        return external_nlas(IFLA_GRE_COLLECT_METADATA, IFLA_GRE_LINK, cfg.link);
+    }
+
     let mut nlas = Vec::new();
     nlas.push(DefaultNla::new(IFLA_GRE_LOCAL, cfg.local.octets().to_vec()));
     nlas.push(DefaultNla::new(
@@ -498,7 +1380,7 @@ fn gre6_nlas(cfg: &Gre6Config) -> Vec<DefaultNla> {
         cfg.remote.octets().to_vec(),
     ));
 
-    if let Some(hop) = cfg.hop_limit {
+    if let Some(hop) = cfg.flags.hop_limit(cfg.hop_limit) {
         nlas.push(DefaultNla::new(IFLA_GRE_TTL, vec![hop]));
     }
 
@@ -506,11 +1388,12 @@ fn gre6_nlas(cfg: &Gre6Config) -> Vec<DefaultNla> {
         nlas.push(DefaultNla::new(IFLA_GRE_TOS, vec![tc]));
     }
 
-    if let Some(key) = cfg.key {
-        let bytes = key.to_be_bytes().to_vec();
-        nlas.push(DefaultNla::new(IFLA_GRE_IKEY, bytes.clone()));
-        nlas.push(DefaultNla::new(IFLA_GRE_OKEY, bytes));
-    }
+    nlas.extend(gre_key_nlas(
+        cfg.ikey.or(cfg.key),
+        cfg.okey.or(cfg.key),
+        cfg.iflags,
+        cfg.oflags,
+    ));
 
     let limit = cfg.encap_limit.unwrap_or(0xff);
     nlas.push(DefaultNla::new(IFLA_GRE_ENCAP_LIMIT, vec![limit]));
@@ -529,10 +1412,27 @@ fn gre6_nlas(cfg: &Gre6Config) -> Vec<DefaultNla> {
         nlas.push(DefaultNla::new(IFLA_GRE_LINK, link.to_ne_bytes().to_vec()));
     }
 
+    nlas.push(DefaultNla::new(
+        IFLA_GRE_FLAGS,
+        cfg.flags.bits().to_ne_bytes().to_vec(),
+    ));
+
+    nlas
+}
+
+fn external_nlas(collect_metadata: u16, link_kind: u16, link: Option<u32>) -> Vec<DefaultNla> {
+    let mut nlas = vec![DefaultNla::new(collect_metadata, Vec::new())];
+    if let Some(link) = link {
+        nlas.push(DefaultNla::new(link_kind, link.to_ne_bytes().to_vec()));
+    }
     nlas
 }
 
 fn iptunnel_v4_nlas(cfg: &IpIpConfig) -> Vec<DefaultNla> {
+    if cfg.external {
+        return external_nlas(IFLA_IPTUN_COLLECT_METADATA, IFLA_IPTUN_LINK, cfg.link);
+    }
+
     let mut nlas = Vec::new();
     nlas.push(DefaultNla::new(
         IFLA_IPTUN_LOCAL,
@@ -570,6 +1470,14 @@ fn iptunnel_v4_nlas(cfg: &IpIpConfig) -> Vec<DefaultNla> {
 }
 
 fn iptunnel_v6_nlas(cfg: &Ip6TnlConfig) -> Vec<DefaultNla> {
+    if cfg.external {
+        let mut nlas = external_nlas(IFLA_IPTUN_COLLECT_METADATA, IFLA_IPTUN_LINK, cfg.link);
+        if let Some(mode) = cfg.mode {
+            nlas.push(DefaultNla::new(IFLA_IPTUN_PROTO, vec![mode.proto()]));
+        }
+        return nlas;
+    }
+
     let mut nlas = Vec::new();
     nlas.push(DefaultNla::new(
         IFLA_IPTUN_LOCAL,
@@ -580,7 +1488,7 @@ fn iptunnel_v6_nlas(cfg: &Ip6TnlConfig) -> Vec<DefaultNla> {
         cfg.remote.octets().to_vec(),
     ));
 
-    if let Some(hop) = cfg.hop_limit {
+    if let Some(hop) = cfg.flags.hop_limit(cfg.hop_limit) {
         nlas.push(DefaultNla::new(IFLA_IPTUN_TTL, vec![hop]));
     }
 
@@ -588,6 +1496,10 @@ fn iptunnel_v6_nlas(cfg: &Ip6TnlConfig) -> Vec<DefaultNla> {
         nlas.push(DefaultNla::new(IFLA_IPTUN_TOS, vec![tc]));
     }
 
+    if let Some(mode) = cfg.mode {
+        nlas.push(DefaultNla::new(IFLA_IPTUN_PROTO, vec![mode.proto()]));
+    }
+
     if let Some(flow) = cfg.flow_label {
         nlas.push(DefaultNla::new(
             IFLA_IPTUN_FLOWINFO,
@@ -610,9 +1522,424 @@ fn iptunnel_v6_nlas(cfg: &Ip6TnlConfig) -> Vec<DefaultNla> {
         ));
     }
 
+    nlas.push(DefaultNla::new(
+        IFLA_IPTUN_FLAGS,
+        cfg.flags.bits().to_ne_bytes().to_vec(),
+    ));
+
     nlas
 }
 
+fn sit_nlas(cfg: &SitConfig) -> Vec<DefaultNla> {
+    let mut nlas = Vec::new();
+    nlas.push(DefaultNla::new(
+        IFLA_IPTUN_LOCAL,
+        cfg.local.octets().to_vec(),
+    ));
+    nlas.push(DefaultNla::new(
+        IFLA_IPTUN_REMOTE,
+        cfg.remote.octets().to_vec(),
+    ));
+
+    if let Some(ttl) = cfg.ttl {
+        nlas.push(DefaultNla::new(IFLA_IPTUN_TTL, vec![ttl]));
+    }
+
+    if let Some(tos) = cfg.tos {
+        nlas.push(DefaultNla::new(IFLA_IPTUN_TOS, vec![tos]));
+    }
+
+    nlas.push(DefaultNla::new(
+        IFLA_IPTUN_PMTUDISC,
+        vec![if cfg.pmtudisc { 1 } else { 0 }],
+    ));
+
+    if let Some(link) = cfg.link {
+        nlas.push(DefaultNla::new(
+            IFLA_IPTUN_LINK,
+            link.to_ne_bytes().to_vec(),
+        ));
+    }
+
+    if let Some(prefix) = cfg.ip6rd_prefix {
+        nlas.push(DefaultNla::new(
+            IFLA_IPTUN_6RD_PREFIX,
+            prefix.addr().octets().to_vec(),
+        ));
+        nlas.push(DefaultNla::new(
+            IFLA_IPTUN_6RD_PREFIXLEN,
+            (prefix.prefix_len() as u16).to_ne_bytes().to_vec(),
+        ));
+    }
+
+    if let Some(relay) = cfg.ip6rd_relay_prefix {
+        nlas.push(DefaultNla::new(
+            IFLA_IPTUN_6RD_RELAY_PREFIX,
+            relay.addr().octets().to_vec(),
+        ));
+        nlas.push(DefaultNla::new(
+            IFLA_IPTUN_6RD_RELAY_PREFIXLEN,
+            (relay.prefix_len() as u16).to_ne_bytes().to_vec(),
+        ));
+    }
+
+    nlas
+}
+
+fn virtual_interface_from_message(message: &LinkMessage) -> Option<VirtualInterfaceInfo> {
+    let mut name = None;
+    let mut parent = None;
+    let mut infos: &[LinkInfo] = &[];
+    for attr in message.attributes.iter() {
+        match attr {
+            LinkAttribute::IfName(if_name) => name = Some(if_name.clone()),
+            LinkAttribute::Link(link) if *link != 0 => parent = Some(*link),
+            LinkAttribute::LinkInfo(link_infos) => infos = link_infos,
+            _ => {}
+        }
+    }
+
+    let mut info_kind = None;
+    let mut info_data = None;
+    for info in infos {
+        match info {
+            LinkInfo::Kind(kind) => info_kind = Some(kind),
+            LinkInfo::Data(data) => info_data = Some(data),
+            _ => {}
+        }
+    }
+
+    let kind = match (info_kind?, info_data?) {
+        (InfoKind::GreTun, InfoData::GreTun(nlas)) => {
+            VirtualInterfaceKind::Gre(decode_gre(&RawAttrs::from_nlas(nlas)))
+        }
+        (InfoKind::GreTap, InfoData::GreTap(nlas)) => {
+            VirtualInterfaceKind::Gretap(decode_gre(&RawAttrs::from_nlas(nlas)))
+        }
+        (InfoKind::GreTun6, InfoData::GreTun6(nlas)) => {
+            VirtualInterfaceKind::Ip6Gre(decode_gre6(&RawAttrs::from_nlas(nlas)))
+        }
+        (InfoKind::GreTap6, InfoData::GreTap6(nlas)) => {
+            VirtualInterfaceKind::Ip6Gretap(decode_gre6(&RawAttrs::from_nlas(nlas)))
+        }
+        (InfoKind::IpTun, InfoData::Other(payload)) => {
+            VirtualInterfaceKind::IpIp(decode_ipip(&RawAttrs::parse(payload)))
+        }
+        (InfoKind::Other(kind), InfoData::Other(payload)) if kind == "ip6tnl" => {
+            VirtualInterfaceKind::Ip6Tnl(decode_ip6tnl(&RawAttrs::parse(payload)))
+        }
+        (InfoKind::SitTun, InfoData::SitTun(nlas)) => {
+            VirtualInterfaceKind::Sit(decode_sit(&RawAttrs::from_nlas(nlas)))
+        }
+        (InfoKind::Vlan, InfoData::Vlan(infos)) => VirtualInterfaceKind::Vlan(VlanConfig {
+            base_ifindex: parent,
+            vlan_id: infos.iter().find_map(|info| match info {
+                InfoVlan::Id(id) => Some(*id),
+                _ => None,
+            }),
+        }),
+        (InfoKind::Vxlan, InfoData::Vxlan(infos)) => {
+            VirtualInterfaceKind::Vxlan(decode_vxlan(infos))
+        }
+        (InfoKind::Geneve, InfoData::Geneve(infos)) => {
+            VirtualInterfaceKind::Geneve(decode_geneve(infos))
+        }
+        (InfoKind::MacSec, InfoData::MacSec(infos)) => {
+            VirtualInterfaceKind::Macsec(decode_macsec(infos, parent))
+        }
+        _ => return None,
+    };
+
+    Some(VirtualInterfaceInfo {
+        if_id: message.header.index,
+        name: name?,
+        kind,
+    })
+}
+
+// IFLA_INFO_DATA attributes as (type, payload) pairs. netlink-packet-route
+// leaves the tunnel attributes undecoded, and ipip/ip6tnl as a raw buffer.
+struct RawAttrs(Vec<(u16, Vec<u8>)>);
+
+impl RawAttrs {
+    fn from_nlas<T: Nla>(nlas: &[T]) -> Self {
+        Self(
+            nlas.iter()
+                .map(|nla| {
+                    let mut value = vec![0u8; nla.value_len()];
+                    nla.emit_value(&mut value);
+                    (nla.kind(), value)
+                })
+                .collect(),
+        )
+    }
+
+    fn parse(mut buffer: &[u8]) -> Self {
+        let mut attrs = Vec::new();
+        while buffer.len() >= NLA_HEADER_LEN {
+            let len = u16::from_ne_bytes([buffer[0], buffer[1]]) as usize;
+            let kind = u16::from_ne_bytes([buffer[2], buffer[3]]) & NLA_TYPE_MASK;
+            if len < NLA_HEADER_LEN || len > buffer.len() {
+                break;
+            }
+            attrs.push((kind, buffer[NLA_HEADER_LEN..len].to_vec()));
+            buffer = &buffer[align_nla(len).min(buffer.len())..];
+        }
+        Self(attrs)
+    }
+
+    fn get(&self, kind: u16) -> Option<&[u8]> {
+        self.0
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map(|(_, value)| value.as_slice())
+    }
+
+    fn has(&self, kind: u16) -> bool {
+        self.get(kind).is_some()
+    }
+
+    fn u8(&self, kind: u16) -> Option<u8> {
+        self.get(kind)?.first().copied()
+    }
+
+    fn bool(&self, kind: u16) -> bool {
+        self.u8(kind).is_some_and(|value| value != 0)
+    }
+
+    fn array<const N: usize>(&self, kind: u16) -> Option<[u8; N]> {
+        self.get(kind)?.get(..N)?.try_into().ok()
+    }
+
+    fn u16_be(&self, kind: u16) -> Option<u16> {
+        self.array(kind).map(u16::from_be_bytes)
+    }
+
+    fn u16_ne(&self, kind: u16) -> Option<u16> {
+        self.array(kind).map(u16::from_ne_bytes)
+    }
+
+    fn u32_be(&self, kind: u16) -> Option<u32> {
+        self.array(kind).map(u32::from_be_bytes)
+    }
+
+    fn u32_ne(&self, kind: u16) -> Option<u32> {
+        self.array(kind).map(u32::from_ne_bytes)
+    }
+
+    fn ipv4(&self, kind: u16) -> Ipv4Addr {
+        self.array::<4>(kind)
+            .map(Ipv4Addr::from)
+            .unwrap_or(Ipv4Addr::UNSPECIFIED)
+    }
+
+    fn ipv6(&self, kind: u16) -> Ipv6Addr {
+        self.array::<16>(kind)
+            .map(Ipv6Addr::from)
+            .unwrap_or(Ipv6Addr::UNSPECIFIED)
+    }
+
+    // The kernel reports unset values as 0; map them back to `None`.
+    fn nonzero_u8(&self, kind: u16) -> Option<u8> {
+        self.u8(kind).filter(|value| *value != 0)
+    }
+
+    fn nonzero_u32_ne(&self, kind: u16) -> Option<u32> {
+        self.u32_ne(kind).filter(|value| *value != 0)
+    }
+
+    fn encap_limit(&self, kind: u16) -> Option<u8> {
+        self.u8(kind).filter(|limit| *limit != 0xff)
+    }
+}
+
+// Keys are only meaningful when the key bit is set in the matching flags.
+// The decoded flags drop the key bit again since a configured key implies it.
+fn decode_gre_keys(
+    attrs: &RawAttrs,
+) -> (Option<u32>, Option<u32>, Option<u32>, GreFlags, GreFlags) {
+    let mut iflags = GreFlags::from_bits(attrs.u16_be(IFLA_GRE_IFLAGS).unwrap_or(0));
+    let mut oflags = GreFlags::from_bits(attrs.u16_be(IFLA_GRE_OFLAGS).unwrap_or(0));
+    let ikey = attrs.u32_be(IFLA_GRE_IKEY).filter(|_| iflags.key);
+    let okey = attrs.u32_be(IFLA_GRE_OKEY).filter(|_| oflags.key);
+    iflags.key = false;
+    oflags.key = false;
+    let (key, ikey, okey) = reported_gre_keys(None, ikey, okey);
+    (key, ikey, okey, iflags, oflags)
+}
+
+// A key used in both directions is reported as `key`.
+fn reported_gre_keys(
+    key: Option<u32>,
+    ikey: Option<u32>,
+    okey: Option<u32>,
+) -> (Option<u32>, Option<u32>, Option<u32>) {
+    let (ikey, okey) = (ikey.or(key), okey.or(key));
+    if ikey == okey {
+        (ikey, None, None)
+    } else {
+        (None, ikey, okey)
+    }
+}
+
+fn sit_6to4_prefix() -> Ipv6Net {
+    Ipv6Net::new(Ipv6Addr::new(0x2002, 0, 0, 0, 0, 0, 0, 0), 16).unwrap()
+}
+
+fn decode_gre(attrs: &RawAttrs) -> GreConfig {
+    let (key, ikey, okey, iflags, oflags) = decode_gre_keys(attrs);
+    GreConfig {
+        local: attrs.ipv4(IFLA_GRE_LOCAL),
+        remote: attrs.ipv4(IFLA_GRE_REMOTE),
+        ttl: attrs.nonzero_u8(IFLA_GRE_TTL),
+        tos: attrs.nonzero_u8(IFLA_GRE_TOS),
+        key,
+        ikey,
+        okey,
+        iflags,
+        oflags,
+        encap_limit: attrs.encap_limit(IFLA_GRE_ENCAP_LIMIT),
+        pmtudisc: attrs.bool(IFLA_GRE_PMTUDISC),
+        ignore_df: attrs.bool(IFLA_GRE_IGNORE_DF),
+        link: attrs.nonzero_u32_ne(IFLA_GRE_LINK),
+        external: attrs.has(IFLA_GRE_COLLECT_METADATA),
+    }
+}
+
+fn decode_gre6(attrs: &RawAttrs) -> Gre6Config {
+    let (key, ikey, okey, iflags, oflags) = decode_gre_keys(attrs);
+    let hop_limit = attrs.u8(IFLA_GRE_TTL);
+    Gre6Config {
+        local: attrs.ipv6(IFLA_GRE_LOCAL),
+        remote: attrs.ipv6(IFLA_GRE_REMOTE),
+        hop_limit: hop_limit.filter(|hop| *hop != 0),
+        traffic_class: attrs.nonzero_u8(IFLA_GRE_TOS),
+        key,
+        ikey,
+        okey,
+        iflags,
+        oflags,
+        encap_limit: attrs.encap_limit(IFLA_GRE_ENCAP_LIMIT),
+        pmtudisc: attrs.bool(IFLA_GRE_PMTUDISC),
+        ignore_df: attrs.bool(IFLA_GRE_IGNORE_DF),
+        link: attrs.nonzero_u32_ne(IFLA_GRE_LINK),
+        flags: Ip6TunnelFlags::from_bits(attrs.u32_ne(IFLA_GRE_FLAGS).unwrap_or(0), hop_limit),
+        external: attrs.has(IFLA_GRE_COLLECT_METADATA),
+    }
+}
+
+fn decode_ipip(attrs: &RawAttrs) -> IpIpConfig {
+    IpIpConfig {
+        local: attrs.ipv4(IFLA_IPTUN_LOCAL),
+        remote: attrs.ipv4(IFLA_IPTUN_REMOTE),
+        ttl: attrs.nonzero_u8(IFLA_IPTUN_TTL),
+        tos: attrs.nonzero_u8(IFLA_IPTUN_TOS),
+        encap_limit: attrs.encap_limit(IFLA_IPTUN_ENCAP_LIMIT),
+        pmtudisc: attrs.bool(IFLA_IPTUN_PMTUDISC),
+        link: attrs.nonzero_u32_ne(IFLA_IPTUN_LINK),
+        external: attrs.has(IFLA_IPTUN_COLLECT_METADATA),
+    }
+}
+
+fn decode_ip6tnl(attrs: &RawAttrs) -> Ip6TnlConfig {
+    let hop_limit = attrs.u8(IFLA_IPTUN_TTL);
+    let mode = match attrs.u8(IFLA_IPTUN_PROTO) {
+        Some(0) => Some(Ip6TnlMode::Any),
+        Some(IPPROTO_IPIP) => Some(Ip6TnlMode::IpIp6),
+        Some(IPPROTO_IPV6) => Some(Ip6TnlMode::Ip6Ip6),
+        _ => None,
+    };
+    Ip6TnlConfig {
+        local: attrs.ipv6(IFLA_IPTUN_LOCAL),
+        remote: attrs.ipv6(IFLA_IPTUN_REMOTE),
+        hop_limit: hop_limit.filter(|hop| *hop != 0),
+        traffic_class: attrs.nonzero_u8(IFLA_IPTUN_TOS),
+        flow_label: attrs.u32_be(IFLA_IPTUN_FLOWINFO).filter(|flow| *flow != 0),
+        encap_limit: attrs.encap_limit(IFLA_IPTUN_ENCAP_LIMIT),
+        pmtudisc: attrs.bool(IFLA_IPTUN_PMTUDISC),
+        link: attrs.nonzero_u32_ne(IFLA_IPTUN_LINK),
+        mode,
+        flags: Ip6TunnelFlags::from_bits(attrs.u32_ne(IFLA_IPTUN_FLAGS).unwrap_or(0), hop_limit),
+        external: attrs.has(IFLA_IPTUN_COLLECT_METADATA),
+    }
+}
+
+fn decode_sit(attrs: &RawAttrs) -> SitConfig {
+    let ip6rd_prefix = attrs
+        .u16_ne(IFLA_IPTUN_6RD_PREFIXLEN)
+        .filter(|len| *len != 0)
+        .and_then(|len| Ipv6Net::new(attrs.ipv6(IFLA_IPTUN_6RD_PREFIX), len as u8).ok());
+    let ip6rd_relay_prefix = attrs
+        .u16_ne(IFLA_IPTUN_6RD_RELAY_PREFIXLEN)
+        .filter(|len| *len != 0)
+        .and_then(|len| Ipv4Net::new(attrs.ipv4(IFLA_IPTUN_6RD_RELAY_PREFIX), len as u8).ok());
+    SitConfig {
+        local: attrs.ipv4(IFLA_IPTUN_LOCAL),
+        remote: attrs.ipv4(IFLA_IPTUN_REMOTE),
+        ttl: attrs.nonzero_u8(IFLA_IPTUN_TTL),
+        tos: attrs.nonzero_u8(IFLA_IPTUN_TOS),
+        pmtudisc: attrs.bool(IFLA_IPTUN_PMTUDISC),
+        link: attrs.nonzero_u32_ne(IFLA_IPTUN_LINK),
+        ip6rd_prefix,
+        ip6rd_relay_prefix,
+    }
+}
+
+fn decode_vxlan(infos: &[InfoVxlan]) -> VxlanConfig {
+    let mut cfg = VxlanConfig::new(0);
+    for info in infos {
+        match info {
+            InfoVxlan::Id(vni) => cfg.vni = *vni,
+            InfoVxlan::Local(addr) if !addr.is_unspecified() => cfg.local = Some(IpAddr::V4(*addr)),
+            InfoVxlan::Local6(addr) if !addr.is_unspecified() => {
+                cfg.local = Some(IpAddr::V6(*addr))
+            }
+            InfoVxlan::Group(addr) if !addr.is_unspecified() => {
+                set_vxlan_peer(&mut cfg, IpAddr::V4(*addr))
+            }
+            InfoVxlan::Group6(addr) if !addr.is_unspecified() => {
+                set_vxlan_peer(&mut cfg, IpAddr::V6(*addr))
+            }
+            InfoVxlan::Port(port) => cfg.dstport = Some(*port),
+            InfoVxlan::Learning(learning) => cfg.learning = *learning,
+            InfoVxlan::Ttl(ttl) if *ttl != 0 => cfg.ttl = Some(*ttl),
+            InfoVxlan::Link(link) if *link != 0 => cfg.link = Some(*link),
+            InfoVxlan::CollectMetadata(external) => cfg.external = *external,
+            _ => {}
+        }
+    }
+    cfg
+}
+
+fn decode_geneve(infos: &[InfoGeneve]) -> GeneveConfig {
+    let mut cfg = GeneveConfig::new(0);
+    for info in infos {
+        match info {
+            InfoGeneve::Id(vni) => cfg.vni = *vni,
+            InfoGeneve::Remote(addr) if !addr.is_unspecified() => {
+                cfg.remote = Some(IpAddr::V4(*addr))
+            }
+            InfoGeneve::Remote6(addr) if !addr.is_unspecified() => {
+                cfg.remote = Some(IpAddr::V6(*addr))
+            }
+            InfoGeneve::Ttl(ttl) if *ttl != 0 => cfg.ttl = Some(*ttl),
+            InfoGeneve::Tos(tos) if *tos != 0 => cfg.tos = Some(*tos),
+            InfoGeneve::Port(port) => cfg.dstport = Some(*port),
+            InfoGeneve::CollectMetadata => cfg.external = true,
+            _ => {}
+        }
+    }
+    cfg
+}
+
+fn set_vxlan_peer(cfg: &mut VxlanConfig, addr: IpAddr) {
+    if addr.is_multicast() {
+        cfg.group = Some(addr);
+    } else {
+        cfg.remote = Some(addr);
+    }
+}
+
 fn encode_default_nlas(nlas: &[DefaultNla]) -> Vec<u8> {
     let mut buffer = Vec::new();
     for nla in nlas {
@@ -624,10 +1951,238 @@ fn encode_default_nlas(nlas: &[DefaultNla]) -> Vec<u8> {
         let target = &mut buffer[start..start + aligned_len];
         let len_bytes = (payload_len as u16).to_ne_bytes();
         target[0..2].copy_from_slice(&len_bytes);
-        let kind_bytes = (nla.kind() as u16).to_ne_bytes();
+        let kind_bytes = nla.kind().to_ne_bytes();
         target[2..4].copy_from_slice(&kind_bytes);
         nla.emit_value(&mut target[4..payload_len]);
         // trailing padding already zeroed by resize
     }
     buffer
 }
+
+fn decode_macsec(infos: &[InfoMacSec], parent: Option<u32>) -> MacsecConfig {
+    let mut cfg = MacsecConfig {
+        link: parent,
+        sci: None,
+        port: None,
+        cipher_suite: None,
+        encrypt: false,
+    };
+    for info in infos {
+        match info {
+            InfoMacSec::Sci(sci) => cfg.sci = Some(*sci),
+            InfoMacSec::Port(port) => cfg.port = Some(*port),
+            InfoMacSec::CipherSuite(id) => {
+                cfg.cipher_suite = MacsecCipherSuite::from_cipher_id(*id)
+            }
+            InfoMacSec::Encrypt(encrypt) => cfg.encrypt = *encrypt != 0,
+            _ => {}
+        }
+    }
+    cfg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip6tnl(hop_limit: Option<u8>, flags: Ip6TunnelFlags) -> Ip6TnlConfig {
+        Ip6TnlConfig {
+            local: "2001:db8::1".parse().unwrap(),
+            remote: "2001:db8::2".parse().unwrap(),
+            hop_limit,
+            traffic_class: None,
+            flow_label: None,
+            encap_limit: None,
+            pmtudisc: false,
+            link: None,
+            mode: None,
+            flags,
+            external: false,
+        }
+    }
+
+    fn gre6(hop_limit: Option<u8>, flags: Ip6TunnelFlags) -> Gre6Config {
+        Gre6Config {
+            local: "2001:db8::1".parse().unwrap(),
+            remote: "2001:db8::2".parse().unwrap(),
+            hop_limit,
+            traffic_class: None,
+            key: None,
+            ikey: None,
+            okey: None,
+            iflags: GreFlags::default(),
+            oflags: GreFlags::default(),
+            encap_limit: None,
+            pmtudisc: false,
+            ignore_df: false,
+            link: None,
+            flags,
+            external: false,
+        }
+    }
+
+    fn gre(key: Option<u32>, ikey: Option<u32>, okey: Option<u32>) -> GreConfig {
+        GreConfig {
+            local: "192.0.2.1".parse().unwrap(),
+            remote: "192.0.2.2".parse().unwrap(),
+            ttl: None,
+            tos: None,
+            key,
+            ikey,
+            okey,
+            iflags: GreFlags::default(),
+            oflags: GreFlags::default(),
+            encap_limit: None,
+            pmtudisc: false,
+            ignore_df: false,
+            link: None,
+            external: false,
+        }
+    }
+
+    fn sit(ip6rd_prefix: Option<Ipv6Net>) -> SitConfig {
+        SitConfig {
+            local: "192.0.2.1".parse().unwrap(),
+            remote: Ipv4Addr::UNSPECIFIED,
+            ttl: None,
+            tos: None,
+            pmtudisc: true,
+            link: None,
+            ip6rd_prefix,
+            ip6rd_relay_prefix: None,
+        }
+    }
+
+    // What the kernel reports for a tunnel created without a hop limit.
+    fn inherited() -> Ip6TunnelFlags {
+        Ip6TunnelFlags::from_bits(0, Some(0))
+    }
+
+    fn satisfied(spec: &VirtualInterfaceKind, existing: &VirtualInterfaceKind) -> bool {
+        spec.with_kernel_defaults(existing) == *existing
+    }
+
+    #[test]
+    fn vxlan_default_port_matches() {
+        let spec = VirtualInterfaceKind::Vxlan(VxlanConfig::new(100));
+        let mut reported = VxlanConfig::new(100);
+        reported.dstport = Some(8472);
+        assert!(satisfied(
+            &spec,
+            &VirtualInterfaceKind::Vxlan(reported.clone())
+        ));
+
+        let mut other_port = VxlanConfig::new(100);
+        other_port.dstport = Some(4789);
+        let spec = VirtualInterfaceKind::Vxlan(other_port);
+        assert!(!satisfied(&spec, &VirtualInterfaceKind::Vxlan(reported)));
+    }
+
+    #[test]
+    fn vxlan_vni_change_is_detected() {
+        let spec = VirtualInterfaceKind::Vxlan(VxlanConfig::new(100));
+        let mut reported = VxlanConfig::new(200);
+        reported.dstport = Some(8472);
+        assert!(!satisfied(&spec, &VirtualInterfaceKind::Vxlan(reported)));
+    }
+
+    #[test]
+    fn macsec_kernel_sci_and_cipher_match() {
+        let mut spec = MacsecConfig::new(2);
+        spec.port = Some(1);
+        let mut reported = MacsecConfig::new(2);
+        reported.sci = Some(0x0011_2233_4455_0001);
+        reported.cipher_suite = Some(MacsecCipherSuite::GcmAes128);
+        assert!(satisfied(
+            &VirtualInterfaceKind::Macsec(spec.clone()),
+            &VirtualInterfaceKind::Macsec(reported.clone())
+        ));
+
+        spec.encrypt = false;
+        assert!(!satisfied(
+            &VirtualInterfaceKind::Macsec(spec.clone()),
+            &VirtualInterfaceKind::Macsec(reported.clone())
+        ));
+
+        spec.encrypt = true;
+        spec.cipher_suite = Some(MacsecCipherSuite::GcmAes256);
+        assert!(!satisfied(
+            &VirtualInterfaceKind::Macsec(spec),
+            &VirtualInterfaceKind::Macsec(reported)
+        ));
+    }
+
+    #[test]
+    fn ip6tnl_unset_hop_limit_matches_inherit() {
+        let mut reported = ip6tnl(None, inherited());
+        reported.mode = Some(Ip6TnlMode::Ip6Ip6);
+        let reported = VirtualInterfaceKind::Ip6Tnl(reported);
+        let spec = VirtualInterfaceKind::Ip6Tnl(ip6tnl(None, Ip6TunnelFlags::default()));
+        assert!(satisfied(&spec, &reported));
+
+        let spec = VirtualInterfaceKind::Ip6Tnl(ip6tnl(Some(64), Ip6TunnelFlags::default()));
+        assert!(!satisfied(&spec, &reported));
+    }
+
+    #[test]
+    fn ip6tnl_explicit_hop_limit_matches() {
+        let reported =
+            VirtualInterfaceKind::Ip6Tnl(ip6tnl(Some(64), Ip6TunnelFlags::from_bits(0, Some(64))));
+        let spec = VirtualInterfaceKind::Ip6Tnl(ip6tnl(Some(64), Ip6TunnelFlags::default()));
+        assert!(satisfied(&spec, &reported));
+    }
+
+    #[test]
+    fn ip6gre_unset_hop_limit_matches_inherit() {
+        let reported = VirtualInterfaceKind::Ip6Gre(gre6(None, inherited()));
+        let spec = VirtualInterfaceKind::Ip6Gre(gre6(None, Ip6TunnelFlags::default()));
+        assert!(satisfied(&spec, &reported));
+
+        let reported = VirtualInterfaceKind::Ip6Gretap(gre6(None, inherited()));
+        let spec = VirtualInterfaceKind::Ip6Gretap(gre6(Some(255), Ip6TunnelFlags::default()));
+        assert!(!satisfied(&spec, &reported));
+    }
+
+    #[test]
+    fn sit_default_6rd_prefix_matches() {
+        let reported = VirtualInterfaceKind::Sit(sit(Some(sit_6to4_prefix())));
+        assert!(satisfied(&VirtualInterfaceKind::Sit(sit(None)), &reported));
+
+        let prefix = "2001:db8::/32".parse().unwrap();
+        let spec = VirtualInterfaceKind::Sit(sit(Some(prefix)));
+        assert!(!satisfied(&spec, &reported));
+
+        let reported = VirtualInterfaceKind::Sit(sit(Some(prefix)));
+        assert!(satisfied(&spec, &reported));
+        assert!(!satisfied(&VirtualInterfaceKind::Sit(sit(None)), &reported));
+    }
+
+    #[test]
+    fn gre_same_key_both_ways_matches() {
+        let reported = VirtualInterfaceKind::Gre(gre(Some(5), None, None));
+        let spec = VirtualInterfaceKind::Gre(gre(None, Some(5), Some(5)));
+        assert!(satisfied(&spec, &reported));
+        let spec = VirtualInterfaceKind::Gre(gre(Some(5), None, Some(5)));
+        assert!(satisfied(&spec, &reported));
+
+        let reported = VirtualInterfaceKind::Gretap(gre(None, Some(6), Some(5)));
+        let spec = VirtualInterfaceKind::Gretap(gre(Some(5), Some(6), None));
+        assert!(satisfied(&spec, &reported));
+        let spec = VirtualInterfaceKind::Gretap(gre(Some(5), None, None));
+        assert!(!satisfied(&spec, &reported));
+    }
+
+    #[test]
+    fn geneve_round_trip() {
+        let mut cfg = GeneveConfig::new(100);
+        cfg.remote = Some("2001:db8::2".parse().unwrap());
+        cfg.ttl = Some(64);
+        cfg.dstport = Some(6081);
+        assert_eq!(decode_geneve(&geneve_infos(&cfg)), cfg);
+
+        let mut external = GeneveConfig::new(0);
+        external.external = true;
+        assert_eq!(geneve_infos(&external), vec![InfoGeneve::CollectMetadata]);
+        assert_eq!(decode_geneve(&geneve_infos(&external)), external);
+    }
+}